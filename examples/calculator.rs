@@ -59,7 +59,7 @@ impl Builder {
                     ab.label(n)
                         .shape(Shape::Circle)
                         .style(NodeStyle::Filled)
-                        .color(Color::LightGrey)
+                        .color(Color::x11("lightgrey").unwrap())
                 });
                 if let Some(id) = parent_id {
                     self.builder
@@ -69,7 +69,9 @@ impl Builder {
             Expr::Add(l, r) => {
                 let entry_id = self.next_id();
                 self.builder.node(entry_id, |ab| {
-                    ab.label("+").color(Color::Blue).shape(Shape::Box)
+                    ab.label("+")
+                        .color(Color::x11("blue").unwrap())
+                        .shape(Shape::Box)
                 });
                 if let Some(id) = parent_id {
                     self.builder.edge_(entry_id, id);
@@ -80,7 +82,9 @@ impl Builder {
             Expr::Sub(l, r) => {
                 let entry_id = self.next_id();
                 self.builder.node(entry_id, |ab| {
-                    ab.label("-").color(Color::Red).shape(Shape::Diamond)
+                    ab.label("-")
+                        .color(Color::x11("red").unwrap())
+                        .shape(Shape::Diamond)
                 });
                 if let Some(id) = parent_id {
                     self.builder.edge_(entry_id, id);
@@ -1,4 +1,4 @@
-use graphe::{directed, print_graph, Graph};
+use graphe::{directed, print_graph, render, Graph};
 
 type NodeId<'a> = &'a str;
 struct Node<'a> {
@@ -9,7 +9,7 @@ struct Node<'a> {
 type AdjacencyList<'a> = Vec<(Node<'a>, Vec<NodeId<'a>>)>;
 
 fn to_graph(aj: &AdjacencyList) -> Graph {
-    let mut builder = directed().dot();
+    let mut builder = directed().dot().svg();
     for (node, edges) in aj {
         builder.node(node.id, |b| b.label(node.name));
         for edge in edges {
@@ -51,8 +51,16 @@ fn main() {
 
     let graph = to_graph(&aj);
 
-    let mut writer = Vec::new();
-    print_graph(&mut writer, &graph).unwrap();
-    let s = std::str::from_utf8(&writer).unwrap();
-    println!("{}", s)
+    // Rendering to SVG needs graphviz, so by default the example prints DOT
+    if std::env::args().any(|arg| arg == "--render") {
+        match render(&graph) {
+            Ok(svg) => println!("{}", String::from_utf8_lossy(&svg)),
+            Err(e) => eprintln!("{}", e),
+        }
+    } else {
+        let mut writer = Vec::new();
+        print_graph(&mut writer, &graph).unwrap();
+        let s = std::str::from_utf8(&writer).unwrap();
+        println!("{}", s)
+    }
 }
@@ -1,5 +1,12 @@
-use crate::attributes::{Color, Label, RankDir, Shape, Size, Style};
+use std::fmt::Display;
 
+use crate::attributes::{
+    Arrow, ColorList, ColorScheme, Dir, FontNames, Intervals, Label, Overlap, PackMode, PageDir,
+    Position, Rank, RankDir, Shape, Size, Start, Style,
+};
+use crate::escape::escape_text;
+
+#[derive(Clone, Copy)]
 pub(crate) enum GraphType {
     Directed,
     Undirected,
@@ -8,8 +15,11 @@ pub(crate) enum GraphType {
 pub struct Graph {
     pub(crate) strict: bool,
     pub(crate) gtype: GraphType,
-    pub(crate) id: Option<String>,
+    pub(crate) id: Option<Id>,
     pub(crate) statements: Vec<Statement>,
+    // The layout engine and output format the graph was built for, if any
+    pub(crate) engine: Option<&'static str>,
+    pub(crate) format: Option<&'static str>,
 }
 
 pub(crate) enum AttributeScope {
@@ -25,8 +35,13 @@ pub(crate) enum Statement {
         attributes: Vec<Attribute>,
     },
     Edge {
-        from: Id,
-        to: Id,
+        from: NodeId,
+        to: NodeId,
+        attributes: Vec<Attribute>,
+    },
+    // a -> b -> c or {a b} -> {c d}, with every edge sharing the attributes
+    EdgeChain {
+        targets: Vec<EdgeTarget>,
         attributes: Vec<Attribute>,
     },
     Subgraph {
@@ -35,15 +50,214 @@ pub(crate) enum Statement {
     },
 }
 
-pub struct Id(pub(crate) String);
+pub(crate) enum EdgeTarget {
+    Node(NodeId),
+    // Stands for every node in the subgraph
+    Subgraph {
+        id: Option<Id>,
+        statements: Vec<Statement>,
+    },
+}
+
+impl EdgeTarget {
+    // The nodes that edges to or from this target connect
+    pub(crate) fn nodes(&self) -> Vec<NodeId> {
+        match self {
+            EdgeTarget::Node(node) => vec![node.clone()],
+            EdgeTarget::Subgraph { statements, .. } => {
+                let mut nodes = Vec::new();
+                collect_nodes(statements, &mut nodes);
+                nodes
+            }
+        }
+    }
+}
+
+fn collect_nodes(statements: &[Statement], nodes: &mut Vec<NodeId>) {
+    for statement in statements {
+        match statement {
+            Statement::Node { id, .. } => push_node(nodes, id),
+            Statement::Edge { from, to, .. } => {
+                push_node(nodes, &from.id);
+                push_node(nodes, &to.id);
+            }
+            Statement::EdgeChain { targets, .. } => {
+                for node in targets.iter().flat_map(EdgeTarget::nodes) {
+                    push_node(nodes, &node.id);
+                }
+            }
+            Statement::Subgraph { statements, .. } => collect_nodes(statements, nodes),
+            Statement::Attribute(..) => {}
+        }
+    }
+}
+
+// A node in a subgraph endpoint is joined as a whole, without its ports
+fn push_node(nodes: &mut Vec<NodeId>, id: &Id) {
+    if !nodes.iter().any(|n| &n.id == id) {
+        nodes.push(id.clone().into());
+    }
+}
+
+// Every edge a chain stands for, in the order graphviz creates them
+pub(crate) fn edge_pairs(targets: &[EdgeTarget]) -> Vec<(NodeId, NodeId)> {
+    let mut pairs = Vec::new();
+    for window in targets.windows(2) {
+        let heads = window[1].nodes();
+        for from in window[0].nodes() {
+            for to in &heads {
+                pairs.push((from.clone(), to.clone()));
+            }
+        }
+    }
+    pairs
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Id {
+    pub(crate) value: String,
+    pub(crate) html: bool,
+}
 
 impl Id {
     pub fn new<T: ToString>(s: T) -> Self {
-        Id(s.to_string())
+        Id {
+            value: escape_text(&s.to_string()),
+            html: false,
+        }
+    }
+
+    // An Id whose value is already in DOT form, e.g. read by the parser
+    pub(crate) fn from_dot<T: ToString>(s: T) -> Self {
+        Id {
+            value: s.to_string(),
+            html: false,
+        }
+    }
+
+    pub(crate) fn html<T: ToString>(s: T) -> Self {
+        Id {
+            value: s.to_string(),
+            html: true,
+        }
     }
 
     pub(crate) fn get(&self) -> &str {
-        self.0.as_str()
+        self.value.as_str()
+    }
+}
+
+// https://graphviz.org/docs/attr-types/portPos/
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Compass {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+    Center,
+    // Any side, chosen by the layout
+    Any,
+}
+
+impl Compass {
+    pub(crate) fn parse(s: &str) -> Option<Compass> {
+        match s {
+            "n" => Some(Compass::North),
+            "ne" => Some(Compass::NorthEast),
+            "e" => Some(Compass::East),
+            "se" => Some(Compass::SouthEast),
+            "s" => Some(Compass::South),
+            "sw" => Some(Compass::SouthWest),
+            "w" => Some(Compass::West),
+            "nw" => Some(Compass::NorthWest),
+            "c" => Some(Compass::Center),
+            "_" => Some(Compass::Any),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Compass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Compass::North => "n",
+            Compass::NorthEast => "ne",
+            Compass::East => "e",
+            Compass::SouthEast => "se",
+            Compass::South => "s",
+            Compass::SouthWest => "sw",
+            Compass::West => "w",
+            Compass::NorthWest => "nw",
+            Compass::Center => "c",
+            Compass::Any => "_",
+        };
+        f.write_str(s)
+    }
+}
+
+// https://graphviz.org/doc/info/lang.html
+// node_id : ID [ port ]
+// port    : ':' ID [ ':' compass_pt ] | ':' compass_pt
+#[derive(Clone)]
+pub struct NodeId {
+    pub(crate) id: Id,
+    pub(crate) port: Option<Id>,
+    pub(crate) compass: Option<Compass>,
+}
+
+impl NodeId {
+    pub fn new<T: ToId>(id: T) -> Self {
+        id.to_id().into()
+    }
+
+    pub fn port<P: ToId>(mut self, port: P) -> Self {
+        self.port = Some(port.to_id());
+        self
+    }
+
+    pub fn compass(mut self, compass: Compass) -> Self {
+        self.compass = Some(compass);
+        self
+    }
+
+    // The port and compass point in DOT form, e.g. `p:n`
+    pub(crate) fn port_text(&self) -> Option<String> {
+        match (&self.port, &self.compass) {
+            (Some(port), Some(compass)) => Some(format!("{}:{}", port.get(), compass)),
+            (Some(port), None) => Some(port.get().to_string()),
+            (None, Some(compass)) => Some(compass.to_string()),
+            (None, None) => None,
+        }
+    }
+
+    // Reads `port_text` back. A port is a compass point when it reads as
+    // one, like in DOT.
+    pub(crate) fn with_port_text(mut self, text: &str) -> Self {
+        match text.split_once(':') {
+            Some((port, compass)) => {
+                self.port = Some(Id::from_dot(port));
+                self.compass = Compass::parse(compass);
+            }
+            None => match Compass::parse(text) {
+                Some(compass) => self.compass = Some(compass),
+                None => self.port = Some(Id::from_dot(text)),
+            },
+        }
+        self
+    }
+}
+
+impl From<Id> for NodeId {
+    fn from(id: Id) -> Self {
+        NodeId {
+            id,
+            port: None,
+            compass: None,
+        }
     }
 }
 
@@ -51,48 +265,246 @@ pub trait ToId {
     fn to_id(&self) -> Id;
 }
 
+// Edge endpoints: a node, `(node, port)` for a port on a record or HTML label,
+// `(node, compass)` for a side of the node or `(node, port, compass)` for a
+// side of a port.
+pub trait ToNodeId {
+    fn to_node_id(&self) -> NodeId;
+}
+
+impl ToNodeId for NodeId {
+    fn to_node_id(&self) -> NodeId {
+        self.clone()
+    }
+}
+
+impl<T: ToId> ToNodeId for T {
+    fn to_node_id(&self) -> NodeId {
+        self.to_id().into()
+    }
+}
+
+impl<T: ToId, P: ToId> ToNodeId for (T, P) {
+    fn to_node_id(&self) -> NodeId {
+        NodeId::new(&self.0).port(&self.1)
+    }
+}
+
+impl<T: ToId> ToNodeId for (T, Compass) {
+    fn to_node_id(&self) -> NodeId {
+        NodeId::new(&self.0).compass(self.1)
+    }
+}
+
+impl<T: ToId, P: ToId> ToNodeId for (T, P, Compass) {
+    fn to_node_id(&self) -> NodeId {
+        NodeId::new(&self.0).port(&self.1).compass(self.2)
+    }
+}
+
 impl ToId for Id {
     fn to_id(&self) -> Id {
-        Id(self.get().to_owned())
+        self.clone()
+    }
+}
+
+impl<T: ToId + ?Sized> ToId for &T {
+    fn to_id(&self) -> Id {
+        (*self).to_id()
     }
 }
 
 impl ToId for u8 {
     fn to_id(&self) -> Id {
-        Id(self.to_string())
+        Id::new(self)
     }
 }
 
 impl ToId for u16 {
     fn to_id(&self) -> Id {
-        Id(self.to_string())
+        Id::new(self)
     }
 }
 
 impl ToId for u32 {
     fn to_id(&self) -> Id {
-        Id(self.to_string())
+        Id::new(self)
     }
 }
 
 impl ToId for u64 {
     fn to_id(&self) -> Id {
-        Id(self.to_string())
+        Id::new(self)
     }
 }
 
 impl ToId for &str {
     fn to_id(&self) -> Id {
-        Id(self.to_owned().to_string())
+        Id::new(self)
     }
 }
 
+#[derive(Clone)]
 pub(crate) enum Attribute {
-    Color(Color),
+    ArrowHead(Arrow),
+    ArrowSize(f64),
+    ArrowTail(Arrow),
+    Area(f64),
+    BgColor(ColorList),
+    Class(Id),
+    Color(ColorList),
+    ColorScheme(ColorScheme),
+    Dir(Dir),
+    Dpi(f64),
+    FillColor(ColorList),
+    FontNames(FontNames),
+    FontPath(Id),
+    Href(Id),
+    Id(Id),
+    // When a node or edge exists, for GEXF
+    Intervals(Intervals),
+    K(f64),
     Label(Label),
     Length(f64),
+    MinDist(f64),
+    Overlap(Overlap),
+    PackMode(PackMode),
+    Page(Size),
+    PageDir(PageDir),
+    Pin(bool),
+    Pos(Position),
+    Rank(Rank),
     RankDir(RankDir),
+    RankSep(f64),
+    RepulsiveForce(f64),
+    Root(Id),
     Style(Style),
     Shape(Shape),
     Size(Size),
+    Start(Start),
+    Stylesheet(Id),
+    Target(Id),
+    Tooltip(Id),
+    Url(Id),
+    // Polygon parameters, see `PolygonBuilder`
+    Sides(u32),
+    Skew(f64),
+    Distortion(f64),
+    Orientation(f64),
+    Regular(bool),
+    Peripheries(u32),
+    // Attributes that graphe has no typed representation for, kept verbatim
+    // so that parsed graphs can be printed again.
+    Other(Id, Id),
+}
+
+impl Attribute {
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Attribute::ArrowHead(_) => "arrowhead",
+            Attribute::ArrowSize(_) => "arrowsize",
+            Attribute::ArrowTail(_) => "arrowtail",
+            Attribute::Area(_) => "area",
+            Attribute::BgColor(_) => "bgcolor",
+            Attribute::Class(_) => "class",
+            Attribute::Color(_) => "color",
+            Attribute::ColorScheme(_) => "colorscheme",
+            Attribute::Dir(_) => "dir",
+            Attribute::Dpi(_) => "dpi",
+            Attribute::FillColor(_) => "fillcolor",
+            Attribute::FontNames(_) => "fontnames",
+            Attribute::FontPath(_) => "fontpath",
+            Attribute::Href(_) => "href",
+            Attribute::Id(_) => "id",
+            Attribute::Intervals(_) => "intervals",
+            Attribute::K(_) => "K",
+            Attribute::Label(_) => "label",
+            Attribute::Length(_) => "len",
+            Attribute::MinDist(_) => "mindist",
+            Attribute::Overlap(_) => "overlap",
+            Attribute::PackMode(_) => "packmode",
+            Attribute::Page(_) => "page",
+            Attribute::PageDir(_) => "pagedir",
+            Attribute::Pin(_) => "pin",
+            Attribute::Pos(_) => "pos",
+            Attribute::Rank(_) => "rank",
+            Attribute::RankDir(_) => "rankdir",
+            Attribute::RankSep(_) => "ranksep",
+            Attribute::RepulsiveForce(_) => "repulsiveforce",
+            Attribute::Root(_) => "root",
+            Attribute::Style(_) => "style",
+            Attribute::Shape(_) => "shape",
+            Attribute::Size(_) => "size",
+            Attribute::Start(_) => "start",
+            Attribute::Stylesheet(_) => "stylesheet",
+            Attribute::Target(_) => "target",
+            Attribute::Tooltip(_) => "tooltip",
+            Attribute::Url(_) => "URL",
+            Attribute::Sides(_) => "sides",
+            Attribute::Skew(_) => "skew",
+            Attribute::Distortion(_) => "distortion",
+            Attribute::Orientation(_) => "orientation",
+            Attribute::Regular(_) => "regular",
+            Attribute::Peripheries(_) => "peripheries",
+            Attribute::Other(name, _) => name.get(),
+        }
+    }
+
+    // The value as graphviz reads it, without DOT quoting
+    pub(crate) fn value(&self) -> String {
+        match self {
+            Attribute::ArrowHead(a) | Attribute::ArrowTail(a) => a.to_string(),
+            Attribute::Area(f)
+            | Attribute::ArrowSize(f)
+            | Attribute::Distortion(f)
+            | Attribute::Dpi(f)
+            | Attribute::K(f)
+            | Attribute::Length(f)
+            | Attribute::MinDist(f)
+            | Attribute::Orientation(f)
+            | Attribute::RankSep(f)
+            | Attribute::RepulsiveForce(f)
+            | Attribute::Skew(f) => f.to_string(),
+            Attribute::BgColor(c) | Attribute::Color(c) | Attribute::FillColor(c) => c.to_string(),
+            Attribute::ColorScheme(s) => s.to_string(),
+            Attribute::Dir(d) => d.to_string(),
+            Attribute::FontNames(n) => n.to_string(),
+            Attribute::Label(Label::Text(l)) => l.clone(),
+            Attribute::Label(Label::Html(h)) => h.get().to_string(),
+            Attribute::Label(Label::Record(r)) => r.get().to_string(),
+            Attribute::Overlap(o) => o.to_string(),
+            Attribute::PackMode(p) => p.to_string(),
+            // Sizes print themselves quoted
+            Attribute::Page(s) | Attribute::Size(s) => s.to_string().trim_matches('"').to_string(),
+            Attribute::PageDir(d) => d.to_string(),
+            Attribute::Peripheries(n) | Attribute::Sides(n) => n.to_string(),
+            Attribute::Pos(p) => p.to_string(),
+            Attribute::Intervals(i) => i.to_string(),
+            Attribute::Rank(r) => r.to_string(),
+            Attribute::RankDir(r) => r.to_string(),
+            Attribute::Pin(b) | Attribute::Regular(b) => b.to_string(),
+            Attribute::Shape(s) => s.to_string(),
+            Attribute::Start(s) => s.to_string(),
+            Attribute::Style(s) => s.to_string(),
+            Attribute::Class(v)
+            | Attribute::FontPath(v)
+            | Attribute::Href(v)
+            | Attribute::Id(v)
+            | Attribute::Root(v)
+            | Attribute::Stylesheet(v)
+            | Attribute::Target(v)
+            | Attribute::Tooltip(v)
+            | Attribute::Url(v)
+            | Attribute::Other(_, v) => v.get().to_string(),
+        }
+    }
+
+    // Whether the value is HTML-like, i.e. written in angle brackets in DOT
+    pub(crate) fn is_html(&self) -> bool {
+        match self {
+            Attribute::Label(Label::Html(_)) => true,
+            Attribute::Other(_, value) => value.html,
+            _ => false,
+        }
+    }
 }
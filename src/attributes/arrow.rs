@@ -0,0 +1,332 @@
+use std::error::Error;
+use std::fmt::Display;
+
+// docs
+// * https://graphviz.org/doc/info/arrows.html
+// * https://graphviz.org/docs/attr-types/arrowType/
+//
+// arrowname : ( [ 'o' ] [ 'l' | 'r' ] shape ){1,4}
+//
+// Not every modifier suits every shape: only the filled shapes have an open
+// version, and dot and none cannot be clipped to one side. The builder takes
+// each shape with exactly the modifiers it allows.
+
+const MAX_SHAPES: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArrowError {
+    Empty,
+    TooManyShapes(usize),
+}
+
+impl Display for ArrowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArrowError::Empty => f.write_str("an arrow needs at least one shape"),
+            ArrowError::TooManyShapes(n) => write!(
+                f,
+                "an arrow stacks at most {} shapes, not {}",
+                MAX_SHAPES, n
+            ),
+        }
+    }
+}
+
+impl Error for ArrowError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fill {
+    Filled,
+    Open,
+}
+
+// Which half of the shape is drawn, as seen looking along the edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Both,
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ArrowShape {
+    Box,
+    Crow,
+    Curve,
+    ICurve,
+    Diamond,
+    Dot,
+    Inv,
+    None,
+    Normal,
+    Tee,
+    Vee,
+}
+
+const SHAPES: [(ArrowShape, &str); 11] = [
+    (ArrowShape::Box, "box"),
+    (ArrowShape::Crow, "crow"),
+    (ArrowShape::Curve, "curve"),
+    (ArrowShape::ICurve, "icurve"),
+    (ArrowShape::Diamond, "diamond"),
+    (ArrowShape::Dot, "dot"),
+    (ArrowShape::Inv, "inv"),
+    (ArrowShape::None, "none"),
+    (ArrowShape::Normal, "normal"),
+    (ArrowShape::Tee, "tee"),
+    (ArrowShape::Vee, "vee"),
+];
+
+impl ArrowShape {
+    fn name(&self) -> &'static str {
+        SHAPES.iter().find(|(shape, _)| shape == self).unwrap().1
+    }
+
+    fn has_open(&self) -> bool {
+        matches!(
+            self,
+            ArrowShape::Box
+                | ArrowShape::Diamond
+                | ArrowShape::Dot
+                | ArrowShape::Inv
+                | ArrowShape::Normal
+        )
+    }
+
+    fn has_sides(&self) -> bool {
+        !matches!(self, ArrowShape::Dot | ArrowShape::None)
+    }
+
+    // Length along the edge relative to a normal arrowhead
+    pub(crate) fn length(&self) -> f64 {
+        match self {
+            ArrowShape::Tee => 0.5,
+            ArrowShape::Dot => 0.8,
+            ArrowShape::Diamond => 1.2,
+            _ => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Part {
+    pub(crate) fill: Fill,
+    pub(crate) side: Side,
+    pub(crate) shape: ArrowShape,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arrow {
+    parts: Vec<Part>,
+}
+
+impl Arrow {
+    // Shapes are listed from the node outwards, e.g.
+    // `Arrow::new(|a| a.tee(Side::Left).diamond(Fill::Open, Side::Left))`
+    // for `lteeoldiamond`
+    pub fn new<F>(f: F) -> Result<Arrow, ArrowError>
+    where
+        F: FnOnce(&mut ArrowBuilder) -> &mut ArrowBuilder,
+    {
+        let mut builder = ArrowBuilder { parts: Vec::new() };
+        f(&mut builder);
+        match builder.parts.len() {
+            0 => Err(ArrowError::Empty),
+            n if n > MAX_SHAPES => Err(ArrowError::TooManyShapes(n)),
+            _ => Ok(Arrow {
+                parts: builder.parts,
+            }),
+        }
+    }
+
+    pub(crate) fn parse(s: &str) -> Option<Arrow> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+        while !rest.is_empty() {
+            // Shape names never start with `o`, `l` or `r`, so a leading one
+            // is always a modifier
+            let (fill, after_fill) = match rest.strip_prefix('o') {
+                Some(after) => (Fill::Open, after),
+                None => (Fill::Filled, rest),
+            };
+            let (side, after_side) = match after_fill.as_bytes().first() {
+                Some(b'l') => (Side::Left, &after_fill[1..]),
+                Some(b'r') => (Side::Right, &after_fill[1..]),
+                _ => (Side::Both, after_fill),
+            };
+            let (shape, name) = SHAPES
+                .iter()
+                .find(|(_, name)| after_side.starts_with(name))?;
+            if (fill == Fill::Open && !shape.has_open())
+                || (side != Side::Both && !shape.has_sides())
+            {
+                return None;
+            }
+            parts.push(Part {
+                fill,
+                side,
+                shape: *shape,
+            });
+            rest = &after_side[name.len()..];
+        }
+        if parts.is_empty() || parts.len() > MAX_SHAPES {
+            return None;
+        }
+        Some(Arrow { parts })
+    }
+
+    // From the node outwards
+    pub(crate) fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+}
+
+impl Display for Arrow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for part in &self.parts {
+            if part.fill == Fill::Open {
+                f.write_str("o")?;
+            }
+            match part.side {
+                Side::Both => {}
+                Side::Left => f.write_str("l")?,
+                Side::Right => f.write_str("r")?,
+            }
+            f.write_str(part.shape.name())?;
+        }
+        Ok(())
+    }
+}
+
+pub struct ArrowBuilder {
+    parts: Vec<Part>,
+}
+
+impl ArrowBuilder {
+    fn part(&mut self, fill: Fill, side: Side, shape: ArrowShape) -> &mut ArrowBuilder {
+        self.parts.push(Part { fill, side, shape });
+        self
+    }
+
+    pub fn normal(&mut self, fill: Fill, side: Side) -> &mut ArrowBuilder {
+        self.part(fill, side, ArrowShape::Normal)
+    }
+
+    pub fn inv(&mut self, fill: Fill, side: Side) -> &mut ArrowBuilder {
+        self.part(fill, side, ArrowShape::Inv)
+    }
+
+    pub fn box_(&mut self, fill: Fill, side: Side) -> &mut ArrowBuilder {
+        self.part(fill, side, ArrowShape::Box)
+    }
+
+    pub fn diamond(&mut self, fill: Fill, side: Side) -> &mut ArrowBuilder {
+        self.part(fill, side, ArrowShape::Diamond)
+    }
+
+    pub fn dot(&mut self, fill: Fill) -> &mut ArrowBuilder {
+        self.part(fill, Side::Both, ArrowShape::Dot)
+    }
+
+    pub fn crow(&mut self, side: Side) -> &mut ArrowBuilder {
+        self.part(Fill::Filled, side, ArrowShape::Crow)
+    }
+
+    pub fn curve(&mut self, side: Side) -> &mut ArrowBuilder {
+        self.part(Fill::Filled, side, ArrowShape::Curve)
+    }
+
+    pub fn icurve(&mut self, side: Side) -> &mut ArrowBuilder {
+        self.part(Fill::Filled, side, ArrowShape::ICurve)
+    }
+
+    pub fn tee(&mut self, side: Side) -> &mut ArrowBuilder {
+        self.part(Fill::Filled, side, ArrowShape::Tee)
+    }
+
+    pub fn vee(&mut self, side: Side) -> &mut ArrowBuilder {
+        self.part(Fill::Filled, side, ArrowShape::Vee)
+    }
+
+    // No arrow at all, or a gap between stacked shapes
+    pub fn none(&mut self) -> &mut ArrowBuilder {
+        self.part(Fill::Filled, Side::Both, ArrowShape::None)
+    }
+}
+
+// docs
+// * https://graphviz.org/docs/attrs/dir/
+#[derive(Clone)]
+pub enum Dir {
+    Forward,
+    Back,
+    Both,
+    None,
+}
+
+impl Dir {
+    pub(crate) fn parse(s: &str) -> Option<Dir> {
+        match s {
+            "forward" => Some(Dir::Forward),
+            "back" => Some(Dir::Back),
+            "both" => Some(Dir::Both),
+            "none" => Some(Dir::None),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Dir {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Dir::Forward => "forward",
+            Dir::Back => "back",
+            Dir::Both => "both",
+            Dir::None => "none",
+        };
+        f.write_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrows() {
+        let arrow = Arrow::new(|a| a.tee(Side::Left).diamond(Fill::Open, Side::Left)).unwrap();
+        assert_eq!(arrow.to_string(), "lteeoldiamond");
+        assert_eq!(Arrow::parse("lteeoldiamond"), Some(arrow));
+
+        let arrow = Arrow::new(|a| a.dot(Fill::Open).none().icurve(Side::Right)).unwrap();
+        assert_eq!(arrow.to_string(), "odotnonericurve");
+        assert_eq!(Arrow::parse("odotnonericurve"), Some(arrow));
+
+        assert_eq!(Arrow::new(|a| a).err(), Some(ArrowError::Empty));
+        assert_eq!(
+            Arrow::new(|a| a
+                .vee(Side::Both)
+                .vee(Side::Both)
+                .vee(Side::Both)
+                .vee(Side::Both)
+                .vee(Side::Both))
+            .err(),
+            Some(ArrowError::TooManyShapes(5))
+        );
+    }
+
+    #[test]
+    fn invalid_arrows() {
+        for s in [
+            "",
+            "otee",
+            "ldot",
+            "rnone",
+            "teeteeteeteetee",
+            "normalx",
+            "ediamond",
+        ] {
+            assert_eq!(Arrow::parse(s), None, "{}", s);
+        }
+    }
+}
@@ -1,20 +1,436 @@
+use std::error::Error;
 use std::fmt::Display;
 
-pub enum Color {
-    Blue,
-    LightGrey,
-    Red,
-    White,
+use crate::attributes::color_names::{SVG, X11};
+use crate::attributes::{BrewerPalette, ColorScheme};
+
+// docs
+// * https://graphviz.org/docs/attr-types/color/
+// * https://graphviz.org/docs/attr-types/colorList/
+// * https://graphviz.org/doc/info/colors.html
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorError {
+    InvalidHex(String),
+    // Hue, saturation and value all lie in [0, 1]
+    HsvOutOfRange(f64),
+    UnknownName(String),
+    EmptyList,
+    InvalidWeight(f64),
+    // The weights of a color list add up to more than 1
+    WeightsExceedOne,
+    // The palette does not come in that many colors
+    PaletteSize(BrewerPalette, u8),
+    // Only Brewer schemes have numbered colors
+    NotIndexed(ColorScheme),
+    IndexOutOfRange { index: u8, size: u8 },
+}
+
+impl Display for ColorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorError::InvalidHex(hex) => {
+                write!(f, "'{}' is not a #rrggbb or #rrggbbaa color", hex)
+            }
+            ColorError::HsvOutOfRange(v) => {
+                write!(f, "HSV component {} is outside of [0, 1]", v)
+            }
+            ColorError::UnknownName(name) => write!(f, "unknown color name '{}'", name),
+            ColorError::EmptyList => f.write_str("a color list needs at least one color"),
+            ColorError::InvalidWeight(w) => {
+                write!(f, "color weight {} is outside of [0, 1]", w)
+            }
+            ColorError::WeightsExceedOne => f.write_str("color weights add up to more than 1"),
+            ColorError::PaletteSize(palette, size) => write!(
+                f,
+                "{:?} comes in {:?} colors, not {}",
+                palette,
+                palette.sizes(),
+                size
+            ),
+            ColorError::NotIndexed(scheme) => {
+                write!(f, "colors of the {} scheme are not numbered", scheme)
+            }
+            ColorError::IndexOutOfRange { index, size } => {
+                write!(f, "color index {} is outside of 1..={}", index, size)
+            }
+        }
+    }
+}
+
+impl Error for ColorError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Color(ColorValue);
+
+#[derive(Debug, Clone, PartialEq)]
+enum ColorValue {
+    Rgb([u8; 3]),
+    Rgba([u8; 4]),
+    Hsv(f64, f64, f64),
+    X11(&'static str),
+    Svg(&'static str),
+    // A name read without a scheme. Graphviz looks it up in the colorscheme
+    // in effect, so it is printed back as it was written.
+    Name(&'static str),
+    // The index is 1-based and within the scheme's size
+    Indexed(ColorScheme, u8),
+    Transparent,
+}
+
+impl Color {
+    pub fn rgb(red: u8, green: u8, blue: u8) -> Color {
+        Color(ColorValue::Rgb([red, green, blue]))
+    }
+
+    pub fn rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Color {
+        Color(ColorValue::Rgba([red, green, blue, alpha]))
+    }
+
+    // No color at all, e.g. for a background that shows what lies behind
+    // the image
+    pub fn transparent() -> Color {
+        Color(ColorValue::Transparent)
+    }
+
+    // "#rrggbb" or "#rrggbbaa"
+    pub fn hex(hex: &str) -> Result<Color, ColorError> {
+        let invalid = || ColorError::InvalidHex(hex.to_string());
+        let digits = hex.strip_prefix('#').ok_or_else(invalid)?;
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).unwrap();
+        match digits.len() {
+            6 => Ok(Color::rgb(channel(0), channel(2), channel(4))),
+            8 => Ok(Color::rgba(channel(0), channel(2), channel(4), channel(6))),
+            _ => Err(invalid()),
+        }
+    }
+
+    pub fn hsv(hue: f64, saturation: f64, value: f64) -> Result<Color, ColorError> {
+        for component in [hue, saturation, value] {
+            if !(0.0..=1.0).contains(&component) {
+                return Err(ColorError::HsvOutOfRange(component));
+            }
+        }
+        Ok(Color(ColorValue::Hsv(hue, saturation, value)))
+    }
+
+    // A name from the X11 scheme, graphviz's default. Names are case
+    // insensitive.
+    pub fn x11(name: &str) -> Result<Color, ColorError> {
+        lookup(&X11, name).map(|name| Color(ColorValue::X11(name)))
+    }
+
+    // A name from the SVG scheme, which differs from X11 for a few colors
+    // such as gray, green and purple
+    pub fn svg(name: &str) -> Result<Color, ColorError> {
+        lookup(&SVG, name).map(|name| Color(ColorValue::Svg(name)))
+    }
+
+    // The `index`th color of a Brewer scheme, e.g. `/blues9/3`
+    pub fn indexed(scheme: ColorScheme, index: u8) -> Result<Color, ColorError> {
+        match scheme {
+            ColorScheme::Brewer { size, .. } if (1..=size).contains(&index) => {
+                Ok(Color(ColorValue::Indexed(scheme, index)))
+            }
+            ColorScheme::Brewer { size, .. } => Err(ColorError::IndexOutOfRange { index, size }),
+            _ => Err(ColorError::NotIndexed(scheme)),
+        }
+    }
+
+    pub(crate) fn parse(s: &str) -> Option<Color> {
+        if s.eq_ignore_ascii_case("transparent") {
+            return Some(Color::transparent());
+        }
+        if s.starts_with('#') {
+            return Color::hex(s).ok();
+        }
+        if let Some((scheme, name)) = s.strip_prefix('/').and_then(|s| s.split_once('/')) {
+            return match ColorScheme::parse(scheme)? {
+                ColorScheme::X11 => Color::x11(name).ok(),
+                ColorScheme::Svg => Color::svg(name).ok(),
+                scheme => Color::indexed(scheme, name.parse().ok()?).ok(),
+            };
+        }
+        if s.starts_with(|c: char| c == '.' || c.is_ascii_digit()) {
+            let components: Vec<f64> = s
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|c| !c.is_empty())
+                .map(|c| c.parse().ok())
+                .collect::<Option<_>>()?;
+            return match components[..] {
+                [h, s, v] => Color::hsv(h, s, v).ok(),
+                _ => None,
+            };
+        }
+        lookup(&X11, s)
+            .ok()
+            .map(|name| Color(ColorValue::Name(name)))
+    }
+    // Red, green, blue and alpha, for drawing without graphviz. Brewer
+    // schemes have no color values here.
+    pub(crate) fn channels(&self) -> Option<[u8; 4]> {
+        let named = |table: &[(&'static str, [u8; 3])], name: &str| {
+            let i = table.binary_search_by(|(n, _)| n.cmp(&name)).ok()?;
+            let [r, g, b] = table[i].1;
+            Some([r, g, b, 255])
+        };
+        match &self.0 {
+            ColorValue::Rgb([r, g, b]) => Some([*r, *g, *b, 255]),
+            ColorValue::Rgba(rgba) => Some(*rgba),
+            ColorValue::Hsv(h, s, v) => {
+                let sector = (h * 6.0).floor();
+                let f = h * 6.0 - sector;
+                let (p, q, t) = (v * (1.0 - s), v * (1.0 - s * f), v * (1.0 - s * (1.0 - f)));
+                let (r, g, b) = match sector as u32 % 6 {
+                    0 => (*v, t, p),
+                    1 => (q, *v, p),
+                    2 => (p, *v, t),
+                    3 => (p, q, *v),
+                    4 => (t, p, *v),
+                    _ => (*v, p, q),
+                };
+                let channel = |c: f64| (c * 255.0).round() as u8;
+                Some([channel(r), channel(g), channel(b), 255])
+            }
+            ColorValue::X11(name) | ColorValue::Name(name) => named(&X11, name),
+            ColorValue::Svg(name) => named(&SVG, name),
+            ColorValue::Indexed(..) => None,
+            ColorValue::Transparent => Some([0, 0, 0, 0]),
+        }
+    }
+}
+
+fn lookup(table: &[(&'static str, [u8; 3])], name: &str) -> Result<&'static str, ColorError> {
+    let lowercase = name.to_ascii_lowercase();
+    table
+        .binary_search_by(|(n, _)| n.cmp(&lowercase.as_str()))
+        .map(|i| table[i].0)
+        .map_err(|_| ColorError::UnknownName(name.to_string()))
 }
 
 impl Display for Color {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = match self {
-            Color::Blue => "blue",
-            Color::LightGrey => "lightgrey",
-            Color::Red => "red",
-            Color::White => "white",
+        match &self.0 {
+            ColorValue::Rgb([r, g, b]) => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+            ColorValue::Rgba([r, g, b, a]) => {
+                write!(f, "#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+            }
+            ColorValue::Hsv(h, s, v) => write!(f, "{},{},{}", h, s, v),
+            // Explicit, as a colorscheme would change what a bare name means
+            ColorValue::X11(name) => write!(f, "/x11/{}", name),
+            ColorValue::Svg(name) => write!(f, "/svg/{}", name),
+            ColorValue::Name(name) => f.write_str(name),
+            ColorValue::Indexed(scheme, index) => write!(f, "/{}/{}", scheme, index),
+            ColorValue::Transparent => f.write_str("transparent"),
+        }
+    }
+}
+
+// A list of colors with optional weights, e.g. `red;0.3:blue`. Striped and
+// wedged fills give each color a share of the node; unweighted colors split
+// whatever is left. Edges draw one parallel line per color.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorList {
+    colors: Vec<(Color, Option<f64>)>,
+}
+
+impl ColorList {
+    pub fn new<F>(f: F) -> Result<ColorList, ColorError>
+    where
+        F: FnOnce(&mut ColorListBuilder) -> &mut ColorListBuilder,
+    {
+        let mut builder = ColorListBuilder { colors: Vec::new() };
+        f(&mut builder);
+        ColorList::validate(builder.colors)
+    }
+
+    fn validate(colors: Vec<(Color, Option<f64>)>) -> Result<ColorList, ColorError> {
+        if colors.is_empty() {
+            return Err(ColorError::EmptyList);
+        }
+        let mut total = 0.0;
+        for weight in colors.iter().filter_map(|(_, weight)| *weight) {
+            if !(0.0..=1.0).contains(&weight) {
+                return Err(ColorError::InvalidWeight(weight));
+            }
+            total += weight;
+        }
+        if total > 1.0 + f64::EPSILON {
+            return Err(ColorError::WeightsExceedOne);
+        }
+        Ok(ColorList { colors })
+    }
+
+    pub(crate) fn parse(s: &str) -> Option<ColorList> {
+        let colors = s
+            .split(':')
+            .map(|item| match item.split_once(';') {
+                Some((color, weight)) => Some((Color::parse(color)?, Some(weight.parse().ok()?))),
+                None => Some((Color::parse(item)?, None)),
+            })
+            .collect::<Option<_>>()?;
+        ColorList::validate(colors).ok()
+    }
+
+    // Every color with its share of the whole, unweighted colors splitting
+    // what the weighted ones leave
+    pub(crate) fn shares(&self) -> Vec<(&Color, f64)> {
+        let weighted: f64 = self.colors.iter().filter_map(|(_, w)| *w).sum();
+        let unweighted = self.colors.iter().filter(|(_, w)| w.is_none()).count();
+        let rest = if unweighted > 0 {
+            (1.0 - weighted).max(0.0) / unweighted as f64
+        } else {
+            0.0
         };
-        f.write_str(s)
+        self.colors
+            .iter()
+            .map(|(color, weight)| (color, weight.unwrap_or(rest)))
+            .collect()
+    }
+
+    pub(crate) fn first(&self) -> &Color {
+        &self.colors[0].0
+    }
+}
+
+impl From<Color> for ColorList {
+    fn from(color: Color) -> Self {
+        ColorList {
+            colors: vec![(color, None)],
+        }
+    }
+}
+
+impl Display for ColorList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (color, weight)) in self.colors.iter().enumerate() {
+            if i > 0 {
+                f.write_str(":")?;
+            }
+            write!(f, "{}", color)?;
+            if let Some(weight) = weight {
+                write!(f, ";{}", weight)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct ColorListBuilder {
+    colors: Vec<(Color, Option<f64>)>,
+}
+
+impl ColorListBuilder {
+    pub fn color(&mut self, color: Color) -> &mut ColorListBuilder {
+        self.colors.push((color, None));
+        self
+    }
+
+    // A color taking up `weight` (between 0 and 1) of the fill
+    pub fn weighted(&mut self, color: Color, weight: f64) -> &mut ColorListBuilder {
+        self.colors.push((color, Some(weight)));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors() {
+        assert_eq!(Color::rgb(255, 0, 10).to_string(), "#ff000a");
+        assert_eq!(Color::hex("#FF000A80").unwrap().to_string(), "#ff000a80");
+        assert_eq!(
+            Color::hsv(0.5, 1.0, 0.25).unwrap().to_string(),
+            "0.5,1,0.25"
+        );
+        assert_eq!(
+            Color::x11("LightGrey").unwrap().to_string(),
+            "/x11/lightgrey"
+        );
+        assert_eq!(Color::x11("gray50").unwrap().to_string(), "/x11/gray50");
+        assert_eq!(Color::parse("/x11/gray50"), Color::x11("gray50").ok());
+        assert_eq!(Color::parse("Gray50").unwrap().to_string(), "gray50");
+        assert_eq!(Color::svg("teal").unwrap().to_string(), "/svg/teal");
+        assert_eq!(Color::parse("transparent"), Some(Color::transparent()));
+
+        assert_eq!(
+            Color::hex("ff0000"),
+            Err(ColorError::InvalidHex("ff0000".into()))
+        );
+        assert_eq!(
+            Color::hex("#ff00"),
+            Err(ColorError::InvalidHex("#ff00".into()))
+        );
+        assert_eq!(
+            Color::hex("#gg0000"),
+            Err(ColorError::InvalidHex("#gg0000".into()))
+        );
+        assert_eq!(
+            Color::hsv(0.5, 1.5, 0.0),
+            Err(ColorError::HsvOutOfRange(1.5))
+        );
+        assert_eq!(
+            Color::x11("teal"),
+            Err(ColorError::UnknownName("teal".into()))
+        );
+        assert_eq!(
+            Color::svg("gray50"),
+            Err(ColorError::UnknownName("gray50".into()))
+        );
+    }
+
+    #[test]
+    fn color_lists() {
+        let red = Color::x11("red").unwrap();
+        let blue = Color::x11("blue").unwrap();
+        let list = ColorList::new(|l| l.weighted(red.clone(), 0.3).color(blue.clone())).unwrap();
+        assert_eq!(list.to_string(), "/x11/red;0.3:/x11/blue");
+        assert_eq!(ColorList::parse("/x11/red;0.3:/x11/blue"), Some(list));
+        assert_eq!(
+            ColorList::parse("red;0.3:blue").map(|l| l.to_string()),
+            Some("red;0.3:blue".to_string())
+        );
+        assert_eq!(
+            ColorList::parse("/svg/green:#00ff00;0.5").map(|l| l.to_string()),
+            Some("/svg/green:#00ff00;0.5".to_string())
+        );
+
+        assert_eq!(ColorList::new(|l| l).err(), Some(ColorError::EmptyList));
+        assert_eq!(
+            ColorList::new(|l| l.weighted(red.clone(), -0.5)).err(),
+            Some(ColorError::InvalidWeight(-0.5))
+        );
+        assert_eq!(
+            ColorList::new(|l| l.weighted(red.clone(), 0.6).weighted(blue.clone(), 0.6)).err(),
+            Some(ColorError::WeightsExceedOne)
+        );
+    }
+
+    #[test]
+    fn indexed_colors() {
+        let blues = ColorScheme::brewer(BrewerPalette::Blues, 5).unwrap();
+        let color = Color::indexed(blues, 5).unwrap();
+        assert_eq!(color.to_string(), "/blues5/5");
+        assert_eq!(Color::parse("/blues5/5"), Some(color));
+        assert_eq!(Color::parse("/blues5/6"), None);
+        assert_eq!(Color::parse("/svg/teal"), Color::svg("teal").ok());
+
+        assert_eq!(
+            Color::indexed(blues, 0),
+            Err(ColorError::IndexOutOfRange { index: 0, size: 5 })
+        );
+        assert_eq!(
+            Color::indexed(blues, 6),
+            Err(ColorError::IndexOutOfRange { index: 6, size: 5 })
+        );
+        assert_eq!(
+            Color::indexed(ColorScheme::Svg, 1),
+            Err(ColorError::NotIndexed(ColorScheme::Svg))
+        );
     }
 }
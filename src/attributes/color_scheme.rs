@@ -0,0 +1,224 @@
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+
+use crate::attributes::ColorError;
+
+// docs
+// * https://graphviz.org/docs/attrs/colorscheme/
+// * https://graphviz.org/doc/info/colors.html#brewer
+//
+// Brewer schemes are named after a palette and the number of colors taken
+// from it, e.g. `blues9`, and their colors are addressed by a 1-based index.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrewerPalette {
+    // Qualitative
+    Accent,
+    Dark2,
+    Paired,
+    Pastel1,
+    Pastel2,
+    Set1,
+    Set2,
+    Set3,
+    // Sequential
+    Blues,
+    BuGn,
+    BuPu,
+    GnBu,
+    Greens,
+    Greys,
+    Oranges,
+    OrRd,
+    PuBu,
+    PuBuGn,
+    PuRd,
+    Purples,
+    RdPu,
+    Reds,
+    YlGn,
+    YlGnBu,
+    YlOrBr,
+    YlOrRd,
+    // Diverging
+    BrBG,
+    PiYG,
+    PRGn,
+    PuOr,
+    RdBu,
+    RdGy,
+    RdYlBu,
+    RdYlGn,
+    Spectral,
+}
+
+const PALETTES: [BrewerPalette; 35] = [
+    BrewerPalette::Accent,
+    BrewerPalette::Dark2,
+    BrewerPalette::Paired,
+    BrewerPalette::Pastel1,
+    BrewerPalette::Pastel2,
+    BrewerPalette::Set1,
+    BrewerPalette::Set2,
+    BrewerPalette::Set3,
+    BrewerPalette::Blues,
+    BrewerPalette::BuGn,
+    BrewerPalette::BuPu,
+    BrewerPalette::GnBu,
+    BrewerPalette::Greens,
+    BrewerPalette::Greys,
+    BrewerPalette::Oranges,
+    BrewerPalette::OrRd,
+    BrewerPalette::PuBu,
+    BrewerPalette::PuBuGn,
+    BrewerPalette::PuRd,
+    BrewerPalette::Purples,
+    BrewerPalette::RdPu,
+    BrewerPalette::Reds,
+    BrewerPalette::YlGn,
+    BrewerPalette::YlGnBu,
+    BrewerPalette::YlOrBr,
+    BrewerPalette::YlOrRd,
+    BrewerPalette::BrBG,
+    BrewerPalette::PiYG,
+    BrewerPalette::PRGn,
+    BrewerPalette::PuOr,
+    BrewerPalette::RdBu,
+    BrewerPalette::RdGy,
+    BrewerPalette::RdYlBu,
+    BrewerPalette::RdYlGn,
+    BrewerPalette::Spectral,
+];
+
+impl BrewerPalette {
+    // The palette sizes graphviz ships for this palette
+    pub fn sizes(&self) -> RangeInclusive<u8> {
+        match self {
+            BrewerPalette::Accent
+            | BrewerPalette::Dark2
+            | BrewerPalette::Pastel2
+            | BrewerPalette::Set2 => 3..=8,
+            BrewerPalette::Paired | BrewerPalette::Set3 => 3..=12,
+            BrewerPalette::Pastel1 | BrewerPalette::Set1 => 3..=9,
+            BrewerPalette::BrBG
+            | BrewerPalette::PiYG
+            | BrewerPalette::PRGn
+            | BrewerPalette::PuOr
+            | BrewerPalette::RdBu
+            | BrewerPalette::RdGy
+            | BrewerPalette::RdYlBu
+            | BrewerPalette::RdYlGn
+            | BrewerPalette::Spectral => 3..=11,
+            _ => 3..=9,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            BrewerPalette::Accent => "accent",
+            BrewerPalette::Dark2 => "dark2",
+            BrewerPalette::Paired => "paired",
+            BrewerPalette::Pastel1 => "pastel1",
+            BrewerPalette::Pastel2 => "pastel2",
+            BrewerPalette::Set1 => "set1",
+            BrewerPalette::Set2 => "set2",
+            BrewerPalette::Set3 => "set3",
+            BrewerPalette::Blues => "blues",
+            BrewerPalette::BuGn => "bugn",
+            BrewerPalette::BuPu => "bupu",
+            BrewerPalette::GnBu => "gnbu",
+            BrewerPalette::Greens => "greens",
+            BrewerPalette::Greys => "greys",
+            BrewerPalette::Oranges => "oranges",
+            BrewerPalette::OrRd => "orrd",
+            BrewerPalette::PuBu => "pubu",
+            BrewerPalette::PuBuGn => "pubugn",
+            BrewerPalette::PuRd => "purd",
+            BrewerPalette::Purples => "purples",
+            BrewerPalette::RdPu => "rdpu",
+            BrewerPalette::Reds => "reds",
+            BrewerPalette::YlGn => "ylgn",
+            BrewerPalette::YlGnBu => "ylgnbu",
+            BrewerPalette::YlOrBr => "ylorbr",
+            BrewerPalette::YlOrRd => "ylorrd",
+            BrewerPalette::BrBG => "brbg",
+            BrewerPalette::PiYG => "piyg",
+            BrewerPalette::PRGn => "prgn",
+            BrewerPalette::PuOr => "puor",
+            BrewerPalette::RdBu => "rdbu",
+            BrewerPalette::RdGy => "rdgy",
+            BrewerPalette::RdYlBu => "rdylbu",
+            BrewerPalette::RdYlGn => "rdylgn",
+            BrewerPalette::Spectral => "spectral",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorScheme {
+    X11,
+    Svg,
+    Brewer { palette: BrewerPalette, size: u8 },
+}
+
+impl ColorScheme {
+    // A Brewer palette cut to `size` colors, e.g. `blues9`
+    pub fn brewer(palette: BrewerPalette, size: u8) -> Result<ColorScheme, ColorError> {
+        if palette.sizes().contains(&size) {
+            Ok(ColorScheme::Brewer { palette, size })
+        } else {
+            Err(ColorError::PaletteSize(palette, size))
+        }
+    }
+
+    pub(crate) fn parse(s: &str) -> Option<ColorScheme> {
+        match s {
+            "x11" => return Some(ColorScheme::X11),
+            "svg" => return Some(ColorScheme::Svg),
+            _ => {}
+        }
+        // Palette names can end in a digit themselves, as in `set312`
+        PALETTES.iter().find_map(|palette| {
+            let size = s.strip_prefix(palette.name())?.parse().ok()?;
+            ColorScheme::brewer(*palette, size).ok()
+        })
+    }
+}
+
+impl Display for ColorScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorScheme::X11 => f.write_str("x11"),
+            ColorScheme::Svg => f.write_str("svg"),
+            ColorScheme::Brewer { palette, size } => write!(f, "{}{}", palette.name(), size),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schemes() {
+        let blues = ColorScheme::brewer(BrewerPalette::Blues, 9).unwrap();
+        assert_eq!(blues.to_string(), "blues9");
+        assert_eq!(ColorScheme::parse("blues9"), Some(blues));
+        assert_eq!(
+            ColorScheme::parse("set312"),
+            ColorScheme::brewer(BrewerPalette::Set3, 12).ok()
+        );
+        assert_eq!(ColorScheme::parse("svg"), Some(ColorScheme::Svg));
+        assert_eq!(ColorScheme::parse("blues12"), None);
+        assert_eq!(ColorScheme::parse("blue9"), None);
+
+        assert_eq!(
+            ColorScheme::brewer(BrewerPalette::Accent, 9),
+            Err(ColorError::PaletteSize(BrewerPalette::Accent, 9))
+        );
+        assert_eq!(
+            ColorScheme::brewer(BrewerPalette::Spectral, 2),
+            Err(ColorError::PaletteSize(BrewerPalette::Spectral, 2))
+        );
+    }
+}
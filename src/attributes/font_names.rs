@@ -0,0 +1,35 @@
+use std::fmt::Display;
+
+// docs
+// * https://graphviz.org/docs/attrs/fontnames/
+#[derive(Clone)]
+pub enum FontNames {
+    // The standard PostScript font names
+    Ps,
+    // The generic SVG families such as serif and sans-serif
+    Svg,
+    // The font names fontconfig resolved
+    Gd,
+}
+
+impl FontNames {
+    pub(crate) fn parse(s: &str) -> Option<FontNames> {
+        match s {
+            "ps" => Some(FontNames::Ps),
+            "svg" => Some(FontNames::Svg),
+            "gd" => Some(FontNames::Gd),
+            _ => None,
+        }
+    }
+}
+
+impl Display for FontNames {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            FontNames::Ps => "ps",
+            FontNames::Svg => "svg",
+            FontNames::Gd => "gd",
+        };
+        f.write_str(s)
+    }
+}
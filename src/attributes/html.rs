@@ -0,0 +1,671 @@
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::attributes::Color;
+use crate::escape::escape_html;
+
+// docs
+// * https://graphviz.org/doc/info/shapes.html#html
+//
+// label     : text | fonttable
+// text      : textitem | text textitem
+// textitem  : string | <BR/> | <FONT> text </FONT> | <I> text </I> | <B> text </B>
+//           | <U> text </U> | <O> text </O> | <SUB> text </SUB> | <SUP> text </SUP>
+//           | <S> text </S>
+// fonttable : table | <FONT> table </FONT> | <I> table </I> | <B> table </B>
+//           | <U> table </U> | <O> table </O>
+// table     : <TABLE> rows </TABLE>
+// rows      : row | rows row | rows <HR/> row
+// row       : <TR> cells </TR>
+// cells     : cell | cells cell | cells <VR/> cell
+// cell      : <TD> label </TD> | <TD> <IMG/> </TD>
+//
+// The builders only offer what the grammar allows at each position, the
+// remaining rules (non-empty tables and rows, rules between elements, one
+// piece of content per cell, unique ports) are checked when building.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HtmlError {
+    EmptyTable,
+    EmptyRow,
+    MisplacedRule,
+    CellContent,
+    DuplicatePort(String),
+}
+
+impl Display for HtmlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HtmlError::EmptyTable => f.write_str("a table needs at least one row"),
+            HtmlError::EmptyRow => f.write_str("a row needs at least one cell"),
+            HtmlError::MisplacedRule => {
+                f.write_str("<HR/> and <VR/> can only appear between rows and cells")
+            }
+            HtmlError::CellContent => f.write_str("a cell holds either a label or one image"),
+            HtmlError::DuplicatePort(port) => {
+                write!(f, "port '{}' is defined more than once", port)
+            }
+        }
+    }
+}
+
+impl Error for HtmlError {}
+
+#[derive(Clone)]
+pub struct Html {
+    markup: String,
+    ports: Vec<String>,
+}
+
+impl Html {
+    pub fn text<F>(f: F) -> Result<Html, HtmlError>
+    where
+        F: FnOnce(&mut TextBuilder) -> &mut TextBuilder,
+    {
+        let mut builder = TextBuilder::new();
+        f(&mut builder);
+        Html::new(builder.markup, Vec::new())
+    }
+
+    pub fn table<F>(f: F) -> Result<Html, HtmlError>
+    where
+        F: FnOnce(&mut TableBuilder) -> &mut TableBuilder,
+    {
+        let mut builder = TableBuilder::new();
+        f(&mut builder);
+        let (markup, ports) = builder.build()?;
+        Html::new(markup, ports)
+    }
+
+    fn new(markup: String, ports: Vec<String>) -> Result<Html, HtmlError> {
+        for (i, port) in ports.iter().enumerate() {
+            if ports[..i].contains(port) {
+                return Err(HtmlError::DuplicatePort(port.clone()));
+            }
+        }
+        Ok(Html { markup, ports })
+    }
+
+    // Markup that was not built here, e.g. read by the parser
+    pub(crate) fn from_dot(markup: &str) -> Html {
+        let mut ports = Vec::new();
+        let mut rest = markup;
+        while let Some(start) = rest.to_ascii_uppercase().find("PORT=\"") {
+            rest = &rest[start + 6..];
+            if let Some(end) = rest.find('"') {
+                ports.push(rest[..end].to_string());
+                rest = &rest[end..];
+            }
+        }
+        Html {
+            markup: markup.to_string(),
+            ports,
+        }
+    }
+
+    pub(crate) fn get(&self) -> &str {
+        self.markup.as_str()
+    }
+
+    pub fn ports(&self) -> &[String] {
+        &self.ports
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+    // Cells only: lines are aligned by their own <BR ALIGN=..>
+    Text,
+}
+
+impl Display for Align {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Align::Left => "LEFT",
+            Align::Center => "CENTER",
+            Align::Right => "RIGHT",
+            Align::Text => "TEXT",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum VAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+impl Display for VAlign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            VAlign::Top => "TOP",
+            VAlign::Middle => "MIDDLE",
+            VAlign::Bottom => "BOTTOM",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Font {
+    attributes: String,
+}
+
+impl Font {
+    pub fn new() -> Self {
+        Font::default()
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        push_attribute(&mut self.attributes, "COLOR", &color.to_string());
+        self
+    }
+
+    pub fn face(mut self, face: &str) -> Self {
+        push_attribute(&mut self.attributes, "FACE", face);
+        self
+    }
+
+    pub fn point_size(mut self, size: f64) -> Self {
+        push_attribute(&mut self.attributes, "POINT-SIZE", &size.to_string());
+        self
+    }
+}
+
+fn push_attribute(attributes: &mut String, name: &str, value: &str) {
+    attributes.push_str(&format!(" {}=\"{}\"", name, escape_html(value)));
+}
+
+pub struct TextBuilder {
+    markup: String,
+}
+
+impl TextBuilder {
+    fn new() -> TextBuilder {
+        TextBuilder {
+            markup: String::new(),
+        }
+    }
+
+    fn element<F>(&mut self, tag: &str, attributes: &str, f: F) -> &mut TextBuilder
+    where
+        F: FnOnce(&mut TextBuilder) -> &mut TextBuilder,
+    {
+        let mut builder = TextBuilder::new();
+        f(&mut builder);
+        self.markup.push_str(&format!(
+            "<{}{}>{}</{}>",
+            tag, attributes, builder.markup, tag
+        ));
+        self
+    }
+
+    pub fn text(&mut self, text: &str) -> &mut TextBuilder {
+        self.markup.push_str(&escape_html(text));
+        self
+    }
+
+    pub fn br(&mut self) -> &mut TextBuilder {
+        self.markup.push_str("<BR/>");
+        self
+    }
+
+    // Ends the line and aligns it, overriding the cell's BALIGN
+    pub fn br_aligned(&mut self, align: Align) -> &mut TextBuilder {
+        self.markup.push_str(&format!("<BR ALIGN=\"{}\"/>", align));
+        self
+    }
+
+    pub fn font<F>(&mut self, font: Font, f: F) -> &mut TextBuilder
+    where
+        F: FnOnce(&mut TextBuilder) -> &mut TextBuilder,
+    {
+        self.element("FONT", &font.attributes, f)
+    }
+
+    pub fn bold<F>(&mut self, f: F) -> &mut TextBuilder
+    where
+        F: FnOnce(&mut TextBuilder) -> &mut TextBuilder,
+    {
+        self.element("B", "", f)
+    }
+
+    pub fn italic<F>(&mut self, f: F) -> &mut TextBuilder
+    where
+        F: FnOnce(&mut TextBuilder) -> &mut TextBuilder,
+    {
+        self.element("I", "", f)
+    }
+
+    pub fn underline<F>(&mut self, f: F) -> &mut TextBuilder
+    where
+        F: FnOnce(&mut TextBuilder) -> &mut TextBuilder,
+    {
+        self.element("U", "", f)
+    }
+
+    pub fn overline<F>(&mut self, f: F) -> &mut TextBuilder
+    where
+        F: FnOnce(&mut TextBuilder) -> &mut TextBuilder,
+    {
+        self.element("O", "", f)
+    }
+
+    pub fn strikethrough<F>(&mut self, f: F) -> &mut TextBuilder
+    where
+        F: FnOnce(&mut TextBuilder) -> &mut TextBuilder,
+    {
+        self.element("S", "", f)
+    }
+
+    pub fn subscript<F>(&mut self, f: F) -> &mut TextBuilder
+    where
+        F: FnOnce(&mut TextBuilder) -> &mut TextBuilder,
+    {
+        self.element("SUB", "", f)
+    }
+
+    pub fn superscript<F>(&mut self, f: F) -> &mut TextBuilder
+    where
+        F: FnOnce(&mut TextBuilder) -> &mut TextBuilder,
+    {
+        self.element("SUP", "", f)
+    }
+}
+
+// The rows and cells of a table are separated by either nothing or a rule
+enum Item {
+    Element(String),
+    Rule,
+}
+
+fn join(items: Vec<Item>, rule: &str, empty: HtmlError) -> Result<String, HtmlError> {
+    match (items.first(), items.last()) {
+        (None, _) => return Err(empty),
+        (Some(Item::Rule), _) | (_, Some(Item::Rule)) => return Err(HtmlError::MisplacedRule),
+        _ => {}
+    }
+    let mut markup = String::new();
+    let mut previous_rule = false;
+    for item in items {
+        match item {
+            Item::Rule if previous_rule => return Err(HtmlError::MisplacedRule),
+            Item::Rule => {
+                markup.push_str(rule);
+                previous_rule = true;
+            }
+            Item::Element(element) => {
+                markup.push_str(&element);
+                previous_rule = false;
+            }
+        }
+    }
+    Ok(markup)
+}
+
+pub struct TableBuilder {
+    attributes: String,
+    // Font and style elements wrapping the table, innermost last
+    wrappers: Vec<(&'static str, String)>,
+    rows: Vec<Item>,
+    ports: Vec<String>,
+    error: Option<HtmlError>,
+}
+
+impl TableBuilder {
+    fn new() -> TableBuilder {
+        TableBuilder {
+            attributes: String::new(),
+            wrappers: Vec::new(),
+            rows: Vec::new(),
+            ports: Vec::new(),
+            error: None,
+        }
+    }
+
+    fn build(self) -> Result<(String, Vec<String>), HtmlError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let rows = join(self.rows, "<HR/>", HtmlError::EmptyTable)?;
+        let mut markup = format!("<TABLE{}>{}</TABLE>", self.attributes, rows);
+        for (tag, attributes) in self.wrappers.iter().rev() {
+            markup = format!("<{}{}>{}</{}>", tag, attributes, markup, tag);
+        }
+        Ok((markup, self.ports))
+    }
+
+    fn attribute(&mut self, name: &str, value: &str) -> &mut TableBuilder {
+        push_attribute(&mut self.attributes, name, value);
+        self
+    }
+
+    pub fn row<F>(&mut self, f: F) -> &mut TableBuilder
+    where
+        F: FnOnce(&mut RowBuilder) -> &mut RowBuilder,
+    {
+        let mut builder = RowBuilder::new();
+        f(&mut builder);
+        self.ports.append(&mut builder.ports);
+        if self.error.is_none() {
+            self.error = builder.error;
+        }
+        match join(builder.cells, "<VR/>", HtmlError::EmptyRow) {
+            Ok(cells) => self.rows.push(Item::Element(format!("<TR>{}</TR>", cells))),
+            Err(error) => {
+                self.error.get_or_insert(error);
+            }
+        }
+        self
+    }
+
+    // A horizontal rule between two rows
+    pub fn hr(&mut self) -> &mut TableBuilder {
+        self.rows.push(Item::Rule);
+        self
+    }
+
+    pub fn font(&mut self, font: Font) -> &mut TableBuilder {
+        self.wrappers.push(("FONT", font.attributes));
+        self
+    }
+
+    pub fn bold(&mut self) -> &mut TableBuilder {
+        self.wrappers.push(("B", String::new()));
+        self
+    }
+
+    pub fn italic(&mut self) -> &mut TableBuilder {
+        self.wrappers.push(("I", String::new()));
+        self
+    }
+
+    pub fn underline(&mut self) -> &mut TableBuilder {
+        self.wrappers.push(("U", String::new()));
+        self
+    }
+
+    pub fn overline(&mut self) -> &mut TableBuilder {
+        self.wrappers.push(("O", String::new()));
+        self
+    }
+
+    pub fn port(&mut self, port: &str) -> &mut TableBuilder {
+        self.ports.push(port.to_string());
+        self.attribute("PORT", port)
+    }
+
+    pub fn align(&mut self, align: Align) -> &mut TableBuilder {
+        self.attribute("ALIGN", &align.to_string())
+    }
+
+    pub fn valign(&mut self, valign: VAlign) -> &mut TableBuilder {
+        self.attribute("VALIGN", &valign.to_string())
+    }
+
+    pub fn bgcolor(&mut self, color: Color) -> &mut TableBuilder {
+        self.attribute("BGCOLOR", &color.to_string())
+    }
+
+    pub fn color(&mut self, color: Color) -> &mut TableBuilder {
+        self.attribute("COLOR", &color.to_string())
+    }
+
+    pub fn border(&mut self, border: u8) -> &mut TableBuilder {
+        self.attribute("BORDER", &border.to_string())
+    }
+
+    pub fn cellborder(&mut self, border: u8) -> &mut TableBuilder {
+        self.attribute("CELLBORDER", &border.to_string())
+    }
+
+    pub fn cellpadding(&mut self, padding: u8) -> &mut TableBuilder {
+        self.attribute("CELLPADDING", &padding.to_string())
+    }
+
+    pub fn cellspacing(&mut self, spacing: u8) -> &mut TableBuilder {
+        self.attribute("CELLSPACING", &spacing.to_string())
+    }
+
+    pub fn width(&mut self, width: u16) -> &mut TableBuilder {
+        self.attribute("WIDTH", &width.to_string())
+    }
+
+    pub fn height(&mut self, height: u16) -> &mut TableBuilder {
+        self.attribute("HEIGHT", &height.to_string())
+    }
+
+    pub fn fixedsize(&mut self, fixedsize: bool) -> &mut TableBuilder {
+        self.attribute("FIXEDSIZE", &fixedsize.to_string().to_uppercase())
+    }
+
+    pub fn tooltip(&mut self, tooltip: &str) -> &mut TableBuilder {
+        self.attribute("TOOLTIP", tooltip)
+    }
+}
+
+pub struct RowBuilder {
+    cells: Vec<Item>,
+    ports: Vec<String>,
+    error: Option<HtmlError>,
+}
+
+impl RowBuilder {
+    fn new() -> RowBuilder {
+        RowBuilder {
+            cells: Vec::new(),
+            ports: Vec::new(),
+            error: None,
+        }
+    }
+
+    pub fn cell<F>(&mut self, f: F) -> &mut RowBuilder
+    where
+        F: FnOnce(&mut CellBuilder) -> &mut CellBuilder,
+    {
+        let mut builder = CellBuilder::new();
+        f(&mut builder);
+        self.ports.append(&mut builder.ports);
+        if self.error.is_none() {
+            self.error = builder.error;
+        }
+        self.cells.push(Item::Element(format!(
+            "<TD{}>{}</TD>",
+            builder.attributes,
+            builder.content.unwrap_or_default()
+        )));
+        self
+    }
+
+    // A vertical rule between two cells
+    pub fn vr(&mut self) -> &mut RowBuilder {
+        self.cells.push(Item::Rule);
+        self
+    }
+}
+
+pub struct CellBuilder {
+    attributes: String,
+    content: Option<String>,
+    ports: Vec<String>,
+    error: Option<HtmlError>,
+}
+
+impl CellBuilder {
+    fn new() -> CellBuilder {
+        CellBuilder {
+            attributes: String::new(),
+            content: None,
+            ports: Vec::new(),
+            error: None,
+        }
+    }
+
+    fn attribute(&mut self, name: &str, value: &str) -> &mut CellBuilder {
+        push_attribute(&mut self.attributes, name, value);
+        self
+    }
+
+    fn set_content(&mut self, content: String) -> &mut CellBuilder {
+        if self.content.is_some() {
+            self.error.get_or_insert(HtmlError::CellContent);
+        }
+        self.content = Some(content);
+        self
+    }
+
+    pub fn text<F>(&mut self, f: F) -> &mut CellBuilder
+    where
+        F: FnOnce(&mut TextBuilder) -> &mut TextBuilder,
+    {
+        let mut builder = TextBuilder::new();
+        f(&mut builder);
+        self.set_content(builder.markup)
+    }
+
+    pub fn table<F>(&mut self, f: F) -> &mut CellBuilder
+    where
+        F: FnOnce(&mut TableBuilder) -> &mut TableBuilder,
+    {
+        let mut builder = TableBuilder::new();
+        f(&mut builder);
+        match builder.build() {
+            Ok((markup, mut ports)) => {
+                self.ports.append(&mut ports);
+                self.set_content(markup)
+            }
+            Err(error) => {
+                self.error.get_or_insert(error);
+                self
+            }
+        }
+    }
+
+    pub fn image(&mut self, src: &str) -> &mut CellBuilder {
+        self.set_content(format!("<IMG SRC=\"{}\"/>", escape_html(src)))
+    }
+
+    pub fn port(&mut self, port: &str) -> &mut CellBuilder {
+        self.ports.push(port.to_string());
+        self.attribute("PORT", port)
+    }
+
+    pub fn align(&mut self, align: Align) -> &mut CellBuilder {
+        self.attribute("ALIGN", &align.to_string())
+    }
+
+    // Default alignment of the lines in the cell
+    pub fn balign(&mut self, align: Align) -> &mut CellBuilder {
+        self.attribute("BALIGN", &align.to_string())
+    }
+
+    pub fn valign(&mut self, valign: VAlign) -> &mut CellBuilder {
+        self.attribute("VALIGN", &valign.to_string())
+    }
+
+    pub fn colspan(&mut self, span: u16) -> &mut CellBuilder {
+        self.attribute("COLSPAN", &span.to_string())
+    }
+
+    pub fn rowspan(&mut self, span: u16) -> &mut CellBuilder {
+        self.attribute("ROWSPAN", &span.to_string())
+    }
+
+    pub fn bgcolor(&mut self, color: Color) -> &mut CellBuilder {
+        self.attribute("BGCOLOR", &color.to_string())
+    }
+
+    pub fn color(&mut self, color: Color) -> &mut CellBuilder {
+        self.attribute("COLOR", &color.to_string())
+    }
+
+    pub fn border(&mut self, border: u8) -> &mut CellBuilder {
+        self.attribute("BORDER", &border.to_string())
+    }
+
+    pub fn cellpadding(&mut self, padding: u8) -> &mut CellBuilder {
+        self.attribute("CELLPADDING", &padding.to_string())
+    }
+
+    pub fn width(&mut self, width: u16) -> &mut CellBuilder {
+        self.attribute("WIDTH", &width.to_string())
+    }
+
+    pub fn height(&mut self, height: u16) -> &mut CellBuilder {
+        self.attribute("HEIGHT", &height.to_string())
+    }
+
+    pub fn fixedsize(&mut self, fixedsize: bool) -> &mut CellBuilder {
+        self.attribute("FIXEDSIZE", &fixedsize.to_string().to_uppercase())
+    }
+
+    pub fn tooltip(&mut self, tooltip: &str) -> &mut CellBuilder {
+        self.attribute("TOOLTIP", tooltip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table() {
+        let html = Html::table(|t| {
+            t.border(0)
+                .cellborder(1)
+                .row(|r| {
+                    r.cell(|c| c.port("here").text(|t| t.text("left")))
+                        .vr()
+                        .cell(|c| c.text(|t| t.bold(|b| b.text("a < b")).br().text("x")))
+                })
+                .hr()
+                .row(|r| r.cell(|c| c.colspan(2).image("logo.png")))
+        })
+        .unwrap();
+        assert_eq!(
+            html.get(),
+            "<TABLE BORDER=\"0\" CELLBORDER=\"1\">\
+             <TR><TD PORT=\"here\">left</TD><VR/><TD><B>a &lt; b</B><BR/>x</TD></TR>\
+             <HR/>\
+             <TR><TD COLSPAN=\"2\"><IMG SRC=\"logo.png\"/></TD></TR>\
+             </TABLE>"
+        );
+        assert_eq!(html.ports(), ["here"]);
+    }
+
+    #[test]
+    fn nesting_rules() {
+        assert_eq!(Html::table(|t| t).err(), Some(HtmlError::EmptyTable));
+        assert_eq!(
+            Html::table(|t| t.row(|r| r)).err(),
+            Some(HtmlError::EmptyRow)
+        );
+        assert_eq!(
+            Html::table(|t| t.hr().row(|r| r.cell(|c| c))).err(),
+            Some(HtmlError::MisplacedRule)
+        );
+        assert_eq!(
+            Html::table(|t| t.row(|r| r.cell(|c| c).vr().vr().cell(|c| c))).err(),
+            Some(HtmlError::MisplacedRule)
+        );
+        assert_eq!(
+            Html::table(|t| t.row(|r| r.cell(|c| c.image("a.png").text(|t| t.text("a"))))).err(),
+            Some(HtmlError::CellContent)
+        );
+        assert_eq!(
+            Html::table(|t| {
+                t.row(|r| {
+                    r.cell(|c| c.port("p"))
+                        .cell(|c| c.table(|t| t.row(|r| r.cell(|c| c.port("p")))))
+                })
+            })
+            .err(),
+            Some(HtmlError::DuplicatePort("p".to_string()))
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use std::fmt::Display;
+
+// docs
+// * https://gexf.net/
+//
+// When a node or edge exists, for the dynamic graphs of GEXF. Graphviz has
+// no such attribute, so DOT output leaves it out. Intervals are `start,end` with a bound
+// left empty when it is open and are separated by ';', e.g. `1,3;5,` for
+// from 1 to 3 and again from 5 on. Bounds are numbers or ISO 8601 dates.
+#[derive(Clone, Default)]
+pub(crate) struct Intervals {
+    pub(crate) intervals: Vec<(Option<String>, Option<String>)>,
+}
+
+impl Intervals {
+    pub(crate) fn add(&mut self, start: Option<String>, end: Option<String>) {
+        let bound = |b: Option<String>| b.map(|b| b.trim().to_string()).filter(|b| !b.is_empty());
+        self.intervals.push((bound(start), bound(end)));
+    }
+
+    pub(crate) fn parse(s: &str) -> Option<Intervals> {
+        let mut intervals = Intervals::default();
+        for interval in s.split(';').filter(|i| !i.trim().is_empty()) {
+            let (start, end) = interval.split_once(',')?;
+            if end.contains(',') {
+                return None;
+            }
+            intervals.add(Some(start.to_string()), Some(end.to_string()));
+        }
+        Some(intervals)
+    }
+}
+
+impl Display for Intervals {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (start, end)) in self.intervals.iter().enumerate() {
+            if i > 0 {
+                f.write_str(";")?;
+            }
+            write!(
+                f,
+                "{},{}",
+                start.as_deref().unwrap_or(""),
+                end.as_deref().unwrap_or("")
+            )?;
+        }
+        Ok(())
+    }
+}
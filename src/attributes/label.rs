@@ -1,10 +1,85 @@
-use std::fmt::Display;
+use crate::ast::{Id, ToId};
+use crate::attributes::{Html, RecordLabel};
+use crate::escape::escape_text;
 
-pub struct Label(String);
+// docs
+// * https://graphviz.org/docs/attrs/label/
+// * https://graphviz.org/docs/attr-types/escString/
+
+#[derive(Clone)]
+pub enum Label {
+    // An escString, printed as label="..."
+    Text(String),
+    // An HTML-like label, printed as label=<...>
+    Html(Html),
+    // The fields of a record shaped node, printed as label="..."
+    Record(RecordLabel),
+}
 
 impl Label {
     pub fn new(l: &str) -> Self {
-        Label(l.to_string())
+        Label::Text(escape_text(l))
+    }
+
+    // A label whose text is already an escString, e.g. read by the parser
+    pub(crate) fn from_dot(l: &str) -> Self {
+        Label::Text(l.to_string())
+    }
+
+    // The methods below build up text labels. HTML and record labels have
+    // their own structure and are returned unchanged.
+    fn push(self, escape: &str) -> Self {
+        match self {
+            Label::Text(mut s) => {
+                s.push_str(escape);
+                Label::Text(s)
+            }
+            label => label,
+        }
+    }
+
+    pub fn text(self, text: &str) -> Self {
+        self.push(&escape_text(text))
+    }
+
+    // Ends the current line and centers it
+    pub fn line_centered(self) -> Self {
+        self.push("\\n")
+    }
+
+    // Ends the current line and left-justifies it
+    pub fn line_left(self) -> Self {
+        self.push("\\l")
+    }
+
+    // Ends the current line and right-justifies it
+    pub fn line_right(self) -> Self {
+        self.push("\\r")
+    }
+
+    // The name of the node the label belongs to
+    pub fn node_name(self) -> Self {
+        self.push("\\N")
+    }
+
+    // The name of the graph or cluster the label belongs to
+    pub fn graph_name(self) -> Self {
+        self.push("\\G")
+    }
+
+    // The name of the edge the label belongs to, e.g. "a->b"
+    pub fn edge_name(self) -> Self {
+        self.push("\\E")
+    }
+
+    // The name of the tail node of the edge the label belongs to
+    pub fn tail_name(self) -> Self {
+        self.push("\\T")
+    }
+
+    // The name of the head node of the edge the label belongs to
+    pub fn head_name(self) -> Self {
+        self.push("\\H")
     }
 }
 
@@ -17,12 +92,35 @@ where
     T: ToString,
 {
     fn to_label(&self) -> Label {
-        Label(self.to_string())
+        Label::new(&self.to_string())
+    }
+}
+
+impl ToLabel for Label {
+    fn to_label(&self) -> Label {
+        self.clone()
+    }
+}
+
+impl ToLabel for Html {
+    fn to_label(&self) -> Label {
+        Label::Html(self.clone())
+    }
+}
+
+impl ToLabel for RecordLabel {
+    fn to_label(&self) -> Label {
+        Label::Record(self.clone())
     }
 }
 
-impl Display for Label {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+// The label as an attribute value, keeping its escapes or HTML markup
+impl ToId for Label {
+    fn to_id(&self) -> Id {
+        match self {
+            Label::Text(text) => Id::from_dot(text),
+            Label::Html(html) => Id::html(html.get()),
+            Label::Record(record) => Id::from_dot(record.get()),
+        }
     }
 }
@@ -1,13 +1,41 @@
+mod arrow;
 mod color;
+mod color_names;
+mod color_scheme;
+mod font_names;
+mod html;
+mod intervals;
 mod label;
+mod overlap;
+mod pack_mode;
+mod page_dir;
+mod position;
+mod rank;
 mod rankdir;
+mod record;
 mod shape;
 mod size;
+mod start;
 mod style;
 
-pub use color::Color;
+pub(crate) use arrow::ArrowShape;
+pub use arrow::{Arrow, ArrowBuilder, ArrowError, Dir, Fill, Side};
+pub use color::{Color, ColorError, ColorList, ColorListBuilder};
+pub use color_scheme::{BrewerPalette, ColorScheme};
+pub use font_names::FontNames;
+pub use html::{
+    Align, CellBuilder, Font, Html, HtmlError, RowBuilder, TableBuilder, TextBuilder, VAlign,
+};
+pub(crate) use intervals::Intervals;
 pub use label::{Label, ToLabel};
+pub use overlap::Overlap;
+pub use pack_mode::PackMode;
+pub use page_dir::PageDir;
+pub use position::Position;
+pub use rank::Rank;
 pub use rankdir::RankDir;
-pub use shape::Shape;
+pub use record::{RecordBuilder, RecordError, RecordLabel};
+pub use shape::{Polygon, PolygonBuilder, Shape, ShapeError};
 pub use size::Size;
+pub use start::Start;
 pub use style::{ClusterStyle, EdgeStyle, NodeStyle, Style};
@@ -0,0 +1,51 @@
+use std::fmt::Display;
+
+// docs
+// * https://graphviz.org/docs/attrs/overlap/
+//
+// What to do about nodes that overlap once the layout is done. graphviz
+// keeps them by default.
+#[derive(Clone)]
+pub enum Overlap {
+    // `true`
+    Retain,
+    // `false`, the best removal method available
+    Remove,
+    // Spread the drawing evenly until no nodes overlap
+    Scale,
+    // Spread the drawing separately along x and y
+    ScaleXY,
+    Prism,
+    Compress,
+    Vpsc,
+}
+
+impl Overlap {
+    pub(crate) fn parse(s: &str) -> Option<Overlap> {
+        match s {
+            "true" | "retain" => Some(Overlap::Retain),
+            "false" => Some(Overlap::Remove),
+            "scale" => Some(Overlap::Scale),
+            "scalexy" => Some(Overlap::ScaleXY),
+            "prism" => Some(Overlap::Prism),
+            "compress" => Some(Overlap::Compress),
+            "vpsc" => Some(Overlap::Vpsc),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Overlap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Overlap::Retain => "true",
+            Overlap::Remove => "false",
+            Overlap::Scale => "scale",
+            Overlap::ScaleXY => "scalexy",
+            Overlap::Prism => "prism",
+            Overlap::Compress => "compress",
+            Overlap::Vpsc => "vpsc",
+        };
+        f.write_str(s)
+    }
+}
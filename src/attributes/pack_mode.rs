@@ -0,0 +1,40 @@
+use std::fmt::Display;
+
+// docs
+// * https://graphviz.org/docs/attrs/packmode/
+// * https://graphviz.org/docs/attr-types/packMode/
+#[derive(Clone)]
+pub enum PackMode {
+    Node,
+    Cluster,
+    Graph,
+    // Rows of components, `array` or with a fixed number of columns
+    Array(Option<u32>),
+}
+
+impl PackMode {
+    pub(crate) fn parse(s: &str) -> Option<PackMode> {
+        match s {
+            "node" => Some(PackMode::Node),
+            "clust" => Some(PackMode::Cluster),
+            "graph" => Some(PackMode::Graph),
+            "array" => Some(PackMode::Array(None)),
+            _ => s
+                .strip_prefix("array")
+                .and_then(|columns| columns.parse().ok())
+                .map(|columns| PackMode::Array(Some(columns))),
+        }
+    }
+}
+
+impl Display for PackMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackMode::Node => f.write_str("node"),
+            PackMode::Cluster => f.write_str("clust"),
+            PackMode::Graph => f.write_str("graph"),
+            PackMode::Array(None) => f.write_str("array"),
+            PackMode::Array(Some(columns)) => write!(f, "array{}", columns),
+        }
+    }
+}
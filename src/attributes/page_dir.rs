@@ -0,0 +1,51 @@
+use std::fmt::Display;
+
+// docs
+// * https://graphviz.org/docs/attrs/pagedir/
+// * https://graphviz.org/docs/attr-types/pagedir/
+//
+// The order pages are emitted in when a drawing spans several, as the major
+// then the minor direction, e.g. BL is bottom to top, then left to right.
+#[derive(Clone)]
+pub enum PageDir {
+    BL,
+    BR,
+    TL,
+    TR,
+    RB,
+    RT,
+    LB,
+    LT,
+}
+
+impl PageDir {
+    pub(crate) fn parse(s: &str) -> Option<PageDir> {
+        match s {
+            "BL" => Some(PageDir::BL),
+            "BR" => Some(PageDir::BR),
+            "TL" => Some(PageDir::TL),
+            "TR" => Some(PageDir::TR),
+            "RB" => Some(PageDir::RB),
+            "RT" => Some(PageDir::RT),
+            "LB" => Some(PageDir::LB),
+            "LT" => Some(PageDir::LT),
+            _ => None,
+        }
+    }
+}
+
+impl Display for PageDir {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            PageDir::BL => "BL",
+            PageDir::BR => "BR",
+            PageDir::TL => "TL",
+            PageDir::TR => "TR",
+            PageDir::RB => "RB",
+            PageDir::RT => "RT",
+            PageDir::LB => "LB",
+            PageDir::LT => "LT",
+        };
+        f.write_str(s)
+    }
+}
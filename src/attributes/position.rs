@@ -0,0 +1,59 @@
+use std::fmt::Display;
+
+// docs
+// * https://graphviz.org/docs/attrs/pos/
+// * https://graphviz.org/docs/attr-types/point/
+//
+// A node position in inches, with y growing upwards. A pinned position ends
+// in '!' and is kept as is by neato and fdp.
+#[derive(Clone)]
+pub struct Position {
+    pub(crate) x: f64,
+    pub(crate) y: f64,
+    pub(crate) pinned: bool,
+}
+
+impl Position {
+    pub fn new(x: f64, y: f64) -> Position {
+        Position {
+            x,
+            y,
+            pinned: false,
+        }
+    }
+
+    pub fn pinned(x: f64, y: f64) -> Position {
+        Position { x, y, pinned: true }
+    }
+
+    // point is "%f,%f('!')?"
+    pub(crate) fn parse(s: &str) -> Option<Position> {
+        let s = s.trim();
+        let (s, pinned) = match s.strip_suffix('!') {
+            Some(s) => (s, true),
+            None => (s, false),
+        };
+        let (x, y) = s.split_once(',')?;
+        Some(Position {
+            x: x.trim().parse().ok()?,
+            y: y.trim().parse().ok()?,
+            pinned,
+        })
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{}", self.x, self.y)?;
+        if self.pinned {
+            f.write_str("!")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<(f64, f64)> for Position {
+    fn from(xy: (f64, f64)) -> Self {
+        Position::new(xy.0, xy.1)
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt::Display;
+
+// docs
+// * https://graphviz.org/docs/attrs/rank/
+// * https://graphviz.org/docs/attr-types/rankType/
+#[derive(Clone)]
+pub enum Rank {
+    Same,
+    Min,
+    Source,
+    Max,
+    Sink,
+}
+
+impl Rank {
+    pub(crate) fn parse(s: &str) -> Option<Rank> {
+        match s {
+            "same" => Some(Rank::Same),
+            "min" => Some(Rank::Min),
+            "source" => Some(Rank::Source),
+            "max" => Some(Rank::Max),
+            "sink" => Some(Rank::Sink),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Rank {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Rank::Same => "same",
+            Rank::Min => "min",
+            Rank::Source => "source",
+            Rank::Max => "max",
+            Rank::Sink => "sink",
+        };
+        f.write_str(s)
+    }
+}
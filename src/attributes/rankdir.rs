@@ -1,5 +1,6 @@
 use std::fmt::Display;
 
+#[derive(Clone)]
 pub enum RankDir {
     TopBottom,
     LeftRight,
@@ -7,6 +8,18 @@ pub enum RankDir {
     RightLeft,
 }
 
+impl RankDir {
+    pub(crate) fn parse(s: &str) -> Option<RankDir> {
+        match s {
+            "TB" => Some(RankDir::TopBottom),
+            "LR" => Some(RankDir::LeftRight),
+            "BT" => Some(RankDir::BottomTop),
+            "RL" => Some(RankDir::RightLeft),
+            _ => None,
+        }
+    }
+}
+
 impl Display for RankDir {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
@@ -0,0 +1,193 @@
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::escape::escape_text;
+
+// docs
+// * https://graphviz.org/doc/info/shapes.html#record
+//
+// rlabel  : field ( '|' field )*
+// field   : fieldId | '{' rlabel '}'
+// fieldId : [ '<' string '>' ] [ string ]
+//
+// Fields are laid out horizontally at the top level (for rankdir=TB or BT)
+// and every pair of braces flips the direction. The builder tracks the
+// direction so that callers ask for the one they want instead of counting
+// braces.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordError {
+    DuplicatePort(String),
+}
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordError::DuplicatePort(port) => {
+                write!(f, "port '{}' is defined more than once", port)
+            }
+        }
+    }
+}
+
+impl Error for RecordError {}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Orientation {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Clone)]
+pub struct RecordLabel {
+    fields: String,
+    ports: Vec<String>,
+}
+
+impl RecordLabel {
+    pub fn horizontal<F>(f: F) -> Result<RecordLabel, RecordError>
+    where
+        F: FnOnce(&mut RecordBuilder) -> &mut RecordBuilder,
+    {
+        RecordLabel::new(Orientation::Horizontal, f)
+    }
+
+    pub fn vertical<F>(f: F) -> Result<RecordLabel, RecordError>
+    where
+        F: FnOnce(&mut RecordBuilder) -> &mut RecordBuilder,
+    {
+        RecordLabel::new(Orientation::Vertical, f)
+    }
+
+    fn new<F>(orientation: Orientation, f: F) -> Result<RecordLabel, RecordError>
+    where
+        F: FnOnce(&mut RecordBuilder) -> &mut RecordBuilder,
+    {
+        let mut builder = RecordBuilder::new(Orientation::Horizontal);
+        builder.group(orientation, f);
+        for (i, port) in builder.ports.iter().enumerate() {
+            if builder.ports[..i].contains(port) {
+                return Err(RecordError::DuplicatePort(port.clone()));
+            }
+        }
+        Ok(RecordLabel {
+            fields: builder.fields.join("|"),
+            ports: builder.ports,
+        })
+    }
+
+    pub(crate) fn get(&self) -> &str {
+        self.fields.as_str()
+    }
+
+    pub fn ports(&self) -> &[String] {
+        &self.ports
+    }
+}
+
+pub struct RecordBuilder {
+    orientation: Orientation,
+    fields: Vec<String>,
+    ports: Vec<String>,
+}
+
+impl RecordBuilder {
+    fn new(orientation: Orientation) -> RecordBuilder {
+        RecordBuilder {
+            orientation,
+            fields: Vec::new(),
+            ports: Vec::new(),
+        }
+    }
+
+    fn group<F>(&mut self, orientation: Orientation, f: F) -> &mut RecordBuilder
+    where
+        F: FnOnce(&mut RecordBuilder) -> &mut RecordBuilder,
+    {
+        let mut builder = RecordBuilder::new(orientation);
+        f(&mut builder);
+        self.ports.append(&mut builder.ports);
+        if orientation == self.orientation {
+            self.fields.append(&mut builder.fields);
+        } else {
+            self.fields
+                .push(format!("{{{}}}", builder.fields.join("|")));
+        }
+        self
+    }
+
+    pub fn field(&mut self, text: &str) -> &mut RecordBuilder {
+        self.fields.push(escape_field(text));
+        self
+    }
+
+    // A field that edges can attach to as `node:port`
+    pub fn port(&mut self, port: &str, text: &str) -> &mut RecordBuilder {
+        self.ports.push(port.to_string());
+        self.fields
+            .push(format!("<{}> {}", escape_field(port), escape_field(text)));
+        self
+    }
+
+    pub fn horizontal<F>(&mut self, f: F) -> &mut RecordBuilder
+    where
+        F: FnOnce(&mut RecordBuilder) -> &mut RecordBuilder,
+    {
+        self.group(Orientation::Horizontal, f)
+    }
+
+    pub fn vertical<F>(&mut self, f: F) -> &mut RecordBuilder
+    where
+        F: FnOnce(&mut RecordBuilder) -> &mut RecordBuilder,
+    {
+        self.group(Orientation::Vertical, f)
+    }
+}
+
+// Braces, bars, angle brackets and spaces are part of the record syntax and
+// are escaped with a backslash to appear literally.
+fn escape_field(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in escape_text(text).chars() {
+        if matches!(c, '{' | '}' | '|' | '<' | '>' | ' ') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields() {
+        // https://graphviz.org/Gallery/directed/datastruct.html
+        let label = RecordLabel::horizontal(|r| {
+            r.field("hello\nworld")
+                .vertical(|r| {
+                    r.field("b")
+                        .horizontal(|r| r.field("c").port("here", "d").field("e"))
+                        .field("f")
+                })
+                .field("g")
+                .field("{h}")
+        })
+        .unwrap();
+        assert_eq!(label.get(), r"hello\nworld|{b|{c|<here> d|e}|f}|g|\{h\}");
+        assert_eq!(label.ports(), ["here"]);
+
+        let label = RecordLabel::vertical(|r| r.port("p 1", "a b").field("c")).unwrap();
+        assert_eq!(label.get(), r"{<p\ 1> a\ b|c}");
+    }
+
+    #[test]
+    fn duplicate_ports() {
+        let label = RecordLabel::horizontal(|r| r.port("p", "a").vertical(|r| r.port("p", "b")));
+        assert_eq!(
+            label.err(),
+            Some(RecordError::DuplicatePort("p".to_string()))
+        );
+    }
+}
@@ -1,26 +1,299 @@
+use std::error::Error;
 use std::fmt::Display;
 
+// docs
+// * https://graphviz.org/doc/info/shapes.html
+
+#[derive(Clone)]
 pub enum Shape {
+    // Polygon-based shapes
     Box,
+    // A custom polygon, see `PolygonBuilder`
+    Polygon,
+    Ellipse,
+    Oval,
     Circle,
+    Point,
+    Egg,
+    Triangle,
+    PlainText,
+    Plain,
     Diamond,
+    Trapezium,
+    Parallelogram,
+    House,
+    Pentagon,
+    Hexagon,
+    Septagon,
+    Octagon,
     DoubleCircle,
-    Ellipse,
+    DoubleOctagon,
+    TripleOctagon,
+    InvTriangle,
+    InvTrapezium,
+    InvHouse,
     MDiamond,
     MSquare,
+    MCircle,
+    Rect,
+    Rectangle,
+    Square,
+    Star,
+    None,
+    Underline,
+    // Shapes for software and architecture diagrams
+    Cylinder,
+    Note,
+    Tab,
+    Folder,
+    Box3D,
+    Component,
+    // Synthetic biology shapes (SBOL)
+    Promoter,
+    Cds,
+    Terminator,
+    Utr,
+    PrimerSite,
+    RestrictionSite,
+    FivePOverhang,
+    ThreePOverhang,
+    NOverhang,
+    Assembly,
+    Signature,
+    Insulator,
+    RiboSite,
+    RnaStab,
+    ProteaseSite,
+    ProteinStab,
+    RPromoter,
+    RArrow,
+    LArrow,
+    LPromoter,
+    // Fields laid out from a record label, see `RecordLabel`
+    Record,
+    MRecord,
+}
+
+impl Shape {
+    pub(crate) fn parse(s: &str) -> Option<Shape> {
+        match s {
+            "box" => Some(Shape::Box),
+            "polygon" => Some(Shape::Polygon),
+            "ellipse" => Some(Shape::Ellipse),
+            "oval" => Some(Shape::Oval),
+            "circle" => Some(Shape::Circle),
+            "point" => Some(Shape::Point),
+            "egg" => Some(Shape::Egg),
+            "triangle" => Some(Shape::Triangle),
+            "plaintext" => Some(Shape::PlainText),
+            "plain" => Some(Shape::Plain),
+            "diamond" => Some(Shape::Diamond),
+            "trapezium" => Some(Shape::Trapezium),
+            "parallelogram" => Some(Shape::Parallelogram),
+            "house" => Some(Shape::House),
+            "pentagon" => Some(Shape::Pentagon),
+            "hexagon" => Some(Shape::Hexagon),
+            "septagon" => Some(Shape::Septagon),
+            "octagon" => Some(Shape::Octagon),
+            "doublecircle" => Some(Shape::DoubleCircle),
+            "doubleoctagon" => Some(Shape::DoubleOctagon),
+            "tripleoctagon" => Some(Shape::TripleOctagon),
+            "invtriangle" => Some(Shape::InvTriangle),
+            "invtrapezium" => Some(Shape::InvTrapezium),
+            "invhouse" => Some(Shape::InvHouse),
+            "Mdiamond" => Some(Shape::MDiamond),
+            "Msquare" => Some(Shape::MSquare),
+            "Mcircle" => Some(Shape::MCircle),
+            "rect" => Some(Shape::Rect),
+            "rectangle" => Some(Shape::Rectangle),
+            "square" => Some(Shape::Square),
+            "star" => Some(Shape::Star),
+            "none" => Some(Shape::None),
+            "underline" => Some(Shape::Underline),
+            "cylinder" => Some(Shape::Cylinder),
+            "note" => Some(Shape::Note),
+            "tab" => Some(Shape::Tab),
+            "folder" => Some(Shape::Folder),
+            "box3d" => Some(Shape::Box3D),
+            "component" => Some(Shape::Component),
+            "promoter" => Some(Shape::Promoter),
+            "cds" => Some(Shape::Cds),
+            "terminator" => Some(Shape::Terminator),
+            "utr" => Some(Shape::Utr),
+            "primersite" => Some(Shape::PrimerSite),
+            "restrictionsite" => Some(Shape::RestrictionSite),
+            "fivepoverhang" => Some(Shape::FivePOverhang),
+            "threepoverhang" => Some(Shape::ThreePOverhang),
+            "noverhang" => Some(Shape::NOverhang),
+            "assembly" => Some(Shape::Assembly),
+            "signature" => Some(Shape::Signature),
+            "insulator" => Some(Shape::Insulator),
+            "ribosite" => Some(Shape::RiboSite),
+            "rnastab" => Some(Shape::RnaStab),
+            "proteasesite" => Some(Shape::ProteaseSite),
+            "proteinstab" => Some(Shape::ProteinStab),
+            "rpromoter" => Some(Shape::RPromoter),
+            "rarrow" => Some(Shape::RArrow),
+            "larrow" => Some(Shape::LArrow),
+            "lpromoter" => Some(Shape::LPromoter),
+            "record" => Some(Shape::Record),
+            "Mrecord" => Some(Shape::MRecord),
+            _ => None,
+        }
+    }
 }
 
 impl Display for Shape {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
             Shape::Box => "box",
+            Shape::Polygon => "polygon",
+            Shape::Ellipse => "ellipse",
+            Shape::Oval => "oval",
             Shape::Circle => "circle",
+            Shape::Point => "point",
+            Shape::Egg => "egg",
+            Shape::Triangle => "triangle",
+            Shape::PlainText => "plaintext",
+            Shape::Plain => "plain",
             Shape::Diamond => "diamond",
+            Shape::Trapezium => "trapezium",
+            Shape::Parallelogram => "parallelogram",
+            Shape::House => "house",
+            Shape::Pentagon => "pentagon",
+            Shape::Hexagon => "hexagon",
+            Shape::Septagon => "septagon",
+            Shape::Octagon => "octagon",
             Shape::DoubleCircle => "doublecircle",
-            Shape::Ellipse => "ellipse",
+            Shape::DoubleOctagon => "doubleoctagon",
+            Shape::TripleOctagon => "tripleoctagon",
+            Shape::InvTriangle => "invtriangle",
+            Shape::InvTrapezium => "invtrapezium",
+            Shape::InvHouse => "invhouse",
             Shape::MDiamond => "Mdiamond",
             Shape::MSquare => "Msquare",
+            Shape::MCircle => "Mcircle",
+            Shape::Rect => "rect",
+            Shape::Rectangle => "rectangle",
+            Shape::Square => "square",
+            Shape::Star => "star",
+            Shape::None => "none",
+            Shape::Underline => "underline",
+            Shape::Cylinder => "cylinder",
+            Shape::Note => "note",
+            Shape::Tab => "tab",
+            Shape::Folder => "folder",
+            Shape::Box3D => "box3d",
+            Shape::Component => "component",
+            Shape::Promoter => "promoter",
+            Shape::Cds => "cds",
+            Shape::Terminator => "terminator",
+            Shape::Utr => "utr",
+            Shape::PrimerSite => "primersite",
+            Shape::RestrictionSite => "restrictionsite",
+            Shape::FivePOverhang => "fivepoverhang",
+            Shape::ThreePOverhang => "threepoverhang",
+            Shape::NOverhang => "noverhang",
+            Shape::Assembly => "assembly",
+            Shape::Signature => "signature",
+            Shape::Insulator => "insulator",
+            Shape::RiboSite => "ribosite",
+            Shape::RnaStab => "rnastab",
+            Shape::ProteaseSite => "proteasesite",
+            Shape::ProteinStab => "proteinstab",
+            Shape::RPromoter => "rpromoter",
+            Shape::RArrow => "rarrow",
+            Shape::LArrow => "larrow",
+            Shape::LPromoter => "lpromoter",
+            Shape::Record => "record",
+            Shape::MRecord => "Mrecord",
         };
         f.write_str(s)
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShapeError {
+    TooFewSides(u32),
+}
+
+impl Display for ShapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShapeError::TooFewSides(n) => {
+                write!(f, "a polygon needs at least 3 sides, not {}", n)
+            }
+        }
+    }
+}
+
+impl Error for ShapeError {}
+
+// The parameters of `Shape::Polygon`. Graphviz ignores sides, skew and
+// distortion on every other shape, so they can only be set through
+// `AttributeBuilder::polygon`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Polygon {
+    pub(crate) sides: Option<u32>,
+    pub(crate) skew: Option<f64>,
+    pub(crate) distortion: Option<f64>,
+}
+
+impl Polygon {
+    // e.g. `Polygon::new(|p| p.sides(5).skew(0.4))`
+    pub fn new<F>(f: F) -> Result<Polygon, ShapeError>
+    where
+        F: FnOnce(&mut PolygonBuilder) -> &mut PolygonBuilder,
+    {
+        let mut builder = PolygonBuilder {
+            polygon: Polygon::default(),
+        };
+        f(&mut builder);
+        match builder.polygon.sides {
+            Some(n) if n < 3 => Err(ShapeError::TooFewSides(n)),
+            _ => Ok(builder.polygon),
+        }
+    }
+}
+
+pub struct PolygonBuilder {
+    polygon: Polygon,
+}
+
+impl PolygonBuilder {
+    // Graphviz draws at least 3 sides
+    pub fn sides(&mut self, sides: u32) -> &mut PolygonBuilder {
+        self.polygon.sides = Some(sides);
+        self
+    }
+
+    // Positive values shift the top to the right, negative ones to the left
+    pub fn skew(&mut self, skew: f64) -> &mut PolygonBuilder {
+        self.polygon.skew = Some(skew);
+        self
+    }
+
+    // Positive values make the top wider than the bottom
+    pub fn distortion(&mut self, distortion: f64) -> &mut PolygonBuilder {
+        self.polygon.distortion = Some(distortion);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polygons() {
+        let polygon = Polygon::new(|p| p.sides(5).skew(0.4)).unwrap();
+        assert_eq!((polygon.sides, polygon.skew), (Some(5), Some(0.4)));
+        assert_eq!(Polygon::new(|p| p).unwrap(), Polygon::default());
+        let error = Polygon::new(|p| p.sides(2)).err();
+        assert_eq!(error, Some(ShapeError::TooFewSides(2)));
+        assert_eq!(
+            error.unwrap().to_string(),
+            "a polygon needs at least 3 sides, not 2"
+        );
+    }
+}
@@ -1,10 +1,24 @@
 use std::fmt::Display;
 
+#[derive(Clone)]
 pub struct Size {
     width: f64,
     height: f64,
 }
 
+impl Size {
+    // size is "%f,%f" or a single "%f" applying to both dimensions
+    pub(crate) fn parse(s: &str) -> Option<Size> {
+        match s.split_once(',') {
+            Some((w, h)) => Some(Size {
+                width: w.trim().parse().ok()?,
+                height: h.trim().parse().ok()?,
+            }),
+            None => s.trim().parse::<f64>().ok().map(Size::from),
+        }
+    }
+}
+
 impl Display for Size {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "\"{},{}\"", self.width, self.height)
@@ -0,0 +1,41 @@
+use std::fmt::Display;
+
+// docs
+// * https://graphviz.org/docs/attrs/start/
+// * https://graphviz.org/docs/attr-types/startType/
+//
+// Where neato and fdp put the nodes before improving their positions. The
+// same seed always gives the same drawing.
+#[derive(Clone)]
+pub enum Start {
+    // Evenly spaced on a circle
+    Regular,
+    // Random positions from the given seed. Without one graphviz picks a
+    // seed of its own and the native layouts use a fixed one.
+    Random(Option<u64>),
+}
+
+impl Start {
+    // startType is a style optionally followed by a seed, or the seed on its
+    // own. The "self" style is left to `Attribute::Other`.
+    pub(crate) fn parse(s: &str) -> Option<Start> {
+        if s == "regular" {
+            return Some(Start::Regular);
+        }
+        let seed = s.strip_prefix("random").unwrap_or(s);
+        if seed.is_empty() {
+            return Some(Start::Random(None));
+        }
+        seed.parse().ok().map(|seed| Start::Random(Some(seed)))
+    }
+}
+
+impl Display for Start {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Start::Regular => f.write_str("regular"),
+            Start::Random(None) => f.write_str("random"),
+            Start::Random(Some(seed)) => write!(f, "random{}", seed),
+        }
+    }
+}
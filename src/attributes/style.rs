@@ -25,6 +25,10 @@ pub enum NodeStyle {
     Diagonals,
     Filled,
     Rounded,
+    // Fills a box with the colors of a color list side by side
+    Striped,
+    // Fills an ellipse with the colors of a color list as pie wedges
+    Wedged,
 }
 
 pub enum ClusterStyle {
@@ -34,8 +38,10 @@ pub enum ClusterStyle {
     Filled,
     Rounded,
     Solid,
+    Striped,
 }
 
+#[derive(Clone, PartialEq)]
 pub enum Style {
     Bold,
     Dashed,
@@ -45,6 +51,8 @@ pub enum Style {
     Invisible,
     Rounded,
     Solid,
+    Striped,
+    Wedged,
 }
 
 impl From<EdgeStyle> for Style {
@@ -70,6 +78,8 @@ impl From<NodeStyle> for Style {
             NodeStyle::Diagonals => Style::Diagonals,
             NodeStyle::Filled => Style::Filled,
             NodeStyle::Rounded => Style::Rounded,
+            NodeStyle::Striped => Style::Striped,
+            NodeStyle::Wedged => Style::Wedged,
         }
     }
 }
@@ -83,6 +93,25 @@ impl From<ClusterStyle> for Style {
             ClusterStyle::Filled => Style::Filled,
             ClusterStyle::Rounded => Style::Rounded,
             ClusterStyle::Solid => Style::Solid,
+            ClusterStyle::Striped => Style::Striped,
+        }
+    }
+}
+
+impl Style {
+    pub(crate) fn parse(s: &str) -> Option<Style> {
+        match s {
+            "bold" => Some(Style::Bold),
+            "dashed" => Some(Style::Dashed),
+            "diagonals" => Some(Style::Diagonals),
+            "dotted" => Some(Style::Dotted),
+            "filled" => Some(Style::Filled),
+            "invis" => Some(Style::Invisible),
+            "rounded" => Some(Style::Rounded),
+            "solid" => Some(Style::Solid),
+            "striped" => Some(Style::Striped),
+            "wedged" => Some(Style::Wedged),
+            _ => None,
         }
     }
 }
@@ -98,6 +127,8 @@ impl Display for Style {
             Style::Invisible => "invis",
             Style::Rounded => "rounded",
             Style::Solid => "solid",
+            Style::Striped => "striped",
+            Style::Wedged => "wedged",
         };
         f.write_str(s)
     }
@@ -1,8 +1,15 @@
+use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
 
 use crate::ast;
 use crate::ast::*;
-use crate::attributes::{ClusterStyle, Color, EdgeStyle, NodeStyle, RankDir, Shape, Size, ToLabel};
+use crate::attributes::{
+    Arrow, ClusterStyle, Color, ColorList, ColorScheme, Dir, EdgeStyle, FontNames, Intervals,
+    Label, NodeStyle, Overlap, PackMode, PageDir, Polygon, Position, Rank, RankDir, Shape, Size,
+    Start, ToLabel,
+};
+use crate::strict::merge_edges;
 
 pub struct DirectedGraph;
 pub struct UndirectedGraph;
@@ -26,47 +33,134 @@ impl EntityContext for ClusterContext {}
 
 pub struct DotLayout;
 pub struct NeatoLayout;
+pub struct CircoLayout;
+pub struct FdpLayout;
+pub struct SfdpLayout;
+pub struct TwopiLayout;
+pub struct OsageLayout;
+pub struct PatchworkLayout;
 pub struct UnspecifiedLayout;
 
-pub trait LayoutContext {}
-impl LayoutContext for DotLayout {}
-impl LayoutContext for NeatoLayout {}
-impl LayoutContext for UnspecifiedLayout {}
+// docs
+// * https://graphviz.org/docs/layouts/
+pub trait LayoutContext {
+    // The value of the `layout` graph attribute
+    const ENGINE: Option<&'static str>;
+}
+impl LayoutContext for DotLayout {
+    const ENGINE: Option<&'static str> = Some("dot");
+}
+impl LayoutContext for NeatoLayout {
+    const ENGINE: Option<&'static str> = Some("neato");
+}
+impl LayoutContext for CircoLayout {
+    const ENGINE: Option<&'static str> = Some("circo");
+}
+impl LayoutContext for FdpLayout {
+    const ENGINE: Option<&'static str> = Some("fdp");
+}
+impl LayoutContext for SfdpLayout {
+    const ENGINE: Option<&'static str> = Some("sfdp");
+}
+impl LayoutContext for TwopiLayout {
+    const ENGINE: Option<&'static str> = Some("twopi");
+}
+impl LayoutContext for OsageLayout {
+    const ENGINE: Option<&'static str> = Some("osage");
+}
+impl LayoutContext for PatchworkLayout {
+    const ENGINE: Option<&'static str> = Some("patchwork");
+}
+impl LayoutContext for UnspecifiedLayout {
+    const ENGINE: Option<&'static str> = None;
+}
 
+// docs
+// * https://graphviz.org/docs/outputs/
+//
+// Output formats grouped by the attributes they understand. Formats for
+// other drawing programs (fig, pic, mp, vml, vdx, tk, pov, vrml) have none
+// of their own and are left unspecified.
 pub struct BitmapOutput;
 pub struct PostscriptOutput;
+pub struct PdfOutput;
 pub struct SVGOutput;
+pub struct ImageMapOutput;
+pub struct JsonOutput;
+pub struct XdotOutput;
+pub struct PlainOutput;
+pub struct GexfOutput;
 pub struct UnspecifiedOutput;
 
-pub trait OutputContext {}
-impl OutputContext for BitmapOutput {}
-impl OutputContext for PostscriptOutput {}
-impl OutputContext for SVGOutput {}
-impl OutputContext for UnspecifiedOutput {}
+pub trait OutputContext {
+    // The `-T` format used when rendering without naming one
+    const FORMAT: Option<&'static str>;
+}
+// png, gif, jpg, bmp, tiff, webp, ...
+impl OutputContext for BitmapOutput {
+    const FORMAT: Option<&'static str> = Some("png");
+}
+// ps, ps2, eps
+impl OutputContext for PostscriptOutput {
+    const FORMAT: Option<&'static str> = Some("ps");
+}
+impl OutputContext for PdfOutput {
+    const FORMAT: Option<&'static str> = Some("pdf");
+}
+// svg, svgz
+impl OutputContext for SVGOutput {
+    const FORMAT: Option<&'static str> = Some("svg");
+}
+// cmapx, imap, cmap, ismap
+impl OutputContext for ImageMapOutput {
+    const FORMAT: Option<&'static str> = Some("cmapx");
+}
+// json, json0, dot_json, xdot_json
+impl OutputContext for JsonOutput {
+    const FORMAT: Option<&'static str> = Some("json");
+}
+// xdot, dot, canon
+impl OutputContext for XdotOutput {
+    const FORMAT: Option<&'static str> = Some("xdot");
+}
+// plain, plain-ext
+impl OutputContext for PlainOutput {
+    const FORMAT: Option<&'static str> = Some("plain");
+}
+// Written by `print_gexf`, graphviz has no GEXF output
+impl OutputContext for GexfOutput {
+    const FORMAT: Option<&'static str> = None;
+}
+impl OutputContext for UnspecifiedOutput {
+    const FORMAT: Option<&'static str> = None;
+}
+
+// Outputs that turn URL, href, target and tooltip into links
+pub trait LinkOutput: OutputContext {}
+impl LinkOutput for SVGOutput {}
+impl LinkOutput for ImageMapOutput {}
+
+// Nodes and edges, which come and go in the dynamic graphs of GEXF
+pub trait TimedContext: EntityContext {}
+impl TimedContext for NodeContext {}
+impl TimedContext for EdgeContext {}
 
 pub fn directed() -> GraphBuilder<DirectedGraph, UnspecifiedLayout, UnspecifiedOutput> {
-    GraphBuilder {
-        statements: Vec::new(),
-        graph_type: PhantomData,
-        layout_context: PhantomData,
-        output_context: PhantomData,
-    }
+    GraphBuilder::new()
 }
 
 pub fn undirected() -> GraphBuilder<UndirectedGraph, UnspecifiedLayout, UnspecifiedOutput> {
-    GraphBuilder {
-        statements: Vec::new(),
-        graph_type: PhantomData,
-        layout_context: PhantomData,
-        output_context: PhantomData,
-    }
+    GraphBuilder::new()
 }
 
+// The root graph holds statements like any subgraph or cluster does, through
+// `Deref` to its `StatementBuilder`, and adds the graph ID, strict mode and
+// the layout and output typestates.
 pub struct GraphBuilder<GT: GraphType, LC: LayoutContext, OC: OutputContext> {
-    statements: Vec<Statement>,
+    statements: StatementBuilder<GraphContext, LC, OC>,
+    id: Option<Id>,
+    strict: bool,
     graph_type: PhantomData<GT>,
-    layout_context: PhantomData<LC>,
-    output_context: PhantomData<OC>,
 }
 
 impl<LC, OC> GraphBuilder<DirectedGraph, LC, OC>
@@ -75,12 +169,7 @@ where
     OC: OutputContext,
 {
     pub fn build(self) -> Graph {
-        Graph {
-            strict: false,
-            gtype: ast::GraphType::Directed,
-            id: None,
-            statements: self.statements,
-        }
+        self.build_graph(ast::GraphType::Directed)
     }
 }
 
@@ -90,12 +179,27 @@ where
     OC: OutputContext,
 {
     pub fn build(self) -> Graph {
-        Graph {
-            strict: false,
-            gtype: ast::GraphType::Undirected,
-            id: None,
-            statements: self.statements,
-        }
+        self.build_graph(ast::GraphType::Undirected)
+    }
+}
+
+// SVG graphs can be drawn without graphviz, see `to_svg`. Only builders for
+// SVG offer this, so that the attributes set are the ones SVG takes.
+impl<LC> GraphBuilder<DirectedGraph, LC, SVGOutput>
+where
+    LC: LayoutContext,
+{
+    pub fn build_svg(self) -> String {
+        crate::svg::to_svg(&self.build())
+    }
+}
+
+impl<LC> GraphBuilder<UndirectedGraph, LC, SVGOutput>
+where
+    LC: LayoutContext,
+{
+    pub fn build_svg(self) -> String {
+        crate::svg::to_svg(&self.build())
     }
 }
 
@@ -105,111 +209,83 @@ where
     LC: LayoutContext,
     OC: OutputContext,
 {
-    pub fn edge_attributes<F>(&mut self, f: F) -> &mut GraphBuilder<GT, LC, OC>
-    where
-        F: FnOnce(
-            &mut AttributeBuilder<NodeContext, LC, OC>,
-        ) -> &mut AttributeBuilder<NodeContext, LC, OC>,
-    {
-        let mut attribute_builder: AttributeBuilder<NodeContext, LC, OC> = AttributeBuilder::new();
-        f(&mut attribute_builder);
-        self.statements.push(Statement::Attribute(
-            AttributeScope::Edge,
-            attribute_builder.build(),
-        ));
-        self
+    fn new() -> GraphBuilder<GT, LC, OC> {
+        GraphBuilder {
+            statements: StatementBuilder::new(),
+            id: None,
+            strict: false,
+            graph_type: PhantomData,
+        }
     }
 
-    pub fn node_attributes<F>(&mut self, f: F) -> &mut GraphBuilder<GT, LC, OC>
-    where
-        F: FnOnce(
-            &mut AttributeBuilder<NodeContext, LC, OC>,
-        ) -> &mut AttributeBuilder<NodeContext, LC, OC>,
-    {
-        let mut attribute_builder: AttributeBuilder<NodeContext, LC, OC> = AttributeBuilder::new();
-        f(&mut attribute_builder);
-        self.statements.push(Statement::Attribute(
-            AttributeScope::Node,
-            attribute_builder.build(),
-        ));
-        self
+    fn build_graph(self, gtype: ast::GraphType) -> Graph {
+        let mut statements = self.statements.build();
+        if let Some(engine) = LC::ENGINE {
+            let layout = Attribute::Other(Id::new("layout"), Id::new(engine));
+            statements.insert(0, Statement::Attribute(AttributeScope::Graph, vec![layout]));
+        }
+        let statements = if self.strict {
+            merge_edges(statements, matches!(gtype, ast::GraphType::Directed))
+        } else {
+            statements
+        };
+        Graph {
+            strict: self.strict,
+            gtype,
+            id: self.id,
+            statements,
+            engine: LC::ENGINE,
+            format: OC::FORMAT,
+        }
     }
 
-    pub fn graph_attributes<F>(&mut self, f: F) -> &mut GraphBuilder<GT, LC, OC>
-    where
-        F: FnOnce(
-            &mut AttributeBuilder<GraphContext, LC, OC>,
-        ) -> &mut AttributeBuilder<GraphContext, LC, OC>,
-    {
-        let mut attribute_builder: AttributeBuilder<GraphContext, LC, OC> = AttributeBuilder::new();
-        f(&mut attribute_builder);
-        self.statements.push(Statement::Attribute(
-            AttributeScope::Graph,
-            attribute_builder.build(),
-        ));
-        self
+    // Moves the graph into another layout or output context
+    fn retype<L: LayoutContext, O: OutputContext>(self) -> GraphBuilder<GT, L, O> {
+        GraphBuilder {
+            statements: self.statements.retype(),
+            id: self.id,
+            strict: self.strict,
+            graph_type: PhantomData,
+        }
     }
 
-    pub fn node<T: ToId, F>(&mut self, id: T, f: F) -> &mut GraphBuilder<GT, LC, OC>
-    where
-        F: FnOnce(
-            &mut AttributeBuilder<NodeContext, LC, OC>,
-        ) -> &mut AttributeBuilder<NodeContext, LC, OC>,
-    {
-        let mut attribute_builder: AttributeBuilder<NodeContext, LC, OC> = AttributeBuilder::new();
-        f(&mut attribute_builder);
-        self.statements.push(Statement::Node {
-            id: id.to_id(),
-            attributes: attribute_builder.build(),
-        });
+    // Like `strict`, this belongs to the root graph alone, so it chains before
+    // statements but not after them: those return the `StatementBuilder` that
+    // subgraphs share. Later on, call it on the builder in a statement of its
+    // own.
+    pub fn id<T: ToId>(&mut self, id: T) -> &mut GraphBuilder<GT, LC, OC> {
+        self.id = Some(id.to_id());
         self
     }
 
-    pub fn node_<T: ToId>(&mut self, id: T) -> &mut GraphBuilder<GT, LC, OC> {
-        self.statements.push(Statement::Node {
-            id: id.to_id(),
-            attributes: Vec::new(),
-        });
+    // At most one edge between two nodes, duplicates are merged into the first
+    pub fn strict(&mut self) -> &mut GraphBuilder<GT, LC, OC> {
+        self.strict = true;
         self
     }
+}
 
-    pub fn edge<T: ToId, F>(&mut self, from: T, to: T, f: F) -> &mut GraphBuilder<GT, LC, OC>
-    where
-        F: FnOnce(
-            &mut AttributeBuilder<EdgeContext, LC, OC>,
-        ) -> &mut AttributeBuilder<EdgeContext, LC, OC>,
-    {
-        let mut attribute_builder: AttributeBuilder<EdgeContext, LC, OC> = AttributeBuilder::new();
-        f(&mut attribute_builder);
-        self.statements.push(Statement::Edge {
-            from: from.to_id(),
-            to: to.to_id(),
-            attributes: attribute_builder.build(),
-        });
-        self
-    }
+impl<GT, LC, OC> Deref for GraphBuilder<GT, LC, OC>
+where
+    GT: GraphType,
+    LC: LayoutContext,
+    OC: OutputContext,
+{
+    type Target = StatementBuilder<GraphContext, LC, OC>;
 
-    pub fn edge_<T: ToId>(&mut self, from: T, to: T) -> &mut GraphBuilder<GT, LC, OC> {
-        self.statements.push(Statement::Edge {
-            from: from.to_id(),
-            to: to.to_id(),
-            attributes: Vec::new(),
-        });
-        self
+    fn deref(&self) -> &StatementBuilder<GraphContext, LC, OC> {
+        &self.statements
     }
+}
 
-    pub fn cluster<T: ToId, F>(&mut self, id: T, f: F) -> &mut GraphBuilder<GT, LC, OC>
-    where
-        F: FnOnce(&mut StatementBuilder<LC, OC>) -> &mut StatementBuilder<LC, OC>,
-    {
-        let mut statement_builder: StatementBuilder<LC, OC> = StatementBuilder::new();
-        f(&mut statement_builder);
-
-        self.statements.push(Statement::Subgraph {
-            id: Some(Id(format!("cluster_{}", id.to_id().get()))),
-            statements: statement_builder.build(),
-        });
-        self
+impl<GT, LC, OC> DerefMut for GraphBuilder<GT, LC, OC>
+where
+    GT: GraphType,
+    LC: LayoutContext,
+    OC: OutputContext,
+{
+    fn deref_mut(&mut self) -> &mut StatementBuilder<GraphContext, LC, OC> {
+        &mut self.statements
     }
 }
 
@@ -219,21 +295,39 @@ where
     LC: LayoutContext,
 {
     pub fn bitmap(self) -> GraphBuilder<GT, LC, BitmapOutput> {
-        GraphBuilder {
-            statements: self.statements,
-            graph_type: self.graph_type,
-            layout_context: self.layout_context,
-            output_context: PhantomData,
-        }
+        self.retype()
     }
 
     pub fn svg(self) -> GraphBuilder<GT, LC, SVGOutput> {
-        GraphBuilder {
-            statements: self.statements,
-            graph_type: self.graph_type,
-            layout_context: self.layout_context,
-            output_context: PhantomData,
-        }
+        self.retype()
+    }
+
+    pub fn postscript(self) -> GraphBuilder<GT, LC, PostscriptOutput> {
+        self.retype()
+    }
+
+    pub fn pdf(self) -> GraphBuilder<GT, LC, PdfOutput> {
+        self.retype()
+    }
+
+    pub fn image_map(self) -> GraphBuilder<GT, LC, ImageMapOutput> {
+        self.retype()
+    }
+
+    pub fn json(self) -> GraphBuilder<GT, LC, JsonOutput> {
+        self.retype()
+    }
+
+    pub fn xdot(self) -> GraphBuilder<GT, LC, XdotOutput> {
+        self.retype()
+    }
+
+    pub fn plain(self) -> GraphBuilder<GT, LC, PlainOutput> {
+        self.retype()
+    }
+
+    pub fn gexf(self) -> GraphBuilder<GT, LC, GexfOutput> {
+        self.retype()
     }
 }
 
@@ -243,38 +337,59 @@ where
     OC: OutputContext,
 {
     pub fn dot(self) -> GraphBuilder<GT, DotLayout, OC> {
-        GraphBuilder {
-            statements: self.statements,
-            graph_type: self.graph_type,
-            layout_context: PhantomData,
-            output_context: self.output_context,
-        }
+        self.retype()
     }
 
     pub fn neato(self) -> GraphBuilder<GT, NeatoLayout, OC> {
-        GraphBuilder {
-            statements: self.statements,
-            graph_type: self.graph_type,
-            layout_context: PhantomData,
-            output_context: self.output_context,
-        }
+        self.retype()
+    }
+
+    pub fn circo(self) -> GraphBuilder<GT, CircoLayout, OC> {
+        self.retype()
+    }
+
+    pub fn fdp(self) -> GraphBuilder<GT, FdpLayout, OC> {
+        self.retype()
+    }
+
+    pub fn sfdp(self) -> GraphBuilder<GT, SfdpLayout, OC> {
+        self.retype()
+    }
+
+    pub fn twopi(self) -> GraphBuilder<GT, TwopiLayout, OC> {
+        self.retype()
+    }
+
+    pub fn osage(self) -> GraphBuilder<GT, OsageLayout, OC> {
+        self.retype()
+    }
+
+    pub fn patchwork(self) -> GraphBuilder<GT, PatchworkLayout, OC> {
+        self.retype()
     }
 }
 
-pub struct StatementBuilder<LC: LayoutContext, OC: OutputContext> {
+// The statements of a graph, subgraph or cluster. The entity context decides
+// which attributes `graph_attributes` offers.
+pub struct StatementBuilder<EC: EntityContext, LC: LayoutContext, OC: OutputContext> {
     statements: Vec<Statement>,
+    ports: HashMap<Id, Vec<String>>,
+    entity_context: PhantomData<EC>,
     layout_context: PhantomData<LC>,
     output_context: PhantomData<OC>,
 }
 
-impl<LC, OC> StatementBuilder<LC, OC>
+impl<EC, LC, OC> StatementBuilder<EC, LC, OC>
 where
+    EC: EntityContext,
     LC: LayoutContext,
     OC: OutputContext,
 {
-    fn new() -> StatementBuilder<LC, OC> {
+    fn new() -> StatementBuilder<EC, LC, OC> {
         StatementBuilder {
             statements: Vec::new(),
+            ports: HashMap::new(),
+            entity_context: PhantomData,
             layout_context: PhantomData,
             output_context: PhantomData,
         }
@@ -284,13 +399,21 @@ where
         self.statements
     }
 
-    pub fn graph_attributes<F>(&mut self, f: F) -> &mut StatementBuilder<LC, OC>
+    fn retype<L: LayoutContext, O: OutputContext>(self) -> StatementBuilder<EC, L, O> {
+        StatementBuilder {
+            statements: self.statements,
+            ports: self.ports,
+            entity_context: PhantomData,
+            layout_context: PhantomData,
+            output_context: PhantomData,
+        }
+    }
+
+    pub fn graph_attributes<F>(&mut self, f: F) -> &mut StatementBuilder<EC, LC, OC>
     where
-        F: FnOnce(
-            &mut AttributeBuilder<GraphContext, LC, OC>,
-        ) -> &mut AttributeBuilder<GraphContext, LC, OC>,
+        F: FnOnce(&mut AttributeBuilder<EC, LC, OC>) -> &mut AttributeBuilder<EC, LC, OC>,
     {
-        let mut attribute_builder: AttributeBuilder<GraphContext, LC, OC> = AttributeBuilder::new();
+        let mut attribute_builder: AttributeBuilder<EC, LC, OC> = AttributeBuilder::new();
         f(&mut attribute_builder);
         self.statements.push(Statement::Attribute(
             AttributeScope::Graph,
@@ -299,7 +422,7 @@ where
         self
     }
 
-    pub fn node_attributes<F>(&mut self, f: F) -> &mut StatementBuilder<LC, OC>
+    pub fn node_attributes<F>(&mut self, f: F) -> &mut StatementBuilder<EC, LC, OC>
     where
         F: FnOnce(
             &mut AttributeBuilder<NodeContext, LC, OC>,
@@ -314,7 +437,22 @@ where
         self
     }
 
-    pub fn node<T: ToId, F>(&mut self, id: T, f: F) -> &mut StatementBuilder<LC, OC>
+    pub fn edge_attributes<F>(&mut self, f: F) -> &mut StatementBuilder<EC, LC, OC>
+    where
+        F: FnOnce(
+            &mut AttributeBuilder<EdgeContext, LC, OC>,
+        ) -> &mut AttributeBuilder<EdgeContext, LC, OC>,
+    {
+        let mut attribute_builder: AttributeBuilder<EdgeContext, LC, OC> = AttributeBuilder::new();
+        f(&mut attribute_builder);
+        self.statements.push(Statement::Attribute(
+            AttributeScope::Edge,
+            attribute_builder.build(),
+        ));
+        self
+    }
+
+    pub fn node<T: ToId, F>(&mut self, id: T, f: F) -> &mut StatementBuilder<EC, LC, OC>
     where
         F: FnOnce(
             &mut AttributeBuilder<NodeContext, LC, OC>,
@@ -322,14 +460,35 @@ where
     {
         let mut attribute_builder: AttributeBuilder<NodeContext, LC, OC> = AttributeBuilder::new();
         f(&mut attribute_builder);
-        self.statements.push(Statement::Node {
-            id: id.to_id(),
-            attributes: attribute_builder.build(),
-        });
+        let id = id.to_id();
+        let attributes = attribute_builder.build();
+        record_ports(&mut self.ports, &id, &attributes);
+        self.statements.push(Statement::Node { id, attributes });
         self
     }
 
-    pub fn edge<T: ToId, F>(&mut self, from: T, to: T, f: F) -> &mut StatementBuilder<LC, OC>
+    pub fn node_<T: ToId>(&mut self, id: T) -> &mut StatementBuilder<EC, LC, OC> {
+        self.node(id, |ab| ab)
+    }
+
+    // The endpoint for a port on the record or HTML label of a node, if the
+    // node was given a label with that port
+    pub fn port<T: ToId>(&self, node: T, port: &str) -> Option<NodeId> {
+        let id = node.to_id();
+        let ports = self.ports.get(&id)?;
+        if ports.iter().any(|p| p == port) {
+            Some(NodeId::new(id).port(port))
+        } else {
+            None
+        }
+    }
+
+    pub fn edge<S: ToNodeId, T: ToNodeId, F>(
+        &mut self,
+        from: S,
+        to: T,
+        f: F,
+    ) -> &mut StatementBuilder<EC, LC, OC>
     where
         F: FnOnce(
             &mut AttributeBuilder<EdgeContext, LC, OC>,
@@ -338,52 +497,243 @@ where
         let mut attribute_builder: AttributeBuilder<EdgeContext, LC, OC> = AttributeBuilder::new();
         f(&mut attribute_builder);
         self.statements.push(Statement::Edge {
-            from: from.to_id(),
-            to: to.to_id(),
+            from: from.to_node_id(),
+            to: to.to_node_id(),
             attributes: attribute_builder.build(),
         });
         self
     }
 
-    pub fn edge_<T: ToId>(&mut self, from: T, to: T) -> &mut StatementBuilder<LC, OC> {
+    pub fn edge_<S: ToNodeId, T: ToNodeId>(
+        &mut self,
+        from: S,
+        to: T,
+    ) -> &mut StatementBuilder<EC, LC, OC> {
         self.statements.push(Statement::Edge {
-            from: from.to_id(),
-            to: to.to_id(),
+            from: from.to_node_id(),
+            to: to.to_node_id(),
             attributes: Vec::new(),
         });
         self
     }
-}
-
-pub struct AttributeBuilder<EC: EntityContext, LC: LayoutContext, OC: OutputContext> {
-    attributes: Vec<Attribute>,
-    entity_context: PhantomData<EC>,
-    layout_context: PhantomData<LC>,
-    output_context: PhantomData<OC>,
-}
 
-impl<EC, LC, OC> AttributeBuilder<EC, LC, OC>
-where
-    EC: EntityContext,
-    LC: LayoutContext,
-    OC: OutputContext,
-{
-    fn new() -> AttributeBuilder<EC, LC, OC> {
-        AttributeBuilder {
-            attributes: Vec::new(),
-            entity_context: PhantomData,
-            layout_context: PhantomData,
-            output_context: PhantomData,
-        }
+    // a -> b -> c, with every edge sharing the attributes. The first two nodes
+    // are taken apart so that every chain makes at least one edge.
+    pub fn edge_chain<S, T, U, I, F>(
+        &mut self,
+        from: S,
+        to: T,
+        rest: I,
+        f: F,
+    ) -> &mut StatementBuilder<EC, LC, OC>
+    where
+        S: ToNodeId,
+        T: ToNodeId,
+        U: ToNodeId,
+        I: IntoIterator<Item = U>,
+        F: FnOnce(
+            &mut AttributeBuilder<EdgeContext, LC, OC>,
+        ) -> &mut AttributeBuilder<EdgeContext, LC, OC>,
+    {
+        let mut targets = vec![
+            EdgeTarget::Node(from.to_node_id()),
+            EdgeTarget::Node(to.to_node_id()),
+        ];
+        targets.extend(
+            rest.into_iter()
+                .map(|node| EdgeTarget::Node(node.to_node_id())),
+        );
+        let mut attribute_builder: AttributeBuilder<EdgeContext, LC, OC> = AttributeBuilder::new();
+        f(&mut attribute_builder);
+        self.statements.push(Statement::EdgeChain {
+            targets,
+            attributes: attribute_builder.build(),
+        });
+        self
     }
 
-    fn build(self) -> Vec<Attribute> {
-        self.attributes
+    pub fn edge_chain_<S, T, U, I>(
+        &mut self,
+        from: S,
+        to: T,
+        rest: I,
+    ) -> &mut StatementBuilder<EC, LC, OC>
+    where
+        S: ToNodeId,
+        T: ToNodeId,
+        U: ToNodeId,
+        I: IntoIterator<Item = U>,
+    {
+        self.edge_chain(from, to, rest, |ab| ab)
     }
 
-    pub fn label<T: ToLabel>(&mut self, label: T) -> &mut AttributeBuilder<EC, LC, OC> {
-        self.attributes.push(Attribute::Label(label.to_label()));
-        self
+    // {a b} -> {c d}, an edge from every node in `from` to every node in `to`
+    pub fn edge_group<S, T, I, J, F>(
+        &mut self,
+        from: I,
+        to: J,
+        f: F,
+    ) -> &mut StatementBuilder<EC, LC, OC>
+    where
+        S: ToId,
+        T: ToId,
+        I: IntoIterator<Item = S>,
+        J: IntoIterator<Item = T>,
+        F: FnOnce(
+            &mut AttributeBuilder<EdgeContext, LC, OC>,
+        ) -> &mut AttributeBuilder<EdgeContext, LC, OC>,
+    {
+        let mut attribute_builder: AttributeBuilder<EdgeContext, LC, OC> = AttributeBuilder::new();
+        f(&mut attribute_builder);
+        self.statements.push(Statement::EdgeChain {
+            targets: vec![group_target(from), group_target(to)],
+            attributes: attribute_builder.build(),
+        });
+        self
+    }
+
+    pub fn edge_group_<S, T, I, J>(&mut self, from: I, to: J) -> &mut StatementBuilder<EC, LC, OC>
+    where
+        S: ToId,
+        T: ToId,
+        I: IntoIterator<Item = S>,
+        J: IntoIterator<Item = T>,
+    {
+        self.edge_group(from, to, |ab| ab)
+    }
+
+    pub fn cluster<T: ToId, F>(&mut self, id: T, f: F) -> &mut StatementBuilder<EC, LC, OC>
+    where
+        F: FnOnce(
+            &mut StatementBuilder<ClusterContext, LC, OC>,
+        ) -> &mut StatementBuilder<ClusterContext, LC, OC>,
+    {
+        let id = Id::from_dot(format!("cluster_{}", id.to_id().get()));
+        self.push_subgraph(Some(id), f)
+    }
+
+    // A subgraph that is not drawn, for grouping nodes or sharing attributes
+    pub fn subgraph<T: ToId, F>(&mut self, id: T, f: F) -> &mut StatementBuilder<EC, LC, OC>
+    where
+        F: FnOnce(
+            &mut StatementBuilder<SubgraphContext, LC, OC>,
+        ) -> &mut StatementBuilder<SubgraphContext, LC, OC>,
+    {
+        self.push_subgraph(Some(id.to_id()), f)
+    }
+
+    // { ... }
+    pub fn anonymous_subgraph<F>(&mut self, f: F) -> &mut StatementBuilder<EC, LC, OC>
+    where
+        F: FnOnce(
+            &mut StatementBuilder<SubgraphContext, LC, OC>,
+        ) -> &mut StatementBuilder<SubgraphContext, LC, OC>,
+    {
+        self.push_subgraph(None, f)
+    }
+
+    fn push_subgraph<C: EntityContext, F>(
+        &mut self,
+        id: Option<Id>,
+        f: F,
+    ) -> &mut StatementBuilder<EC, LC, OC>
+    where
+        F: FnOnce(&mut StatementBuilder<C, LC, OC>) -> &mut StatementBuilder<C, LC, OC>,
+    {
+        let mut statement_builder: StatementBuilder<C, LC, OC> = StatementBuilder::new();
+        f(&mut statement_builder);
+        self.ports.extend(statement_builder.ports.drain());
+
+        self.statements.push(Statement::Subgraph {
+            id,
+            statements: statement_builder.build(),
+        });
+        self
+    }
+}
+
+impl<EC, OC> StatementBuilder<EC, DotLayout, OC>
+where
+    EC: EntityContext,
+    OC: OutputContext,
+{
+    // { rank=same; a; b; }
+    pub fn rank_group<T: ToId, I: IntoIterator<Item = T>>(
+        &mut self,
+        rank: Rank,
+        nodes: I,
+    ) -> &mut StatementBuilder<EC, DotLayout, OC> {
+        self.anonymous_subgraph(|builder| {
+            builder.graph_attributes(|builder| builder.rank(rank));
+            for node in nodes {
+                builder.node_(node);
+            }
+            builder
+        })
+    }
+}
+
+// A single node stays a node, several become an anonymous subgraph
+fn group_target<T: ToId, I: IntoIterator<Item = T>>(nodes: I) -> EdgeTarget {
+    let mut statements: Vec<Statement> = nodes
+        .into_iter()
+        .map(|node| Statement::Node {
+            id: node.to_id(),
+            attributes: Vec::new(),
+        })
+        .collect();
+    if statements.len() == 1 {
+        if let Some(Statement::Node { id, .. }) = statements.pop() {
+            return EdgeTarget::Node(id.into());
+        }
+    }
+    EdgeTarget::Subgraph {
+        id: None,
+        statements,
+    }
+}
+
+// Remembers the ports of record and HTML labels so that edges can address them
+fn record_ports(ports: &mut HashMap<Id, Vec<String>>, id: &Id, attributes: &[Attribute]) {
+    for attribute in attributes {
+        let label_ports = match attribute {
+            Attribute::Label(Label::Record(record)) => record.ports(),
+            Attribute::Label(Label::Html(html)) => html.ports(),
+            _ => continue,
+        };
+        ports.insert(id.clone(), label_ports.to_vec());
+    }
+}
+
+pub struct AttributeBuilder<EC: EntityContext, LC: LayoutContext, OC: OutputContext> {
+    attributes: Vec<Attribute>,
+    entity_context: PhantomData<EC>,
+    layout_context: PhantomData<LC>,
+    output_context: PhantomData<OC>,
+}
+
+impl<EC, LC, OC> AttributeBuilder<EC, LC, OC>
+where
+    EC: EntityContext,
+    LC: LayoutContext,
+    OC: OutputContext,
+{
+    fn new() -> AttributeBuilder<EC, LC, OC> {
+        AttributeBuilder {
+            attributes: Vec::new(),
+            entity_context: PhantomData,
+            layout_context: PhantomData,
+            output_context: PhantomData,
+        }
+    }
+
+    fn build(self) -> Vec<Attribute> {
+        self.attributes
+    }
+
+    pub fn label<T: ToLabel>(&mut self, label: T) -> &mut AttributeBuilder<EC, LC, OC> {
+        self.attributes.push(Attribute::Label(label.to_label()));
+        self
     }
 }
 
@@ -392,10 +742,33 @@ where
     LC: LayoutContext,
     OC: OutputContext,
 {
+    // The scheme that bare color names and indices are looked up in
+    pub fn colorscheme(
+        &mut self,
+        scheme: ColorScheme,
+    ) -> &mut AttributeBuilder<GraphContext, LC, OC> {
+        self.attributes.push(Attribute::ColorScheme(scheme));
+        self
+    }
+
     pub fn size(&mut self, size: Size) -> &mut AttributeBuilder<GraphContext, LC, OC> {
         self.attributes.push(Attribute::Size(size));
         self
     }
+
+    // Inherited by every cluster that sets no color of its own
+    pub fn color<T: Into<ColorList>>(
+        &mut self,
+        color: T,
+    ) -> &mut AttributeBuilder<GraphContext, LC, OC> {
+        self.attributes.push(Attribute::Color(color.into()));
+        self
+    }
+
+    pub fn style(&mut self, style: ClusterStyle) -> &mut AttributeBuilder<GraphContext, LC, OC> {
+        self.attributes.push(Attribute::Style(style.into()));
+        self
+    }
 }
 
 impl<OC> AttributeBuilder<GraphContext, DotLayout, OC>
@@ -416,23 +789,95 @@ where
     LC: LayoutContext,
     OC: OutputContext,
 {
+    // The scheme that bare color names and indices are looked up in
+    pub fn colorscheme(
+        &mut self,
+        scheme: ColorScheme,
+    ) -> &mut AttributeBuilder<EdgeContext, LC, OC> {
+        self.attributes.push(Attribute::ColorScheme(scheme));
+        self
+    }
+
+    // Several colors draw parallel lines, or segments when weighted
+    pub fn color<T: Into<ColorList>>(
+        &mut self,
+        color: T,
+    ) -> &mut AttributeBuilder<EdgeContext, LC, OC> {
+        self.attributes.push(Attribute::Color(color.into()));
+        self
+    }
+
+    pub fn arrowhead(&mut self, arrow: Arrow) -> &mut AttributeBuilder<EdgeContext, LC, OC> {
+        self.attributes.push(Attribute::ArrowHead(arrow));
+        self
+    }
+
+    pub fn arrowtail(&mut self, arrow: Arrow) -> &mut AttributeBuilder<EdgeContext, LC, OC> {
+        self.attributes.push(Attribute::ArrowTail(arrow));
+        self
+    }
+
+    // A multiplier on the default arrow size
+    pub fn arrowsize(&mut self, size: f64) -> &mut AttributeBuilder<EdgeContext, LC, OC> {
+        self.attributes.push(Attribute::ArrowSize(size));
+        self
+    }
+
+    // Which ends get arrows, by default the head in directed graphs and
+    // neither end in undirected ones
+    pub fn dir(&mut self, dir: Dir) -> &mut AttributeBuilder<EdgeContext, LC, OC> {
+        self.attributes.push(Attribute::Dir(dir));
+        self
+    }
+
     pub fn style(&mut self, style: EdgeStyle) -> &mut AttributeBuilder<EdgeContext, LC, OC> {
         self.attributes.push(Attribute::Style(style.into()));
         self
     }
 }
 
-impl<LC, OC> AttributeBuilder<GraphContext, LC, OC>
+impl<OC> AttributeBuilder<SubgraphContext, DotLayout, OC>
+where
+    OC: OutputContext,
+{
+    pub fn rank(&mut self, rank: Rank) -> &mut AttributeBuilder<SubgraphContext, DotLayout, OC> {
+        self.attributes.push(Attribute::Rank(rank));
+        self
+    }
+}
+
+impl<LC, OC> AttributeBuilder<ClusterContext, LC, OC>
 where
     LC: LayoutContext,
     OC: OutputContext,
 {
-    pub fn color(&mut self, color: Color) -> &mut AttributeBuilder<GraphContext, LC, OC> {
-        self.attributes.push(Attribute::Color(color));
+    // The scheme that bare color names and indices are looked up in
+    pub fn colorscheme(
+        &mut self,
+        scheme: ColorScheme,
+    ) -> &mut AttributeBuilder<ClusterContext, LC, OC> {
+        self.attributes.push(Attribute::ColorScheme(scheme));
         self
     }
 
-    pub fn style(&mut self, style: ClusterStyle) -> &mut AttributeBuilder<GraphContext, LC, OC> {
+    pub fn color<T: Into<ColorList>>(
+        &mut self,
+        color: T,
+    ) -> &mut AttributeBuilder<ClusterContext, LC, OC> {
+        self.attributes.push(Attribute::Color(color.into()));
+        self
+    }
+
+    // A color list with `ClusterStyle::Striped`, or a gradient
+    pub fn fillcolor<T: Into<ColorList>>(
+        &mut self,
+        color: T,
+    ) -> &mut AttributeBuilder<ClusterContext, LC, OC> {
+        self.attributes.push(Attribute::FillColor(color.into()));
+        self
+    }
+
+    pub fn style(&mut self, style: ClusterStyle) -> &mut AttributeBuilder<ClusterContext, LC, OC> {
         self.attributes.push(Attribute::Style(style.into()));
         self
     }
@@ -443,8 +888,30 @@ where
     LC: LayoutContext,
     OC: OutputContext,
 {
-    pub fn color(&mut self, color: Color) -> &mut AttributeBuilder<NodeContext, LC, OC> {
-        self.attributes.push(Attribute::Color(color));
+    // The scheme that bare color names and indices are looked up in
+    pub fn colorscheme(
+        &mut self,
+        scheme: ColorScheme,
+    ) -> &mut AttributeBuilder<NodeContext, LC, OC> {
+        self.attributes.push(Attribute::ColorScheme(scheme));
+        self
+    }
+
+    pub fn color<T: Into<ColorList>>(
+        &mut self,
+        color: T,
+    ) -> &mut AttributeBuilder<NodeContext, LC, OC> {
+        self.attributes.push(Attribute::Color(color.into()));
+        self
+    }
+
+    // A color list with `NodeStyle::Striped` or `NodeStyle::Wedged`, or a
+    // gradient
+    pub fn fillcolor<T: Into<ColorList>>(
+        &mut self,
+        color: T,
+    ) -> &mut AttributeBuilder<NodeContext, LC, OC> {
+        self.attributes.push(Attribute::FillColor(color.into()));
         self
     }
 
@@ -453,6 +920,36 @@ where
         self
     }
 
+    // shape=polygon together with the parameters only it takes into account
+    pub fn polygon(&mut self, polygon: Polygon) -> &mut AttributeBuilder<NodeContext, LC, OC> {
+        self.attributes.push(Attribute::Shape(Shape::Polygon));
+        let parameters = vec![
+            polygon.sides.map(Attribute::Sides),
+            polygon.skew.map(Attribute::Skew),
+            polygon.distortion.map(Attribute::Distortion),
+        ];
+        self.attributes.extend(parameters.into_iter().flatten());
+        self
+    }
+
+    // Rotation in degrees
+    pub fn orientation(&mut self, degrees: f64) -> &mut AttributeBuilder<NodeContext, LC, OC> {
+        self.attributes.push(Attribute::Orientation(degrees));
+        self
+    }
+
+    // Equal width and height, and for polygons equal sides and angles
+    pub fn regular(&mut self, regular: bool) -> &mut AttributeBuilder<NodeContext, LC, OC> {
+        self.attributes.push(Attribute::Regular(regular));
+        self
+    }
+
+    // The number of outlines drawn around the node, 0 for none
+    pub fn peripheries(&mut self, peripheries: u32) -> &mut AttributeBuilder<NodeContext, LC, OC> {
+        self.attributes.push(Attribute::Peripheries(peripheries));
+        self
+    }
+
     pub fn style(&mut self, style: NodeStyle) -> &mut AttributeBuilder<NodeContext, LC, OC> {
         self.attributes.push(Attribute::Style(style.into()));
         self
@@ -469,9 +966,350 @@ where
     }
 }
 
+impl<OC> AttributeBuilder<NodeContext, NeatoLayout, OC>
+where
+    OC: OutputContext,
+{
+    // The initial position in inches, or the final one if pinned
+    pub fn pos<T: Into<Position>>(
+        &mut self,
+        position: T,
+    ) -> &mut AttributeBuilder<NodeContext, NeatoLayout, OC> {
+        self.attributes.push(Attribute::Pos(position.into()));
+        self
+    }
+
+    // Keeps the node at its `pos`
+    pub fn pin(&mut self, pin: bool) -> &mut AttributeBuilder<NodeContext, NeatoLayout, OC> {
+        self.attributes.push(Attribute::Pin(pin));
+        self
+    }
+}
+
+impl<OC> AttributeBuilder<GraphContext, NeatoLayout, OC>
+where
+    OC: OutputContext,
+{
+    pub fn overlap(
+        &mut self,
+        overlap: Overlap,
+    ) -> &mut AttributeBuilder<GraphContext, NeatoLayout, OC> {
+        self.attributes.push(Attribute::Overlap(overlap));
+        self
+    }
+
+    // The initial placement of nodes without a `pos` and its seed
+    pub fn start(&mut self, start: Start) -> &mut AttributeBuilder<GraphContext, NeatoLayout, OC> {
+        self.attributes.push(Attribute::Start(start));
+        self
+    }
+}
+
+impl<OC> AttributeBuilder<GraphContext, CircoLayout, OC>
+where
+    OC: OutputContext,
+{
+    // Minimum distance between nodes on the circle
+    pub fn mindist(
+        &mut self,
+        distance: f64,
+    ) -> &mut AttributeBuilder<GraphContext, CircoLayout, OC> {
+        self.attributes.push(Attribute::MinDist(distance));
+        self
+    }
+
+    // The node to put on the circle of each component first
+    pub fn root<T: ToId>(
+        &mut self,
+        node: T,
+    ) -> &mut AttributeBuilder<GraphContext, CircoLayout, OC> {
+        self.attributes.push(Attribute::Root(node.to_id()));
+        self
+    }
+}
+
+impl<OC> AttributeBuilder<GraphContext, FdpLayout, OC>
+where
+    OC: OutputContext,
+{
+    // Ideal edge length in inches
+    #[allow(non_snake_case)]
+    pub fn K(&mut self, k: f64) -> &mut AttributeBuilder<GraphContext, FdpLayout, OC> {
+        self.attributes.push(Attribute::K(k));
+        self
+    }
+}
+
+impl<OC> AttributeBuilder<GraphContext, SfdpLayout, OC>
+where
+    OC: OutputContext,
+{
+    // Ideal edge length in inches
+    #[allow(non_snake_case)]
+    pub fn K(&mut self, k: f64) -> &mut AttributeBuilder<GraphContext, SfdpLayout, OC> {
+        self.attributes.push(Attribute::K(k));
+        self
+    }
+
+    // Strength of the repulsion between nodes, relative to the attraction
+    // along edges
+    pub fn repulsiveforce(
+        &mut self,
+        force: f64,
+    ) -> &mut AttributeBuilder<GraphContext, SfdpLayout, OC> {
+        self.attributes.push(Attribute::RepulsiveForce(force));
+        self
+    }
+}
+
+impl<OC> AttributeBuilder<GraphContext, TwopiLayout, OC>
+where
+    OC: OutputContext,
+{
+    // The node at the center of the layout
+    pub fn root<T: ToId>(
+        &mut self,
+        node: T,
+    ) -> &mut AttributeBuilder<GraphContext, TwopiLayout, OC> {
+        self.attributes.push(Attribute::Root(node.to_id()));
+        self
+    }
+
+    // Distance in inches between concentric circles
+    pub fn ranksep(
+        &mut self,
+        separation: f64,
+    ) -> &mut AttributeBuilder<GraphContext, TwopiLayout, OC> {
+        self.attributes.push(Attribute::RankSep(separation));
+        self
+    }
+}
+
+impl<OC> AttributeBuilder<GraphContext, OsageLayout, OC>
+where
+    OC: OutputContext,
+{
+    // How the top level clusters are packed
+    pub fn packmode(
+        &mut self,
+        mode: PackMode,
+    ) -> &mut AttributeBuilder<GraphContext, OsageLayout, OC> {
+        self.attributes.push(Attribute::PackMode(mode));
+        self
+    }
+}
+
+impl<OC> AttributeBuilder<ClusterContext, OsageLayout, OC>
+where
+    OC: OutputContext,
+{
+    // How the clusters inside this one are packed
+    pub fn packmode(
+        &mut self,
+        mode: PackMode,
+    ) -> &mut AttributeBuilder<ClusterContext, OsageLayout, OC> {
+        self.attributes.push(Attribute::PackMode(mode));
+        self
+    }
+}
+
+impl<OC> AttributeBuilder<NodeContext, PatchworkLayout, OC>
+where
+    OC: OutputContext,
+{
+    // The area of the node's rectangle, in square inches
+    pub fn area(&mut self, area: f64) -> &mut AttributeBuilder<NodeContext, PatchworkLayout, OC> {
+        self.attributes.push(Attribute::Area(area));
+        self
+    }
+}
+
+impl<OC> AttributeBuilder<ClusterContext, PatchworkLayout, OC>
+where
+    OC: OutputContext,
+{
+    // The area of the cluster's rectangle, in square inches
+    pub fn area(
+        &mut self,
+        area: f64,
+    ) -> &mut AttributeBuilder<ClusterContext, PatchworkLayout, OC> {
+        self.attributes.push(Attribute::Area(area));
+        self
+    }
+}
+
+impl<EC, LC, OC> AttributeBuilder<EC, LC, OC>
+where
+    EC: EntityContext,
+    LC: LayoutContext,
+    OC: LinkOutput,
+{
+    #[allow(non_snake_case)]
+    pub fn URL<T: ToString>(&mut self, url: T) -> &mut AttributeBuilder<EC, LC, OC> {
+        self.attributes.push(Attribute::Url(Id::new(url)));
+        self
+    }
+
+    // A synonym for URL
+    pub fn href<T: ToString>(&mut self, url: T) -> &mut AttributeBuilder<EC, LC, OC> {
+        self.attributes.push(Attribute::Href(Id::new(url)));
+        self
+    }
+
+    // The browser window the link opens in, e.g. `_blank`
+    pub fn target<T: ToString>(&mut self, target: T) -> &mut AttributeBuilder<EC, LC, OC> {
+        self.attributes.push(Attribute::Target(Id::new(target)));
+        self
+    }
+
+    pub fn tooltip<T: ToString>(&mut self, tooltip: T) -> &mut AttributeBuilder<EC, LC, OC> {
+        self.attributes.push(Attribute::Tooltip(Id::new(tooltip)));
+        self
+    }
+}
+
+impl<EC, LC> AttributeBuilder<EC, LC, SVGOutput>
+where
+    EC: EntityContext,
+    LC: LayoutContext,
+{
+    // CSS classes of the SVG element, separated by spaces
+    pub fn class<T: ToString>(&mut self, class: T) -> &mut AttributeBuilder<EC, LC, SVGOutput> {
+        self.attributes.push(Attribute::Class(Id::new(class)));
+        self
+    }
+
+    pub fn id<T: ToString>(&mut self, id: T) -> &mut AttributeBuilder<EC, LC, SVGOutput> {
+        self.attributes.push(Attribute::Id(Id::new(id)));
+        self
+    }
+}
+
+impl<LC> AttributeBuilder<GraphContext, LC, SVGOutput>
+where
+    LC: LayoutContext,
+{
+    // The URL of a CSS stylesheet the SVG links to
+    pub fn stylesheet<T: ToString>(
+        &mut self,
+        url: T,
+    ) -> &mut AttributeBuilder<GraphContext, LC, SVGOutput> {
+        self.attributes.push(Attribute::Stylesheet(Id::new(url)));
+        self
+    }
+}
+
+impl<LC> AttributeBuilder<GraphContext, LC, BitmapOutput>
+where
+    LC: LayoutContext,
+{
+    // Pixels per inch, 96 by default
+    pub fn dpi(&mut self, dpi: f64) -> &mut AttributeBuilder<GraphContext, LC, BitmapOutput> {
+        self.attributes.push(Attribute::Dpi(dpi));
+        self
+    }
+
+    pub fn bgcolor<T: Into<ColorList>>(
+        &mut self,
+        color: T,
+    ) -> &mut AttributeBuilder<GraphContext, LC, BitmapOutput> {
+        self.attributes.push(Attribute::BgColor(color.into()));
+        self
+    }
+
+    // A transparent background, for formats with an alpha channel
+    pub fn transparent(&mut self) -> &mut AttributeBuilder<GraphContext, LC, BitmapOutput> {
+        self.bgcolor(Color::transparent())
+    }
+}
+
+// Every call adds an interval the node or edge exists for. Bounds are
+// numbers or ISO 8601 dates.
+impl<EC, LC> AttributeBuilder<EC, LC, GexfOutput>
+where
+    EC: TimedContext,
+    LC: LayoutContext,
+{
+    pub fn interval<T: ToString>(
+        &mut self,
+        start: T,
+        end: T,
+    ) -> &mut AttributeBuilder<EC, LC, GexfOutput> {
+        self.add_interval(Some(start.to_string()), Some(end.to_string()))
+    }
+
+    pub fn since<T: ToString>(&mut self, start: T) -> &mut AttributeBuilder<EC, LC, GexfOutput> {
+        self.add_interval(Some(start.to_string()), None)
+    }
+
+    pub fn until<T: ToString>(&mut self, end: T) -> &mut AttributeBuilder<EC, LC, GexfOutput> {
+        self.add_interval(None, Some(end.to_string()))
+    }
+
+    fn add_interval(
+        &mut self,
+        start: Option<String>,
+        end: Option<String>,
+    ) -> &mut AttributeBuilder<EC, LC, GexfOutput> {
+        let existing = self.attributes.iter_mut().find_map(|a| match a {
+            Attribute::Intervals(intervals) => Some(intervals),
+            _ => None,
+        });
+        match existing {
+            Some(intervals) => intervals.add(start, end),
+            None => {
+                let mut intervals = Intervals::default();
+                intervals.add(start, end);
+                self.attributes.push(Attribute::Intervals(intervals));
+            }
+        }
+        self
+    }
+}
+
+impl<LC> AttributeBuilder<GraphContext, LC, PostscriptOutput>
+where
+    LC: LayoutContext,
+{
+    // Splits the drawing into pages of this size in inches
+    pub fn page<T: Into<Size>>(
+        &mut self,
+        page: T,
+    ) -> &mut AttributeBuilder<GraphContext, LC, PostscriptOutput> {
+        self.attributes.push(Attribute::Page(page.into()));
+        self
+    }
+
+    pub fn pagedir(
+        &mut self,
+        pagedir: PageDir,
+    ) -> &mut AttributeBuilder<GraphContext, LC, PostscriptOutput> {
+        self.attributes.push(Attribute::PageDir(pagedir));
+        self
+    }
+
+    // Directories searched for fonts, separated by colons
+    pub fn fontpath<T: ToString>(
+        &mut self,
+        path: T,
+    ) -> &mut AttributeBuilder<GraphContext, LC, PostscriptOutput> {
+        self.attributes.push(Attribute::FontPath(Id::new(path)));
+        self
+    }
+
+    pub fn fontnames(
+        &mut self,
+        names: FontNames,
+    ) -> &mut AttributeBuilder<GraphContext, LC, PostscriptOutput> {
+        self.attributes.push(Attribute::FontNames(names));
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::attributes::{BrewerPalette, Color, ColorList, Fill, RecordLabel, Side};
     use crate::printer::print_graph;
     use std::str;
 
@@ -496,9 +1334,15 @@ mod tests {
             .cluster("0", |builder| {
                 builder
                     .graph_attributes(|builder| {
-                        builder.style(ClusterStyle::Filled).color(Color::LightGrey)
+                        builder
+                            .style(ClusterStyle::Filled)
+                            .color(Color::x11("lightgrey").unwrap())
+                    })
+                    .node_attributes(|builder| {
+                        builder
+                            .style(NodeStyle::Filled)
+                            .color(Color::x11("white").unwrap())
                     })
-                    .node_attributes(|builder| builder.style(NodeStyle::Filled).color(Color::White))
                     .edge_("a0", "a1")
                     .edge_("a1", "a2")
                     .edge_("a2", "a3")
@@ -510,7 +1354,11 @@ mod tests {
                     .edge_("b0", "b1")
                     .edge_("b1", "b2")
                     .edge_("b2", "b3")
-                    .graph_attributes(|builder| builder.label("process #2").color(Color::Blue))
+                    .graph_attributes(|builder| {
+                        builder
+                            .label("process #2")
+                            .color(Color::x11("blue").unwrap())
+                    })
             })
             .edge_("start", "a0")
             .edge_("start", "b0")
@@ -529,6 +1377,404 @@ mod tests {
         println!("{}", s);
     }
 
+    #[test]
+    fn records() {
+        // https://graphviz.org/Gallery/directed/datastruct.html
+        let struct1 =
+            RecordLabel::horizontal(|r| r.port("f0", "left").port("f1", "mid dle")).unwrap();
+        let struct2 = RecordLabel::vertical(|r| {
+            r.port("f0", "one")
+                .horizontal(|r| r.field("two").port("f1", "three"))
+        })
+        .unwrap();
+
+        let mut builder = directed().dot();
+        builder
+            .node_attributes(|builder| builder.shape(Shape::Record))
+            .node("struct1", |builder| builder.label(struct1))
+            .node("struct2", |builder| builder.label(struct2))
+            .edge_(("struct1", "f1"), ("struct2", "f0"))
+            .edge_("struct2", ("struct1", "f0", Compass::South));
+        assert!(builder.port("struct2", "f1").is_some());
+        assert!(builder.port("struct2", "f2").is_none());
+        let f1 = builder.port("struct2", "f1").unwrap();
+        builder.edge_(f1, ("struct1", Compass::East));
+
+        let graph = builder.build();
+        let mut writer = Vec::new();
+        print_graph(&mut writer, &graph).unwrap();
+        let s = str::from_utf8(&writer).unwrap();
+        println!("{}", s);
+        assert!(s.contains(r#"struct1 [label="<f0> left|<f1> mid\ dle"];"#));
+        assert!(s.contains(r#"struct2 [label="{<f0> one|{two|<f1> three}}"];"#));
+        assert!(s.contains("struct1:f1 -> struct2:f0;"));
+        assert!(s.contains("struct2 -> struct1:f0:s;"));
+        assert!(s.contains("struct2:f1 -> struct1:e;"));
+    }
+
+    #[test]
+    fn edge_chains() {
+        let mut builder = directed().dot();
+        builder
+            .edge_chain("a", "b", ["c"], |builder| builder.style(EdgeStyle::Dashed))
+            .edge_chain_(
+                ("c", Compass::South),
+                ("d", Compass::North),
+                Vec::<&str>::new(),
+            )
+            .edge_group(vec!["a", "b"], vec!["c", "d"], |builder| {
+                builder.label("fan out")
+            })
+            .edge_group_(vec!["e"], 1u32..=3);
+
+        let graph = builder.build();
+        let mut writer = Vec::new();
+        print_graph(&mut writer, &graph).unwrap();
+        let s = str::from_utf8(&writer).unwrap();
+        println!("{}", s);
+        assert!(s.contains("a -> b -> c [style=dashed];"));
+        assert!(s.contains("c:s -> d:n;"));
+        assert!(s.contains("{a b} -> {c d} [label=\"fan out\"];"));
+        assert!(s.contains("e -> {1 2 3};"));
+    }
+
+    #[test]
+    fn strict_graphs() {
+        let mut builder = undirected();
+        builder
+            .id("merged edges")
+            .strict()
+            .edge("a", "b", |builder| {
+                builder.label("first").style(EdgeStyle::Dashed)
+            })
+            .edge("b", ("a", Compass::East), |builder| builder.label("second"))
+            .edge_chain_("a", "c", ["d"])
+            .edge_chain_("c", "d", ["e"])
+            .cluster("x", |builder| builder.edge_("d", "e"));
+
+        let graph = builder.build();
+        let mut writer = Vec::new();
+        print_graph(&mut writer, &graph).unwrap();
+        let s = str::from_utf8(&writer).unwrap();
+        println!("{}", s);
+        assert!(s.starts_with("strict graph \"merged edges\" {"));
+        assert!(s.contains("a:e -- b [style=dashed, label=\"second\"];"));
+        assert!(!s.contains("b -- a"));
+        assert!(s.contains("a -- c -- d;"));
+        assert!(s.contains("d -- e;"));
+        assert_eq!(s.matches("c -- d").count(), 1);
+        assert!(s.contains("subgraph cluster_x {\n    d;\n    e;\n  }"));
+
+        let mut builder = directed();
+        builder.node_("a").edge_("a", "b");
+        builder.id("late").strict();
+        let mut writer = Vec::new();
+        print_graph(&mut writer, &builder.build()).unwrap();
+        assert!(str::from_utf8(&writer)
+            .unwrap()
+            .starts_with("strict digraph late {"));
+    }
+
+    #[test]
+    fn subgraphs() {
+        let mut builder = directed().dot();
+        builder
+            .rank_group(Rank::Same, vec!["a", "b"])
+            .rank_group(Rank::Sink, vec!["z"])
+            .subgraph("shared", |builder| {
+                builder
+                    .graph_attributes(|builder| builder.rank(Rank::Min))
+                    .node_attributes(|builder| builder.shape(Shape::Box))
+                    .node("c", |builder| builder)
+            })
+            .anonymous_subgraph(|builder| builder.node("d", |builder| builder))
+            .edge_("a", "z");
+
+        let graph = builder.build();
+        let mut writer = Vec::new();
+        print_graph(&mut writer, &graph).unwrap();
+        let s = str::from_utf8(&writer).unwrap();
+        println!("{}", s);
+        assert!(s.contains("  {\n    graph [rank=same];\n    a;\n    b;\n  }\n"));
+        assert!(s.contains("graph [rank=sink];\n    z;"));
+        assert!(s.contains("  subgraph shared {\n    graph [rank=min];"));
+        assert!(!s.contains("cluster"));
+        assert!(s.contains("  {\n    d;\n  }\n"));
+    }
+
+    #[test]
+    fn root_cluster_defaults() {
+        let mut builder = directed();
+        builder
+            .graph_attributes(|builder| {
+                builder
+                    .color(Color::x11("red").unwrap())
+                    .style(ClusterStyle::Dashed)
+            })
+            .cluster("x", |builder| builder.node_("a"));
+
+        let graph = builder.build();
+        let mut writer = Vec::new();
+        print_graph(&mut writer, &graph).unwrap();
+        let s = str::from_utf8(&writer).unwrap();
+        assert!(s.starts_with("digraph {\n  graph [color=\"/x11/red\", style=dashed];"));
+    }
+
+    #[test]
+    fn nested_clusters() {
+        let mut builder = undirected().dot();
+        builder
+            .edge_attributes(|builder| builder.style(EdgeStyle::Dotted))
+            .cluster("outer", |builder| {
+                builder
+                    .graph_attributes(|builder| {
+                        builder.label("outer").color(Color::x11("blue").unwrap())
+                    })
+                    .edge_attributes(|builder| builder.style(EdgeStyle::Bold))
+                    .node_("a")
+                    .cluster("inner", |builder| {
+                        builder
+                            .node_("b")
+                            .cluster("innermost", |builder| builder.edge_("c", "d"))
+                            .rank_group(Rank::Same, vec!["b", "c"])
+                    })
+                    .subgraph("plain", |builder| builder.node_("e"))
+            })
+            .edge_("a", "d");
+
+        let graph = builder.build();
+        let mut writer = Vec::new();
+        print_graph(&mut writer, &graph).unwrap();
+        let s = str::from_utf8(&writer).unwrap();
+        println!("{}", s);
+        assert!(s.contains("  edge [style=dotted];\n  subgraph cluster_outer {\n"));
+        assert!(s.contains("    edge [style=bold];\n    a;\n    subgraph cluster_inner {\n"));
+        assert!(s.contains("      subgraph cluster_innermost {\n        c -- d;\n      }\n"));
+        assert!(
+            s.contains("      {\n        graph [rank=same];\n        b;\n        c;\n      }\n")
+        );
+        assert!(s.contains("    subgraph plain {\n      e;\n    }\n"));
+    }
+
+    #[test]
+    fn color_lists() {
+        let red = Color::x11("red").unwrap();
+        let blue = Color::svg("blue").unwrap();
+        let stripes =
+            ColorList::new(|l| l.weighted(red.clone(), 0.25).color(blue.clone())).unwrap();
+        let mut builder = directed();
+        builder
+            .node("a", |builder| {
+                builder
+                    .shape(Shape::Box)
+                    .style(NodeStyle::Striped)
+                    .fillcolor(stripes)
+                    .color(Color::rgba(0, 0, 0, 128))
+            })
+            .edge("a", "b", |builder| {
+                builder.color(ColorList::new(|l| l.color(red).color(blue)).unwrap())
+            });
+
+        let graph = builder.build();
+        let mut writer = Vec::new();
+        print_graph(&mut writer, &graph).unwrap();
+        let s = str::from_utf8(&writer).unwrap();
+        println!("{}", s);
+        assert!(s.contains(
+            r##"a [shape=box, style=striped, fillcolor="/x11/red;0.25:/svg/blue", color="#00000080"];"##
+        ));
+        assert!(s.contains(r#"a -> b [color="/x11/red:/svg/blue"];"#));
+    }
+
+    #[test]
+    fn color_schemes() {
+        let scheme = ColorScheme::brewer(BrewerPalette::Set1, 3).unwrap();
+        let mut builder = undirected();
+        builder
+            .graph_attributes(|builder| builder.colorscheme(scheme))
+            .node("a", |builder| {
+                builder
+                    .colorscheme(scheme)
+                    .color(Color::indexed(scheme, 1).unwrap())
+            })
+            .edge("a", "b", |builder| {
+                builder
+                    .colorscheme(ColorScheme::Svg)
+                    .color(Color::x11("gray").unwrap())
+            })
+            .cluster("c", |builder| {
+                builder.graph_attributes(|builder| builder.colorscheme(ColorScheme::X11))
+            });
+
+        let graph = builder.build();
+        let mut writer = Vec::new();
+        print_graph(&mut writer, &graph).unwrap();
+        let s = str::from_utf8(&writer).unwrap();
+        println!("{}", s);
+        assert!(s.contains("graph [colorscheme=set13];"));
+        assert!(s.contains(r#"a [colorscheme=set13, color="/set13/1"];"#));
+        // Gray is a different color in the SVG scheme
+        assert!(s.contains(r#"a -- b [colorscheme=svg, color="/x11/gray"];"#));
+        assert!(s.contains("graph [colorscheme=x11];"));
+    }
+
+    #[test]
+    fn polygons() {
+        let skewed = Polygon::new(|polygon| polygon.sides(5).skew(0.4).distortion(-0.25)).unwrap();
+        let hexagon = Polygon::new(|polygon| polygon.sides(6)).unwrap();
+        let mut builder = directed();
+        builder
+            .node("a", |builder| builder.shape(Shape::Cylinder))
+            .node("b", |builder| builder.polygon(skewed))
+            .node("c", |builder| {
+                builder
+                    .polygon(hexagon)
+                    .orientation(30.)
+                    .regular(true)
+                    .peripheries(2)
+            })
+            .node("d", |builder| builder.polygon(Polygon::default()))
+            .node("e", |builder| builder.peripheries(0));
+
+        let graph = builder.build();
+        let mut writer = Vec::new();
+        print_graph(&mut writer, &graph).unwrap();
+        let s = str::from_utf8(&writer).unwrap();
+        println!("{}", s);
+        assert!(s.contains("a [shape=cylinder];"));
+        assert!(s.contains("b [shape=polygon, sides=5, skew=0.4, distortion=-0.25];"));
+        assert!(
+            s.contains("c [shape=polygon, sides=6, orientation=30, regular=true, peripheries=2];")
+        );
+        assert!(s.contains("d [shape=polygon];"));
+        assert!(s.contains("e [peripheries=0];"));
+    }
+
+    #[test]
+    fn arrows() {
+        let crow = Arrow::new(|a| a.tee(Side::Both).crow(Side::Both)).unwrap();
+        let diamond = Arrow::new(|a| a.diamond(Fill::Open, Side::Left)).unwrap();
+        let mut builder = undirected();
+        builder.edge("a", "b", |builder| {
+            builder
+                .dir(Dir::Both)
+                .arrowhead(crow)
+                .arrowtail(diamond)
+                .arrowsize(1.5)
+        });
+
+        let graph = builder.build();
+        let mut writer = Vec::new();
+        print_graph(&mut writer, &graph).unwrap();
+        let s = str::from_utf8(&writer).unwrap();
+        println!("{}", s);
+        assert!(
+            s.contains("a -- b [dir=both, arrowhead=teecrow, arrowtail=oldiamond, arrowsize=1.5];")
+        );
+    }
+
+    #[test]
+    fn layout_engines() {
+        let print = |graph: Graph| {
+            let mut writer = Vec::new();
+            print_graph(&mut writer, &graph).unwrap();
+            String::from_utf8(writer).unwrap()
+        };
+
+        let mut builder = undirected().twopi();
+        builder
+            .graph_attributes(|builder| builder.root("hub").ranksep(1.5))
+            .edge_("hub", "spoke");
+        let s = print(builder.build());
+        assert!(s.starts_with("graph {\n  graph [layout=twopi];\n  graph [root=hub, ranksep=1.5];"));
+
+        let mut builder = undirected().sfdp();
+        builder.graph_attributes(|builder| builder.K(0.5).repulsiveforce(2.));
+        assert!(print(builder.build()).contains("graph [K=0.5, repulsiveforce=2];"));
+
+        let mut builder = undirected().circo();
+        builder.graph_attributes(|builder| builder.mindist(0.25));
+        assert!(print(builder.build()).contains("graph [mindist=0.25];"));
+
+        let mut builder = undirected().osage();
+        builder
+            .graph_attributes(|builder| builder.packmode(PackMode::Array(Some(3))))
+            .cluster("a", |builder| {
+                builder.graph_attributes(|builder| builder.packmode(PackMode::Cluster))
+            });
+        let s = print(builder.build());
+        assert!(s.contains("graph [packmode=array3];"));
+        assert!(s.contains("graph [packmode=clust];"));
+
+        let mut builder = undirected().patchwork();
+        builder.node("a", |builder| builder.area(2.));
+        assert!(print(builder.build()).contains("a [area=2];"));
+
+        let mut builder = undirected().neato();
+        builder
+            .graph_attributes(|builder| {
+                builder
+                    .overlap(Overlap::Scale)
+                    .start(Start::Random(Some(7)))
+            })
+            .node("a", |builder| builder.pos(Position::pinned(1., 2.5)))
+            .node("b", |builder| builder.pos((0., 0.)).pin(false))
+            .edge("a", "b", |builder| builder.len(2.));
+        let s = print(builder.build());
+        assert!(s.contains("graph [overlap=scale, start=random7];"));
+        assert!(s.contains("a [pos=\"1,2.5!\"];"));
+        assert!(s.contains("b [pos=\"0,0\", pin=false];"));
+        assert!(s.contains("a -- b [len=2];"));
+
+        assert!(print(undirected().fdp().build()).contains("graph [layout=fdp];"));
+        assert!(!print(undirected().build()).contains("layout"));
+    }
+
+    #[test]
+    fn output_formats() {
+        let print = |graph: Graph| {
+            let mut writer = Vec::new();
+            print_graph(&mut writer, &graph).unwrap();
+            String::from_utf8(writer).unwrap()
+        };
+
+        let mut builder = directed().svg();
+        builder
+            .graph_attributes(|builder| builder.stylesheet("style.css").id("root"))
+            .node("a", |builder| {
+                builder
+                    .URL("https://graphviz.org")
+                    .target("_blank")
+                    .tooltip("Graphviz")
+                    .class("external link")
+            });
+        let s = print(builder.build());
+        assert!(s.contains("graph [stylesheet=\"style.css\", id=root];"));
+        assert!(s.contains(
+            "a [URL=\"https://graphviz.org\", target=_blank, tooltip=Graphviz, class=\"external link\"];"
+        ));
+
+        let mut builder = directed().image_map();
+        builder.edge("a", "b", |builder| builder.href("#b"));
+        assert!(print(builder.build()).contains("a -> b [href=\"#b\"];"));
+
+        let mut builder = directed().bitmap();
+        builder.graph_attributes(|builder| builder.dpi(300.).transparent());
+        assert!(print(builder.build()).contains("graph [dpi=300, bgcolor=transparent];"));
+
+        let mut builder = directed().postscript();
+        builder.graph_attributes(|builder| {
+            builder
+                .page(8.5)
+                .pagedir(PageDir::TL)
+                .fontpath("/usr/share/fonts")
+                .fontnames(FontNames::Ps)
+        });
+        assert!(print(builder.build()).contains(
+            "graph [page=\"8.5,8.5\", pagedir=TL, fontpath=\"/usr/share/fonts\", fontnames=ps];"
+        ));
+    }
+
     #[test]
     fn finite_state_machine() {
         // https://graphviz.org/Gallery/directed/fsm.html
@@ -571,7 +1817,7 @@ mod tests {
             .node_attributes(|ab| {
                 ab.shape(Shape::Diamond)
                     .style(NodeStyle::Filled)
-                    .color(Color::LightGrey)
+                    .color(Color::x11("lightgrey").unwrap())
             })
             .node_("C-I")
             .node_("S-C")
@@ -0,0 +1,785 @@
+use std::fmt::Display;
+
+use crate::ast::{Compass, Id, ToId};
+use crate::ast::{Graph, GraphType};
+use crate::escape;
+use crate::lexer::{tokenize, Token, TokenKind, KEYWORDS};
+use crate::parser::{lower_graph, ParseError};
+
+// A lossless concrete syntax tree for DOT. Every byte of the input, including
+// whitespace, comments and preprocessor lines, is kept in a token so that
+// printing the tree gives back the original text. Edits only touch the tokens
+// they replace, leaving the rest of the layout alone.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NodeKind {
+    Graph,
+    AttributeStatement,
+    NodeStatement,
+    EdgeStatement,
+    IdAssignment,
+    Subgraph,
+    AttributeList,
+    Attribute,
+    NodeId,
+    Id,
+}
+
+#[derive(Clone)]
+pub(crate) struct SyntaxToken {
+    pub(crate) kind: TokenKind,
+    pub(crate) text: String,
+}
+
+#[derive(Clone)]
+pub(crate) enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+#[derive(Clone)]
+pub(crate) struct SyntaxNode {
+    pub(crate) kind: NodeKind,
+    pub(crate) children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    pub(crate) fn nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    // Non-trivia tokens that are direct children of this node
+    pub(crate) fn tokens(&self) -> impl Iterator<Item = &SyntaxToken> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Token(token) if !token.kind.is_trivia() => Some(token),
+            _ => None,
+        })
+    }
+
+    fn write(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for child in self.children.iter() {
+            match child {
+                SyntaxElement::Node(node) => node.write(f)?,
+                SyntaxElement::Token(token) => f.write_str(&token.text)?,
+            }
+        }
+        Ok(())
+    }
+
+    // The unquoted value of an Id node
+    pub(crate) fn id_value(&self) -> crate::ast::Id {
+        crate::parser::lower_id(self)
+    }
+}
+
+pub struct SyntaxTree {
+    pub(crate) root: SyntaxNode,
+}
+
+impl SyntaxTree {
+    pub fn parse(input: &str) -> Result<SyntaxTree, ParseError> {
+        let tokens = tokenize(input)?;
+        let mut parser = CstParser {
+            tokens,
+            position: 0,
+            gtype: GraphType::Directed,
+            stack: vec![Vec::new()],
+        };
+        parser.graph()?;
+        parser.trivia();
+        if let Some(token) = parser.peek() {
+            return Err(unexpected(&token));
+        }
+        let mut children = parser.stack.pop().expect("the root is never popped");
+        // Trivia after the closing brace belongs to the graph
+        let trailing = children.split_off(1);
+        let mut root = match children.pop() {
+            Some(SyntaxElement::Node(node)) => node,
+            _ => unreachable!("graph() always produces a node"),
+        };
+        root.children.extend(trailing);
+        Ok(SyntaxTree { root })
+    }
+
+    pub fn to_graph(&self) -> Graph {
+        lower_graph(&self.root)
+    }
+
+    // Sets `name=value` on the first node statement for `node`, replacing an
+    // existing value in place or appending to its attribute list. Returns
+    // false if the graph has no statement for that node. Plain strings are
+    // escaped; pass a `Label` to keep an escString or HTML label as it is.
+    pub fn set_node_attribute<T: ToId>(&mut self, node: &str, name: &str, value: T) -> bool {
+        let value = value.to_id();
+        let statement = match find_node_statement(&mut self.root, node) {
+            Some(statement) => statement,
+            None => return false,
+        };
+
+        for list in statement.children.iter_mut() {
+            if let SyntaxElement::Node(list) = list {
+                if list.kind != NodeKind::AttributeList {
+                    continue;
+                }
+                for attribute in list.children.iter_mut() {
+                    if let SyntaxElement::Node(attribute) = attribute {
+                        if attribute_name(attribute).as_deref() == Some(name) {
+                            let value_node = attribute
+                                .children
+                                .iter_mut()
+                                .filter_map(|child| match child {
+                                    SyntaxElement::Node(node) => Some(node),
+                                    SyntaxElement::Token(_) => None,
+                                })
+                                .nth(1)
+                                .expect("attributes have a name and a value");
+                            *value_node = id_node(&value);
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+
+        let new_attribute = SyntaxElement::Node(SyntaxNode {
+            kind: NodeKind::Attribute,
+            children: vec![
+                SyntaxElement::Node(id_node(&Id::new(name))),
+                token(TokenKind::Equals, "="),
+                SyntaxElement::Node(id_node(&value)),
+            ],
+        });
+        let last_list = statement
+            .children
+            .iter_mut()
+            .rev()
+            .find_map(|child| match child {
+                SyntaxElement::Node(node) if node.kind == NodeKind::AttributeList => Some(node),
+                _ => None,
+            });
+        match last_list {
+            Some(list) => {
+                let close = list
+                    .children
+                    .iter()
+                    .rposition(|child| {
+                        matches!(child, SyntaxElement::Token(t) if t.kind == TokenKind::RBracket)
+                    })
+                    .expect("attribute lists are closed");
+                let mut inserted = vec![new_attribute];
+                if list.nodes().next().is_some() {
+                    // Follow the separator of the existing list
+                    let separator = list
+                        .tokens()
+                        .find(|t| t.kind == TokenKind::Comma || t.kind == TokenKind::Semicolon)
+                        .map_or((TokenKind::Comma, ","), |t| (t.kind, t.text.as_str()));
+                    let trailing_separator = matches!(
+                        list.children[..close]
+                            .iter()
+                            .rev()
+                            .find(|child| !is_trivia(child)),
+                        Some(SyntaxElement::Token(t))
+                            if t.kind == TokenKind::Comma || t.kind == TokenKind::Semicolon
+                    );
+                    if trailing_separator {
+                        inserted.insert(0, token(TokenKind::Whitespace, " "));
+                    } else {
+                        inserted.insert(0, token(TokenKind::Whitespace, " "));
+                        inserted.insert(0, token(separator.0, separator.1));
+                    }
+                }
+                // Keep whitespace before the closing bracket where it is
+                let position = list.children[..close]
+                    .iter()
+                    .rposition(|child| !is_trivia(child))
+                    .map_or(close, |i| i + 1);
+                list.children.splice(position..position, inserted);
+            }
+            None => {
+                let node_id = statement
+                    .children
+                    .iter()
+                    .position(|child| matches!(child, SyntaxElement::Node(n) if n.kind == NodeKind::NodeId))
+                    .expect("node statements start with a node id");
+                let list = SyntaxElement::Node(SyntaxNode {
+                    kind: NodeKind::AttributeList,
+                    children: vec![
+                        token(TokenKind::LBracket, "["),
+                        new_attribute,
+                        token(TokenKind::RBracket, "]"),
+                    ],
+                });
+                statement.children.splice(
+                    node_id + 1..node_id + 1,
+                    vec![token(TokenKind::Whitespace, " "), list],
+                );
+            }
+        }
+        true
+    }
+
+    // Removes `name` from the first node statement for `node` together with
+    // its separator. Returns false if there was nothing to remove.
+    pub fn remove_node_attribute(&mut self, node: &str, name: &str) -> bool {
+        let statement = match find_node_statement(&mut self.root, node) {
+            Some(statement) => statement,
+            None => return false,
+        };
+        for list in statement.children.iter_mut() {
+            let list = match list {
+                SyntaxElement::Node(list) if list.kind == NodeKind::AttributeList => list,
+                _ => continue,
+            };
+            let index = list.children.iter().position(|child| {
+                matches!(child, SyntaxElement::Node(attribute) if attribute_name(attribute).as_deref() == Some(name))
+            });
+            let index = match index {
+                Some(index) => index,
+                None => continue,
+            };
+            let is_separator = |child: &SyntaxElement| matches!(child, SyntaxElement::Token(t) if t.kind == TokenKind::Comma || t.kind == TokenKind::Semicolon);
+            // Prefer removing the separator (and whitespace) that follows the
+            // attribute, otherwise the one that precedes it.
+            let mut end = index + 1;
+            while end < list.children.len() && is_trivia(&list.children[end]) {
+                end += 1;
+            }
+            if end < list.children.len() && is_separator(&list.children[end]) {
+                end += 1;
+                while end < list.children.len() && is_trivia(&list.children[end]) {
+                    end += 1;
+                }
+                list.children.drain(index..end);
+            } else {
+                let mut start = index;
+                while start > 0 && is_trivia(&list.children[start - 1]) {
+                    start -= 1;
+                }
+                if start > 0 && is_separator(&list.children[start - 1]) {
+                    start -= 1;
+                    list.children.drain(start..index + 1);
+                } else {
+                    list.children.remove(index);
+                }
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl Display for SyntaxTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.root.write(f)
+    }
+}
+
+fn is_trivia(element: &SyntaxElement) -> bool {
+    matches!(element, SyntaxElement::Token(t) if t.kind.is_trivia())
+}
+
+fn token(kind: TokenKind, text: &str) -> SyntaxElement {
+    SyntaxElement::Token(SyntaxToken {
+        kind,
+        text: text.to_string(),
+    })
+}
+
+fn id_node(id: &Id) -> SyntaxNode {
+    let value = id.get();
+    let kind = if id.html {
+        TokenKind::Html
+    } else if escape::is_identifier(value) {
+        TokenKind::Identifier
+    } else if escape::is_numeral(value) {
+        TokenKind::Numeral
+    } else {
+        TokenKind::QuotedString
+    };
+    SyntaxNode {
+        kind: NodeKind::Id,
+        children: vec![token(kind, &escape::id(id))],
+    }
+}
+
+fn attribute_name(attribute: &SyntaxNode) -> Option<String> {
+    attribute.nodes().next().map(|id| id.id_value().value)
+}
+
+fn find_node_statement<'a>(node: &'a mut SyntaxNode, id: &str) -> Option<&'a mut SyntaxNode> {
+    if node.kind == NodeKind::NodeStatement {
+        let matches = node
+            .nodes()
+            .next()
+            .and_then(|node_id| node_id.nodes().next())
+            .is_some_and(|first| first.id_value().value == id);
+        return if matches { Some(node) } else { None };
+    }
+    for child in node.children.iter_mut() {
+        if let SyntaxElement::Node(child) = child {
+            if let Some(found) = find_node_statement(child, id) {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+// Keywords are case-independent
+pub(crate) fn is_keyword(token: &Token, keyword: &str) -> bool {
+    token.kind == TokenKind::Identifier && token.text.eq_ignore_ascii_case(keyword)
+}
+
+fn unexpected(token: &Token) -> ParseError {
+    ParseError::new(
+        &format!("unexpected '{}'", token.text),
+        token.line,
+        token.column,
+    )
+}
+
+struct CstParser<'a> {
+    tokens: Vec<Token<'a>>,
+    position: usize,
+    gtype: GraphType,
+    // Children of the nodes that are currently open, innermost last
+    stack: Vec<Vec<SyntaxElement>>,
+}
+
+impl<'a> CstParser<'a> {
+    // Attaches any trivia at the current position to the innermost open node
+    fn trivia(&mut self) {
+        while let Some(token) = self.tokens.get(self.position) {
+            if !token.kind.is_trivia() {
+                break;
+            }
+            let token = *token;
+            self.push_token(&token);
+            self.position += 1;
+        }
+    }
+
+    fn push_token(&mut self, token: &Token) {
+        self.stack
+            .last_mut()
+            .expect("there is always an open node")
+            .push(SyntaxElement::Token(SyntaxToken {
+                kind: token.kind,
+                text: token.text.to_string(),
+            }));
+    }
+
+    fn start_node(&mut self) {
+        self.stack.push(Vec::new());
+    }
+
+    fn finish_node(&mut self, kind: NodeKind) {
+        let children = self.stack.pop().expect("a node was started");
+        self.stack
+            .last_mut()
+            .expect("the root is never finished")
+            .push(SyntaxElement::Node(SyntaxNode { kind, children }));
+    }
+
+    // Where the next child of the innermost open node will go
+    fn checkpoint(&mut self) -> usize {
+        self.trivia();
+        self.stack
+            .last()
+            .expect("there is always an open node")
+            .len()
+    }
+
+    // Wraps the children added since `checkpoint` into a node, leaving
+    // trailing trivia outside of it.
+    fn wrap_node(&mut self, checkpoint: usize, kind: NodeKind) {
+        let current = self.stack.last_mut().expect("there is always an open node");
+        let end = current[checkpoint..]
+            .iter()
+            .rposition(|child| !is_trivia(child))
+            .map_or(checkpoint, |i| checkpoint + i + 1);
+        let children: Vec<SyntaxElement> = current.drain(checkpoint..end).collect();
+        current.insert(
+            checkpoint,
+            SyntaxElement::Node(SyntaxNode { kind, children }),
+        );
+    }
+
+    fn peek(&mut self) -> Option<Token<'a>> {
+        self.trivia();
+        self.tokens.get(self.position).copied()
+    }
+
+    fn peek_kind(&mut self) -> Option<TokenKind> {
+        self.peek().map(|t| t.kind)
+    }
+
+    fn peek_keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(t) => is_keyword(&t, keyword),
+            None => false,
+        }
+    }
+
+    fn bump(&mut self) -> Option<Token<'a>> {
+        let token = self.peek()?;
+        self.push_token(&token);
+        self.position += 1;
+        Some(token)
+    }
+
+    fn eat(&mut self, kind: TokenKind) -> bool {
+        if self.peek_kind() == Some(kind) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek_keyword(keyword) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &str) -> Result<Token<'a>, ParseError> {
+        match self.peek() {
+            Some(t) if t.kind == kind => {
+                self.bump();
+                Ok(t)
+            }
+            Some(t) => Err(ParseError::new(
+                &format!("expected {}, found '{}'", expected, t.text),
+                t.line,
+                t.column,
+            )),
+            None => Err(self.end_of_input(expected)),
+        }
+    }
+
+    fn end_of_input(&self, expected: &str) -> ParseError {
+        let (line, column) = match self.tokens.last() {
+            Some(t) => match t.text.rsplit_once('\n') {
+                Some((before, after)) => (
+                    t.line + before.matches('\n').count() + 1,
+                    after.chars().count() + 1,
+                ),
+                None => (t.line, t.column + t.text.chars().count()),
+            },
+            None => (1, 1),
+        };
+        ParseError::new(
+            &format!("expected {}, found end of input", expected),
+            line,
+            column,
+        )
+    }
+
+    // graph : [ strict ] (graph | digraph) [ ID ] '{' stmt_list '}'
+    fn graph(&mut self) -> Result<(), ParseError> {
+        self.start_node();
+        self.eat_keyword("strict");
+        self.gtype = if self.eat_keyword("digraph") {
+            GraphType::Directed
+        } else if self.eat_keyword("graph") {
+            GraphType::Undirected
+        } else {
+            return match self.peek() {
+                Some(t) => Err(ParseError::new(
+                    &format!("expected 'graph' or 'digraph', found '{}'", t.text),
+                    t.line,
+                    t.column,
+                )),
+                None => Err(self.end_of_input("'graph' or 'digraph'")),
+            };
+        };
+        if self.peek_kind() != Some(TokenKind::LBrace) {
+            self.id()?;
+        }
+        self.expect(TokenKind::LBrace, "'{'")?;
+        self.statement_list()?;
+        self.expect(TokenKind::RBrace, "'}'")?;
+        self.finish_node(NodeKind::Graph);
+        Ok(())
+    }
+
+    // stmt_list : [ stmt [ ';' ] stmt_list ]
+    fn statement_list(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.peek_kind() {
+                None | Some(TokenKind::RBrace) => return Ok(()),
+                _ => {
+                    self.statement()?;
+                    self.eat(TokenKind::Semicolon);
+                }
+            }
+        }
+    }
+
+    // stmt : node_stmt | edge_stmt | attr_stmt | ID '=' ID | subgraph
+    fn statement(&mut self) -> Result<(), ParseError> {
+        if self.peek_keyword("graph") || self.peek_keyword("node") || self.peek_keyword("edge") {
+            self.start_node();
+            self.bump();
+            self.attribute_lists()?;
+            self.finish_node(NodeKind::AttributeStatement);
+            return Ok(());
+        }
+
+        let checkpoint = self.checkpoint();
+        if self.peek_keyword("subgraph") || self.peek_kind() == Some(TokenKind::LBrace) {
+            self.subgraph()?;
+            if self.peek_edge_op() {
+                return self.edge_statement(checkpoint);
+            }
+            return Ok(());
+        }
+
+        let id_checkpoint = self.checkpoint();
+        self.id()?;
+        if self.peek_kind() == Some(TokenKind::Equals) {
+            self.bump();
+            self.id()?;
+            self.wrap_node(checkpoint, NodeKind::IdAssignment);
+            return Ok(());
+        }
+        self.port(id_checkpoint)?;
+        if self.peek_edge_op() {
+            return self.edge_statement(checkpoint);
+        }
+        if self.peek_kind() == Some(TokenKind::LBracket) {
+            self.attribute_lists()?;
+        }
+        self.wrap_node(checkpoint, NodeKind::NodeStatement);
+        Ok(())
+    }
+
+    fn peek_edge_op(&mut self) -> bool {
+        matches!(
+            self.peek_kind(),
+            Some(TokenKind::DirectedEdge) | Some(TokenKind::UndirectedEdge)
+        )
+    }
+
+    fn edge_op(&mut self) -> Result<(), ParseError> {
+        let token = self.bump().expect("caller checked for an edge operator");
+        match (self.gtype, token.kind) {
+            (GraphType::Directed, TokenKind::DirectedEdge)
+            | (GraphType::Undirected, TokenKind::UndirectedEdge) => Ok(()),
+            (GraphType::Directed, _) => Err(ParseError::new(
+                "undirected edge '--' in a digraph",
+                token.line,
+                token.column,
+            )),
+            (GraphType::Undirected, _) => Err(ParseError::new(
+                "directed edge '->' in an undirected graph",
+                token.line,
+                token.column,
+            )),
+        }
+    }
+
+    // edge_stmt : (node_id | subgraph) edgeRHS [ attr_list ]
+    // edgeRHS   : edgeop (node_id | subgraph) [ edgeRHS ]
+    fn edge_statement(&mut self, checkpoint: usize) -> Result<(), ParseError> {
+        while self.peek_edge_op() {
+            self.edge_op()?;
+            if self.peek_keyword("subgraph") || self.peek_kind() == Some(TokenKind::LBrace) {
+                self.subgraph()?;
+            } else {
+                let id_checkpoint = self.checkpoint();
+                self.id()?;
+                self.port(id_checkpoint)?;
+            }
+        }
+        if self.peek_kind() == Some(TokenKind::LBracket) {
+            self.attribute_lists()?;
+        }
+        self.wrap_node(checkpoint, NodeKind::EdgeStatement);
+        Ok(())
+    }
+
+    // subgraph : [ subgraph [ ID ] ] '{' stmt_list '}'
+    fn subgraph(&mut self) -> Result<(), ParseError> {
+        self.start_node();
+        if self.eat_keyword("subgraph") && self.peek_kind() != Some(TokenKind::LBrace) {
+            self.id()?;
+        }
+        self.expect(TokenKind::LBrace, "'{'")?;
+        self.statement_list()?;
+        self.expect(TokenKind::RBrace, "'}'")?;
+        self.finish_node(NodeKind::Subgraph);
+        Ok(())
+    }
+
+    // node_id : ID [ port ]
+    // port    : ':' ID [ ':' compass_pt ] | ':' compass_pt
+    fn port(&mut self, checkpoint: usize) -> Result<(), ParseError> {
+        if self.eat(TokenKind::Colon) {
+            self.id()?;
+            if self.eat(TokenKind::Colon) {
+                if let Some(token) = self.peek() {
+                    let text = token.text.trim_matches('"');
+                    if Compass::parse(text).is_none() {
+                        return Err(ParseError::new(
+                            &format!("invalid compass point '{}'", text),
+                            token.line,
+                            token.column,
+                        ));
+                    }
+                }
+                self.id()?;
+            }
+        }
+        self.wrap_node(checkpoint, NodeKind::NodeId);
+        Ok(())
+    }
+
+    // attr_list : '[' [ a_list ] ']' [ attr_list ]
+    // a_list    : ID '=' ID [ (';' | ',') ] [ a_list ]
+    fn attribute_lists(&mut self) -> Result<(), ParseError> {
+        loop {
+            self.start_node();
+            self.expect(TokenKind::LBracket, "'['")?;
+            while !self.eat(TokenKind::RBracket) {
+                self.start_node();
+                self.id()?;
+                self.expect(TokenKind::Equals, "'='")?;
+                self.id()?;
+                self.finish_node(NodeKind::Attribute);
+                if !self.eat(TokenKind::Comma) {
+                    self.eat(TokenKind::Semicolon);
+                }
+            }
+            self.finish_node(NodeKind::AttributeList);
+            if self.peek_kind() != Some(TokenKind::LBracket) {
+                return Ok(());
+            }
+        }
+    }
+
+    // ID : identifier | numeral | quoted string ('+' quoted string)* | HTML string
+    fn id(&mut self) -> Result<(), ParseError> {
+        let token = match self.peek() {
+            Some(t) => t,
+            None => return Err(self.end_of_input("an ID")),
+        };
+        self.start_node();
+        match token.kind {
+            TokenKind::Identifier if KEYWORDS.iter().any(|k| is_keyword(&token, k)) => {
+                return Err(ParseError::new(
+                    &format!("keyword '{}' cannot be used as an ID", token.text),
+                    token.line,
+                    token.column,
+                ));
+            }
+            TokenKind::Identifier | TokenKind::Numeral | TokenKind::Html => {
+                self.bump();
+            }
+            TokenKind::QuotedString => {
+                self.bump();
+                while self.peek_kind() == Some(TokenKind::Plus) {
+                    self.bump();
+                    self.expect(TokenKind::QuotedString, "a quoted string")?;
+                }
+            }
+            _ => {
+                return Err(ParseError::new(
+                    &format!("expected an ID, found '{}'", token.text),
+                    token.line,
+                    token.column,
+                ))
+            }
+        }
+        self.finish_node(NodeKind::Id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attributes::{Html, Label, ToLabel};
+
+    const INPUT: &str = r#"# 1 "input.dot"
+/* header comment */
+digraph "G" {
+    // the start
+    start   [shape=Mdiamond, label="Start\lhere"] ;
+    a0 -> a1   /* inline */ -> a2;
+    end
+}
+"#;
+
+    #[test]
+    fn lossless() {
+        let tree = SyntaxTree::parse(INPUT).unwrap();
+        assert_eq!(tree.to_string(), INPUT);
+    }
+
+    #[test]
+    fn replace_attribute() {
+        let mut tree = SyntaxTree::parse(INPUT).unwrap();
+        assert!(tree.set_node_attribute("start", "shape", "box"));
+        assert_eq!(
+            tree.to_string(),
+            INPUT.replace("[shape=Mdiamond,", "[shape=box,")
+        );
+    }
+
+    #[test]
+    fn add_attribute() {
+        let mut tree = SyntaxTree::parse(INPUT).unwrap();
+        assert!(tree.set_node_attribute("start", "color", "light grey"));
+        assert!(tree.set_node_attribute("end", "shape", "Msquare"));
+        assert!(!tree.set_node_attribute("a0", "shape", "box"));
+        let expected = INPUT
+            .replace(r#"here"] ;"#, r#"here", color="light grey"] ;"#)
+            .replace("    end\n", "    end [shape=Msquare]\n");
+        assert_eq!(tree.to_string(), expected);
+    }
+
+    #[test]
+    fn escaped_attributes() {
+        let mut tree = SyntaxTree::parse(INPUT).unwrap();
+        let label = Label::new("a").line_left().text("b");
+        assert!(tree.set_node_attribute("start", "label", &label));
+        let html = Html::text(|text| text.bold(|text| text.text("end"))).unwrap();
+        assert!(tree.set_node_attribute("end", "label", html.to_label()));
+        let expected = INPUT
+            .replace(r#""Start\lhere""#, r#""a\lb""#)
+            .replace("    end\n", "    end [label=<<B>end</B>>]\n");
+        assert_eq!(tree.to_string(), expected);
+        // Plain text is still escaped
+        assert!(tree.set_node_attribute("start", "label", r"a\lb"));
+        assert!(tree.to_string().contains(r#"label="a\\lb""#));
+    }
+
+    #[test]
+    fn remove_attribute() {
+        let mut tree = SyntaxTree::parse(INPUT).unwrap();
+        assert!(tree.remove_node_attribute("start", "shape"));
+        assert!(!tree.remove_node_attribute("start", "shape"));
+        assert_eq!(tree.to_string(), INPUT.replace("[shape=Mdiamond, ", "["));
+        assert!(tree.remove_node_attribute("start", "label"));
+        assert_eq!(
+            tree.to_string(),
+            INPUT.replace(r#"[shape=Mdiamond, label="Start\lhere"]"#, "[]")
+        );
+    }
+
+    #[test]
+    fn to_graph() {
+        let tree = SyntaxTree::parse(INPUT).unwrap();
+        let graph = tree.to_graph();
+        assert_eq!(graph.id.as_ref().map(Id::get), Some("G"));
+        assert_eq!(graph.statements.len(), 3);
+    }
+}
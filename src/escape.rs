@@ -0,0 +1,146 @@
+use std::borrow::Cow;
+
+use crate::ast::Id;
+use crate::lexer::{is_identifier_continue, is_identifier_start, KEYWORDS};
+
+// docs
+// * https://graphviz.org/doc/info/lang.html
+// * https://graphviz.org/docs/attr-types/escString/
+//
+// Id values are stored the way graphviz sees them once the lexer has removed
+// the quotes, i.e. with `\"` already turned into `"` but every other backslash
+// still meaningful. Text coming from users goes through `escape_text` first so
+// that a backslash in it is a backslash and not the start of `\N` or `\l`.
+
+// Formats an Id as the simplest DOT ID that reads back as the same value.
+pub(crate) fn id(id: &Id) -> Cow<'_, str> {
+    let value = id.get();
+    if id.html {
+        Cow::Owned(format!("<{}>", value))
+    } else if is_identifier(value) || is_numeral(value) {
+        Cow::Borrowed(value)
+    } else {
+        Cow::Owned(quote(value))
+    }
+}
+
+// An alphabetic identifier that is not one of the (case-independent) keywords
+pub(crate) fn is_identifier(s: &str) -> bool {
+    s.starts_with(is_identifier_start) && s.chars().all(is_identifier_continue) && !is_keyword(s)
+}
+
+pub(crate) fn is_keyword(s: &str) -> bool {
+    KEYWORDS.iter().any(|k| s.eq_ignore_ascii_case(k))
+}
+
+// numeral : [-]?(.[0-9]⁺ | [0-9]⁺(.[0-9]*)? )
+pub(crate) fn is_numeral(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    let (integer, fraction) = match digits.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (digits, None),
+    };
+    let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    match fraction {
+        None => !integer.is_empty() && all_digits(integer),
+        Some(fraction) => {
+            all_digits(integer)
+                && all_digits(fraction)
+                && (!integer.is_empty() || !fraction.is_empty())
+        }
+    }
+}
+
+// Wraps a value in double quotes, escaping the quotes inside it. Escape
+// sequences such as `\n` or `\N` are written out untouched; a backslash that
+// would otherwise escape the closing quote is doubled.
+pub(crate) fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => match chars.peek() {
+                Some(&next) if next != '"' => {
+                    quoted.push('\\');
+                    quoted.push(next);
+                    chars.next();
+                }
+                _ => quoted.push_str("\\\\"),
+            },
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Turns literal text into an escString: backslashes stand for themselves and
+// line breaks become centered `\n` breaks.
+pub(crate) fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\r' => {
+                if chars.peek() != Some(&'\n') {
+                    escaped.push_str("\\n");
+                }
+            }
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Escapes text for use inside HTML-like labels and their attribute values
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_ids() {
+        assert_eq!(id(&Id::new("a_1")), "a_1");
+        assert_eq!(id(&Id::new("-.5")), "-.5");
+        assert_eq!(id(&Id::new(42)), "42");
+        assert_eq!(id(&Id::new("Ünïcode")), "Ünïcode");
+    }
+
+    #[test]
+    fn quoted_ids() {
+        assert_eq!(id(&Id::new("1a")), "\"1a\"");
+        assert_eq!(id(&Id::new("a b")), "\"a b\"");
+        assert_eq!(id(&Id::new("")), "\"\"");
+        assert_eq!(id(&Id::new(".")), "\".\"");
+        assert_eq!(id(&Id::new("node")), "\"node\"");
+        assert_eq!(id(&Id::new("Subgraph")), "\"Subgraph\"");
+        assert_eq!(id(&Id::new("say \"hi\"")), r#""say \"hi\"""#);
+        assert_eq!(id(&Id::new("C:\\dir\\")), r#""C:\\dir\\""#);
+        assert_eq!(id(&Id::new("two\nlines")), r#""two\nlines""#);
+    }
+
+    #[test]
+    fn escape_sequences() {
+        assert_eq!(quote(r"\N\l\G"), r#""\N\l\G""#);
+        assert_eq!(quote("trailing\\"), r#""trailing\\""#);
+        assert_eq!(quote("\\\""), r#""\\\"""#);
+        assert_eq!(id(&Id::html("<b>x</b>")), "<<b>x</b>>");
+    }
+}
@@ -0,0 +1,484 @@
+use std::collections::HashMap;
+use std::io;
+
+use crate::ast::{Attribute, Graph};
+use crate::attributes::{Intervals, Shape, Style};
+use crate::escape::escape_html;
+use crate::graphml::value_type;
+use crate::layout::node_size;
+use crate::model::{find, number, styles, Model};
+use crate::svg::num;
+
+// docs
+// * https://gexf.net/
+// * https://gexf.net/1.3/gexf.xsd
+// * https://gexf.net/1.3/viz.xsd
+//
+// GEXF the way Gephi reads it. Labels go on the nodes and edges, colors,
+// sizes and shapes into the viz namespace, and every other attribute into
+// attribute values declared per class, typed like GraphML keys. With
+// `intervals` on any node or edge the graph is dynamic, and the type of the
+// bounds decides the time format. Subgraphs are left out.
+
+// Attributes written in a form of their own rather than as values
+const NODE_VIZ: [&str; 6] = ["label", "color", "fillcolor", "shape", "image", "intervals"];
+const EDGE_VIZ: [&str; 6] = ["label", "color", "penwidth", "style", "weight", "intervals"];
+
+struct Declared {
+    id: usize,
+    value_type: &'static str,
+}
+
+pub fn print_gexf<W: io::Write>(writer: &mut W, graph: &Graph) -> io::Result<()> {
+    let model = Model::new(graph);
+    let (mut node_keys, mut edge_keys) = (HashMap::new(), HashMap::new());
+    for node in &model.nodes {
+        declare(&mut node_keys, &node.attributes, &NODE_VIZ);
+    }
+    for edge in &model.edges {
+        declare(&mut edge_keys, &edge.attributes, &EDGE_VIZ);
+    }
+    let mut printer = GexfPrinter {
+        writer,
+        model: &model,
+        node_keys: &node_keys,
+        edge_keys: &edge_keys,
+    };
+    printer.document()
+}
+
+fn declare(keys: &mut HashMap<String, Declared>, attributes: &[Attribute], skip: &[&str]) {
+    for attribute in attributes.iter().filter(|a| !skip.contains(&a.name())) {
+        // GEXF knows no HTML, its markup is a plain string
+        let value_type = match value_type(attribute) {
+            _ if attribute.is_html() => "string",
+            "int" => "integer",
+            other => other,
+        };
+        let id = keys.len();
+        let key = keys
+            .entry(attribute.name().to_string())
+            .or_insert(Declared { id, value_type });
+        // A value that did not parse, e.g. pin=maybe, is a string
+        if key.value_type != value_type {
+            key.value_type = "string";
+        }
+    }
+}
+
+fn intervals(attributes: &[Attribute]) -> Option<&Intervals> {
+    match find(attributes, "intervals") {
+        Some(Attribute::Intervals(intervals)) if !intervals.intervals.is_empty() => Some(intervals),
+        _ => None,
+    }
+}
+
+// Numbers if every bound is one, dates unless some have a time of day
+fn time_format<'a>(bounds: impl Iterator<Item = &'a String>) -> &'static str {
+    let mut format = "double";
+    for bound in bounds {
+        if bound.contains('T') {
+            return "dateTime";
+        }
+        if bound.parse::<f64>().is_err() {
+            format = "date";
+        }
+    }
+    format
+}
+
+fn color(attributes: &[Attribute], names: &[&str]) -> Option<[u8; 4]> {
+    names.iter().find_map(|name| match find(attributes, name) {
+        Some(Attribute::Color(list)) | Some(Attribute::FillColor(list)) => list.first().channels(),
+        _ => None,
+    })
+}
+
+// Gephi draws discs unless told otherwise, and no other shapes but these
+fn viz_shape(shape: &Shape) -> Option<&'static str> {
+    match shape {
+        Shape::Ellipse
+        | Shape::Oval
+        | Shape::Circle
+        | Shape::Point
+        | Shape::Egg
+        | Shape::DoubleCircle
+        | Shape::MCircle => Some("disc"),
+        Shape::Box | Shape::Rect | Shape::Rectangle | Shape::Square | Shape::MSquare => {
+            Some("square")
+        }
+        Shape::Triangle | Shape::InvTriangle => Some("triangle"),
+        Shape::Diamond | Shape::MDiamond => Some("diamond"),
+        _ => None,
+    }
+}
+
+struct GexfPrinter<'a, W: io::Write> {
+    writer: &'a mut W,
+    model: &'a Model,
+    node_keys: &'a HashMap<String, Declared>,
+    edge_keys: &'a HashMap<String, Declared>,
+}
+
+impl<'a, W: io::Write> GexfPrinter<'a, W> {
+    fn document(&mut self) -> io::Result<()> {
+        let model = self.model;
+        writeln!(self.writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
+            self.writer,
+            "<gexf xmlns=\"http://gexf.net/1.3\" xmlns:viz=\"http://gexf.net/1.3/viz\" \
+             xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+             xsi:schemaLocation=\"http://gexf.net/1.3 http://gexf.net/1.3/gexf.xsd\" \
+             version=\"1.3\">"
+        )?;
+        if let Some(label) = find(&model.attributes, "label") {
+            writeln!(self.writer, "  <meta>")?;
+            writeln!(
+                self.writer,
+                "    <description>{}</description>",
+                escape_html(&label.value())
+            )?;
+            writeln!(self.writer, "  </meta>")?;
+        }
+
+        let timed: Vec<&Intervals> = model
+            .nodes
+            .iter()
+            .map(|n| &n.attributes)
+            .chain(model.edges.iter().map(|e| &e.attributes))
+            .filter_map(|attributes| intervals(attributes))
+            .collect();
+        write!(
+            self.writer,
+            "  <graph defaultedgetype=\"{}\"",
+            if model.directed {
+                "directed"
+            } else {
+                "undirected"
+            }
+        )?;
+        if timed.is_empty() {
+            writeln!(self.writer, " mode=\"static\">")?;
+        } else {
+            let bounds = timed
+                .iter()
+                .flat_map(|i| &i.intervals)
+                .flat_map(|(start, end)| start.iter().chain(end));
+            writeln!(
+                self.writer,
+                " mode=\"dynamic\" timeformat=\"{}\" timerepresentation=\"interval\">",
+                time_format(bounds)
+            )?;
+        }
+        self.declarations("node", self.node_keys)?;
+        self.declarations("edge", self.edge_keys)?;
+
+        writeln!(self.writer, "    <nodes>")?;
+        for node in 0..model.nodes.len() {
+            self.node(node)?;
+        }
+        writeln!(self.writer, "    </nodes>")?;
+        writeln!(self.writer, "    <edges>")?;
+        for edge in 0..model.edges.len() {
+            self.edge(edge)?;
+        }
+        writeln!(self.writer, "    </edges>")?;
+        writeln!(self.writer, "  </graph>")?;
+        writeln!(self.writer, "</gexf>")
+    }
+
+    fn declarations(&mut self, class: &str, keys: &HashMap<String, Declared>) -> io::Result<()> {
+        if keys.is_empty() {
+            return Ok(());
+        }
+        let mut keys: Vec<(&String, &Declared)> = keys.iter().collect();
+        keys.sort_by_key(|(_, key)| key.id);
+        writeln!(self.writer, "    <attributes class=\"{}\">", class)?;
+        for (title, key) in keys {
+            writeln!(
+                self.writer,
+                "      <attribute id=\"{}\" title=\"{}\" type=\"{}\"/>",
+                key.id,
+                escape_html(title),
+                key.value_type
+            )?;
+        }
+        writeln!(self.writer, "    </attributes>")
+    }
+
+    fn node(&mut self, node: usize) -> io::Result<()> {
+        let model = self.model;
+        let attributes = &model.nodes[node].attributes;
+        write!(
+            self.writer,
+            "      <node id=\"{}\" label=\"{}\"",
+            escape_html(model.nodes[node].id.get()),
+            escape_html(&model.node_label(node))
+        )?;
+        let spells = self.interval(attributes)?;
+        writeln!(self.writer, ">")?;
+        self.attvalues(attributes, self.node_keys, &NODE_VIZ)?;
+        self.spells(spells)?;
+        self.color(color(attributes, &["fillcolor", "color"]))?;
+        let (width, height) = node_size(model, node);
+        writeln!(
+            self.writer,
+            "        <viz:size value=\"{}\"/>",
+            num(width.max(height) / 2.0)
+        )?;
+        match find(attributes, "image") {
+            Some(image) => writeln!(
+                self.writer,
+                "        <viz:shape value=\"image\" uri=\"{}\"/>",
+                escape_html(&image.value())
+            )?,
+            None => {
+                let shape = match find(attributes, "shape") {
+                    Some(Attribute::Shape(shape)) => viz_shape(shape),
+                    _ => None,
+                };
+                if let Some(shape) = shape {
+                    writeln!(self.writer, "        <viz:shape value=\"{}\"/>", shape)?;
+                }
+            }
+        }
+        writeln!(self.writer, "      </node>")
+    }
+
+    fn edge(&mut self, edge: usize) -> io::Result<()> {
+        let model = self.model;
+        let attributes = &model.edges[edge].attributes;
+        write!(
+            self.writer,
+            "      <edge id=\"{}\" source=\"{}\" target=\"{}\"",
+            edge,
+            escape_html(model.nodes[model.edges[edge].tail].id.get()),
+            escape_html(model.nodes[model.edges[edge].head].id.get())
+        )?;
+        if let Some(label) = model.edge_label(edge) {
+            write!(self.writer, " label=\"{}\"", escape_html(&label))?;
+        }
+        if let Some(weight) = number(attributes, "weight") {
+            write!(self.writer, " weight=\"{}\"", weight)?;
+        }
+        let spells = self.interval(attributes)?;
+        let color = color(attributes, &["color"]);
+        let thickness = number(attributes, "penwidth");
+        let shape = styles(attributes).iter().find_map(|style| match style {
+            Style::Solid => Some("solid"),
+            Style::Dashed => Some("dashed"),
+            Style::Dotted => Some("dotted"),
+            _ => None,
+        });
+        let plain = spells.is_none() && color.is_none() && thickness.is_none() && shape.is_none();
+        if plain && !attributes.iter().any(|a| !EDGE_VIZ.contains(&a.name())) {
+            return writeln!(self.writer, "/>");
+        }
+        writeln!(self.writer, ">")?;
+        self.attvalues(attributes, self.edge_keys, &EDGE_VIZ)?;
+        self.spells(spells)?;
+        self.color(color)?;
+        if let Some(thickness) = thickness {
+            writeln!(
+                self.writer,
+                "        <viz:thickness value=\"{}\"/>",
+                num(thickness)
+            )?;
+        }
+        if let Some(shape) = shape {
+            writeln!(self.writer, "        <viz:shape value=\"{}\"/>", shape)?;
+        }
+        writeln!(self.writer, "      </edge>")
+    }
+
+    // A single interval goes on the element itself, several become spells
+    fn interval(&mut self, attributes: &'a [Attribute]) -> io::Result<Option<&'a Intervals>> {
+        match intervals(attributes) {
+            Some(intervals) if intervals.intervals.len() == 1 => {
+                let (start, end) = &intervals.intervals[0];
+                self.bounds(start, end)?;
+                Ok(None)
+            }
+            spells => Ok(spells),
+        }
+    }
+
+    fn bounds(&mut self, start: &Option<String>, end: &Option<String>) -> io::Result<()> {
+        if let Some(start) = start {
+            write!(self.writer, " start=\"{}\"", escape_html(start))?;
+        }
+        if let Some(end) = end {
+            write!(self.writer, " end=\"{}\"", escape_html(end))?;
+        }
+        Ok(())
+    }
+
+    fn spells(&mut self, intervals: Option<&Intervals>) -> io::Result<()> {
+        let intervals = match intervals {
+            Some(intervals) => intervals,
+            None => return Ok(()),
+        };
+        writeln!(self.writer, "        <spells>")?;
+        for (start, end) in &intervals.intervals {
+            write!(self.writer, "          <spell")?;
+            self.bounds(start, end)?;
+            writeln!(self.writer, "/>")?;
+        }
+        writeln!(self.writer, "        </spells>")
+    }
+
+    fn attvalues(
+        &mut self,
+        attributes: &[Attribute],
+        keys: &HashMap<String, Declared>,
+        skip: &[&str],
+    ) -> io::Result<()> {
+        let values: Vec<&Attribute> = attributes
+            .iter()
+            .filter(|a| !skip.contains(&a.name()))
+            .collect();
+        if values.is_empty() {
+            return Ok(());
+        }
+        writeln!(self.writer, "        <attvalues>")?;
+        for attribute in values {
+            writeln!(
+                self.writer,
+                "          <attvalue for=\"{}\" value=\"{}\"/>",
+                keys[attribute.name()].id,
+                escape_html(&attribute.value())
+            )?;
+        }
+        writeln!(self.writer, "        </attvalues>")
+    }
+
+    fn color(&mut self, channels: Option<[u8; 4]>) -> io::Result<()> {
+        let [r, g, b, a] = match channels {
+            Some(channels) => channels,
+            None => return Ok(()),
+        };
+        write!(
+            self.writer,
+            "        <viz:color r=\"{}\" g=\"{}\" b=\"{}\"",
+            r, g, b
+        )?;
+        if a < 255 {
+            write!(self.writer, " a=\"{}\"", num(a as f64 / 255.0))?;
+        }
+        writeln!(self.writer, "/>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attributes::{Color, EdgeStyle};
+    use crate::builder::directed;
+    use crate::parser::parse_graph;
+    use crate::printer::print_graph;
+
+    fn gexf(graph: &Graph) -> String {
+        let mut writer = Vec::new();
+        print_gexf(&mut writer, graph).unwrap();
+        String::from_utf8(writer).unwrap()
+    }
+
+    #[test]
+    fn document() {
+        let graph = parse_graph(
+            r##"digraph G {
+                label="Deps & more";
+                a [label="A", shape=box, fillcolor="#ff000080", fontname=Helvetica];
+                b [shape=diamond, peripheries=2];
+                a -> b [label=uses, weight=2, penwidth=1.5, style=dashed, color=blue];
+                b -> a;
+            }"##,
+        )
+        .unwrap();
+        assert_eq!(
+            gexf(&graph),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<gexf xmlns="http://gexf.net/1.3" xmlns:viz="http://gexf.net/1.3/viz" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:schemaLocation="http://gexf.net/1.3 http://gexf.net/1.3/gexf.xsd" version="1.3">
+  <meta>
+    <description>Deps &amp; more</description>
+  </meta>
+  <graph defaultedgetype="directed" mode="static">
+    <attributes class="node">
+      <attribute id="0" title="fontname" type="string"/>
+      <attribute id="1" title="peripheries" type="integer"/>
+    </attributes>
+    <nodes>
+      <node id="a" label="A">
+        <attvalues>
+          <attvalue for="0" value="Helvetica"/>
+        </attvalues>
+        <viz:color r="255" g="0" b="0" a="0.5"/>
+        <viz:size value="27"/>
+        <viz:shape value="square"/>
+      </node>
+      <node id="b" label="b">
+        <attvalues>
+          <attvalue for="1" value="2"/>
+        </attvalues>
+        <viz:size value="27"/>
+        <viz:shape value="diamond"/>
+      </node>
+    </nodes>
+    <edges>
+      <edge id="0" source="a" target="b" label="uses" weight="2">
+        <viz:color r="0" g="0" b="255"/>
+        <viz:thickness value="1.5"/>
+        <viz:shape value="dashed"/>
+      </edge>
+      <edge id="1" source="b" target="a"/>
+    </edges>
+  </graph>
+</gexf>
+"#
+        );
+    }
+
+    #[test]
+    fn intervals() {
+        let graph = parse_graph(r#"graph { a [intervals="1,3;5,"]; b; a -- b [intervals="2,4"] }"#)
+            .unwrap();
+        let written = gexf(&graph);
+        assert!(written.contains(
+            "<graph defaultedgetype=\"undirected\" mode=\"dynamic\" timeformat=\"double\" \
+             timerepresentation=\"interval\">"
+        ));
+        assert!(written.contains(
+            "<node id=\"a\" label=\"a\">\n        <spells>\n          \
+             <spell start=\"1\" end=\"3\"/>\n          <spell start=\"5\"/>\n        </spells>"
+        ));
+        assert!(written.contains("<node id=\"b\" label=\"b\">"));
+        assert!(
+            written.contains("<edge id=\"0\" source=\"a\" target=\"b\" start=\"2\" end=\"4\"/>")
+        );
+
+        let mut builder = directed().gexf();
+        builder
+            .node("a", |n| n.since("2020-01-01").until("2019-01-01"))
+            .edge("a", "b", |e| {
+                e.style(EdgeStyle::Dotted)
+                    .color(Color::rgb(0, 128, 0))
+                    .interval("2020-01-01", "2021-06-01T12:00:00")
+            });
+        let graph = builder.build();
+        let written = gexf(&graph);
+        assert!(written.contains("timeformat=\"dateTime\""));
+        assert!(written
+            .contains("<spell start=\"2020-01-01\"/>\n          <spell end=\"2019-01-01\"/>"));
+        assert!(written.contains(
+            "<edge id=\"0\" source=\"a\" target=\"b\" start=\"2020-01-01\" end=\"2021-06-01T12:00:00\">\n        \
+             <viz:color r=\"0\" g=\"128\" b=\"0\"/>\n        <viz:shape value=\"dotted\"/>"
+        ));
+
+        let mut writer = Vec::new();
+        print_graph(&mut writer, &graph).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            "digraph {\n  a;\n  a -> b [style=dotted, color=\"#008000\"];\n}\n"
+        );
+    }
+}
@@ -0,0 +1,1011 @@
+use std::collections::HashMap;
+use std::io;
+
+use crate::ast::{Attribute, AttributeScope, Graph, GraphType, Id, NodeId, Statement};
+use crate::attributes::Align;
+use crate::json::{self, quote, Kind, Value};
+use crate::layout::{ClusterLayout, EdgeLayout, Layout, NodeLayout, Point, PAD, POINTS_PER_INCH};
+use crate::model::Model;
+use crate::parser::{attribute, ParseError};
+
+// docs
+// * https://graphviz.org/docs/outputs/json/
+// * https://graphviz.org/docs/outputs/canon/#xdot
+//
+// Graphviz's JSON keeps the root graph's attributes next to `objects`, the
+// subgraphs followed by the nodes, and `edges`. Objects and edges are
+// numbered by `_gvid`, their index in the array, and refer to each other by
+// it. A subgraph lists the subgraphs directly inside it, and every node and
+// edge inside it at any depth. Attribute values are strings in DOT form,
+// HTML-like ones in angle brackets. Anonymous graphs are named `%` and a
+// number.
+//
+// `-Tjson` adds the layout to `-Tjson0`: `bb`, `pos` and `lp` in points with
+// y growing upwards, node sizes in inches, and the xdot drawing operations
+// of `_draw_`, `_ldraw_` and friends. A graph read with a `bb` has all of
+// these moved into its layout, node sizes included.
+
+// Members that are not attributes
+const STRUCTURE: [&str; 10] = [
+    "name",
+    "directed",
+    "strict",
+    "_subgraph_cnt",
+    "_gvid",
+    "subgraphs",
+    "objects",
+    "nodes",
+    "edges",
+    "xdotversion",
+];
+
+// Attributes a layout writes, which the reader moves into the layout
+const LAYOUT: [&str; 14] = [
+    "bb", "pos", "lp", "xlp", "head_lp", "tail_lp", "lwidth", "lheight", "_draw_", "_ldraw_",
+    "_hdraw_", "_tdraw_", "_hldraw_", "_tldraw_",
+];
+
+// Node attributes that a layout writes too, moved as well when there is one
+const NODE_SIZE: [&str; 2] = ["width", "height"];
+
+const DRAW: [&str; 6] = [
+    "_draw_", "_ldraw_", "_hdraw_", "_tdraw_", "_hldraw_", "_tldraw_",
+];
+
+// Writes the graph the way `dot -Tjson0` does, with the attributes every
+// node, edge and subgraph resolves to
+pub fn print_json<W: io::Write>(writer: &mut W, graph: &Graph) -> io::Result<()> {
+    let model = Model::new(graph);
+    let count = model.subgraphs.len();
+    let list = |indices: &[usize]| {
+        let indices: Vec<String> = indices.iter().map(usize::to_string).collect();
+        format!("[{}]", indices.join(", "))
+    };
+    // Whether an edge was written inside a subgraph, at any depth
+    let inside = |edge: usize, subgraph: usize| {
+        let mut parent = model.edges[edge].subgraph;
+        while let Some(s) = parent {
+            if s == subgraph {
+                return true;
+            }
+            parent = model.subgraphs[s].parent;
+        }
+        false
+    };
+    let children = |parent: Option<usize>| -> Vec<usize> {
+        (0..count)
+            .filter(|&s| model.subgraphs[s].parent == parent)
+            .collect()
+    };
+
+    let mut objects = Vec::new();
+    for (s, subgraph) in model.subgraphs.iter().enumerate() {
+        let mut members = vec![
+            ("_gvid".to_string(), s.to_string()),
+            ("name".to_string(), quote(&name(&subgraph.id, s + 1))),
+        ];
+        members.extend(attribute_members(&subgraph.attributes));
+        let inner = children(Some(s));
+        if !inner.is_empty() {
+            members.push(("subgraphs".to_string(), list(&inner)));
+        }
+        let mut nodes: Vec<usize> = subgraph.nodes.iter().map(|n| count + n).collect();
+        nodes.sort_unstable();
+        if !nodes.is_empty() {
+            members.push(("nodes".to_string(), list(&nodes)));
+        }
+        let edges: Vec<usize> = (0..model.edges.len()).filter(|&e| inside(e, s)).collect();
+        if !edges.is_empty() {
+            members.push(("edges".to_string(), list(&edges)));
+        }
+        objects.push(object(2, &members));
+    }
+    for (n, node) in model.nodes.iter().enumerate() {
+        let mut members = vec![
+            ("_gvid".to_string(), (count + n).to_string()),
+            ("name".to_string(), quote(node.id.get())),
+        ];
+        members.extend(attribute_members(&node.attributes));
+        objects.push(object(2, &members));
+    }
+    let mut edges = Vec::new();
+    for (e, edge) in model.edges.iter().enumerate() {
+        let mut members = vec![
+            ("_gvid".to_string(), e.to_string()),
+            ("tail".to_string(), (count + edge.tail).to_string()),
+            ("head".to_string(), (count + edge.head).to_string()),
+        ];
+        if let Some(port) = edge.from.port_text() {
+            members.push(("tailport".to_string(), quote(&port)));
+        }
+        if let Some(port) = edge.to.port_text() {
+            members.push(("headport".to_string(), quote(&port)));
+        }
+        members.extend(attribute_members(&edge.attributes));
+        edges.push(object(2, &members));
+    }
+
+    let mut members = vec![
+        ("name".to_string(), quote(&name(&model.id, 0))),
+        ("directed".to_string(), model.directed.to_string()),
+        ("strict".to_string(), graph.strict.to_string()),
+    ];
+    members.extend(attribute_members(&model.attributes));
+    members.push(("_subgraph_cnt".to_string(), count.to_string()));
+    let top = children(None);
+    if !top.is_empty() {
+        members.push(("subgraphs".to_string(), list(&top)));
+    }
+    members.push(("objects".to_string(), array(1, &objects)));
+    members.push(("edges".to_string(), array(1, &edges)));
+    writeln!(writer, "{}", object(0, &members))
+}
+
+fn name(id: &Option<Id>, index: usize) -> String {
+    match id {
+        Some(id) => id.get().to_string(),
+        None => format!("%{}", index),
+    }
+}
+
+fn attribute_members(attributes: &[Attribute]) -> Vec<(String, String)> {
+    attributes
+        .iter()
+        .map(|a| {
+            let value = if a.is_html() {
+                format!("<{}>", a.value())
+            } else {
+                a.value()
+            };
+            (a.name().to_string(), quote(&value))
+        })
+        .collect()
+}
+
+// Members one per line, at `depth` levels of indentation
+fn object(depth: usize, members: &[(String, String)]) -> String {
+    let indent = "  ".repeat(depth + 1);
+    let lines: Vec<String> = members
+        .iter()
+        .map(|(key, value)| format!("{}{}: {}", indent, quote(key), value))
+        .collect();
+    format!("{{\n{}\n{}}}", lines.join(",\n"), "  ".repeat(depth))
+}
+
+fn array(depth: usize, items: &[String]) -> String {
+    if items.is_empty() {
+        return "[]".to_string();
+    }
+    let indent = "  ".repeat(depth + 1);
+    let lines: Vec<String> = items
+        .iter()
+        .map(|item| format!("{}{}", indent, item))
+        .collect();
+    format!("[\n{}\n{}]", lines.join(",\n"), "  ".repeat(depth))
+}
+
+// A graph read from graphviz's JSON, with the layout of `-Tjson` output
+pub struct JsonGraph {
+    // The graph without the attributes its layout added
+    pub graph: Graph,
+    // `None` without a `bb`, i.e. for `-Tjson0`
+    pub layout: Option<Layout>,
+    pub ops: DrawOps,
+}
+
+// The xdot drawing operations of every element, in the order of the
+// layout's nodes, edges and clusters and in layout coordinates. Empty
+// without a layout.
+#[derive(Default)]
+pub struct DrawOps {
+    pub graph: Vec<DrawOp>,
+    pub nodes: Vec<Vec<DrawOp>>,
+    pub edges: Vec<Vec<DrawOp>>,
+    pub clusters: Vec<Vec<DrawOp>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawOp {
+    // `e` and `E`, with the radii along each axis
+    Ellipse {
+        filled: bool,
+        center: Point,
+        rx: f64,
+        ry: f64,
+    },
+    // `p` and `P`
+    Polygon {
+        filled: bool,
+        points: Vec<Point>,
+    },
+    // `L`
+    Polyline(Vec<Point>),
+    // `b` and `B`, a cubic B-spline's start point and then two control
+    // points and an end point per segment
+    Bezier {
+        filled: bool,
+        points: Vec<Point>,
+    },
+    // `T`, with `point` on the baseline
+    Text {
+        point: Point,
+        align: Align,
+        width: f64,
+        text: String,
+    },
+    // `c` and `C` for the pen and the fill, a gradient's first stop
+    Color {
+        fill: bool,
+        color: String,
+    },
+    // `F`
+    Font {
+        size: f64,
+        face: String,
+    },
+    // `S`
+    Style(String),
+    // `t`, bold, italic and so on as bit flags
+    FontChar(u32),
+    // `I`, from the top left corner
+    Image {
+        point: Point,
+        width: f64,
+        height: f64,
+        name: String,
+    },
+}
+
+// Reads `-Tjson` or `-Tjson0` output. Layout positions are turned into the
+// coordinates of `layout::layout`, with the origin at the top left.
+pub fn parse_json(input: &str) -> Result<JsonGraph, ParseError> {
+    let root = json::parse(input)?;
+    if !matches!(root.kind, Kind::Object(_)) {
+        return Err(root.error("expected an object"));
+    }
+    let empty = Vec::new();
+    let objects = match root.get("objects") {
+        Some(objects) => objects
+            .as_array()
+            .ok_or_else(|| objects.error("expected a list of objects"))?,
+        None => &empty,
+    };
+    let edges = match root.get("edges") {
+        Some(edges) => edges
+            .as_array()
+            .ok_or_else(|| edges.error("expected a list of edges"))?,
+        None => &empty,
+    };
+    let count = match root.get("_subgraph_cnt") {
+        Some(count) => index(count, objects.len() + 1)?,
+        None => 0,
+    };
+
+    let mut reader = JsonReader {
+        objects,
+        edges,
+        count,
+        laid_out: root.get("bb").is_some(),
+        children: Vec::new(),
+        depths: vec![0; count],
+        node_homes: Vec::new(),
+        edge_homes: Vec::new(),
+        subgraph_order: Vec::new(),
+        edge_order: Vec::new(),
+    };
+    reader.structure(&root)?;
+    let statements = reader.statements(&root, None)?;
+    let graph = Graph {
+        strict: root.get("strict").and_then(Value::as_bool).unwrap_or(false),
+        gtype: match root.get("directed").and_then(Value::as_bool) {
+            Some(false) => GraphType::Undirected,
+            _ => GraphType::Directed,
+        },
+        id: object_name(&root),
+        statements,
+        engine: None,
+        format: None,
+    };
+    let (layout, ops) = match root.get("bb") {
+        Some(bb) => {
+            let (layout, ops) = reader.layout(&root, bb, &graph)?;
+            (Some(layout), ops)
+        }
+        None => (None, DrawOps::default()),
+    };
+    Ok(JsonGraph { graph, layout, ops })
+}
+
+struct JsonReader<'a> {
+    objects: &'a [Value],
+    edges: &'a [Value],
+    // The number of subgraphs at the start of `objects`
+    count: usize,
+    // Whether the document has a layout to move node sizes into
+    laid_out: bool,
+    // The subgraphs directly inside each subgraph, the root graph last
+    children: Vec<Vec<usize>>,
+    depths: Vec<usize>,
+    // The innermost subgraph each node and edge is in, `None` for the root
+    node_homes: Vec<Option<usize>>,
+    edge_homes: Vec<Option<usize>>,
+    // Subgraphs and edges in the order the statements mention them
+    subgraph_order: Vec<usize>,
+    edge_order: Vec<usize>,
+}
+
+enum Item {
+    // A node with its attributes, or just mentioned to put it in a subgraph
+    Node(usize, bool),
+    Subgraph(usize),
+}
+
+impl<'a> JsonReader<'a> {
+    // Works out which subgraph is inside which, and where nodes and edges go
+    fn structure(&mut self, root: &Value) -> Result<(), ParseError> {
+        let mut parents = vec![None; self.count];
+        for s in 0..self.count {
+            let inner = self.indices(&self.objects[s], "subgraphs", self.count)?;
+            for &child in &inner {
+                // Subgraphs come after the subgraph they are in, which rules
+                // out cycles
+                if child <= s {
+                    return Err(self.objects[s].error("subgraphs must follow their parent"));
+                }
+                parents[child] = Some(s);
+                self.depths[child] = self.depths[s] + 1;
+            }
+            self.children.push(inner);
+        }
+        let top = match root.get("subgraphs") {
+            Some(_) => self.indices(root, "subgraphs", self.count)?,
+            None => (0..self.count).filter(|&s| parents[s].is_none()).collect(),
+        };
+        self.children.push(top);
+
+        let nodes = self.objects.len() - self.count;
+        self.node_homes = vec![None; nodes];
+        self.edge_homes = vec![None; self.edges.len()];
+        for s in 0..self.count {
+            let object = &self.objects[s];
+            for gvid in self.indices(object, "nodes", self.objects.len())? {
+                let node = gvid
+                    .checked_sub(self.count)
+                    .ok_or_else(|| object.error(&format!("object {} is not a node", gvid)))?;
+                Self::place(&mut self.node_homes[node], s, &self.depths);
+            }
+            for edge in self.indices(object, "edges", self.edges.len())? {
+                Self::place(&mut self.edge_homes[edge], s, &self.depths);
+            }
+        }
+        Ok(())
+    }
+
+    // Keeps the most deeply nested subgraph, the first one of several
+    fn place(home: &mut Option<usize>, subgraph: usize, depths: &[usize]) {
+        match home {
+            Some(h) if depths[*h] >= depths[subgraph] => {}
+            _ => *home = Some(subgraph),
+        }
+    }
+
+    fn indices(&self, object: &Value, key: &str, limit: usize) -> Result<Vec<usize>, ParseError> {
+        match object.get(key) {
+            Some(list) => list
+                .as_array()
+                .ok_or_else(|| list.error(&format!("expected a list of indices for '{}'", key)))?
+                .iter()
+                .map(|value| index(value, limit))
+                .collect(),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn statements(
+        &mut self,
+        object: &Value,
+        subgraph: Option<usize>,
+    ) -> Result<Vec<Statement>, ParseError> {
+        let mut statements = Vec::new();
+        let attributes = object_attributes(object, &[])?;
+        if !attributes.is_empty() {
+            statements.push(Statement::Attribute(AttributeScope::Graph, attributes));
+        }
+
+        let children = &self.children[subgraph.unwrap_or(self.count)];
+        let mut items: Vec<(usize, Item)> = Vec::new();
+        for (node, home) in self.node_homes.iter().enumerate() {
+            if *home == subgraph {
+                items.push((node, Item::Node(node, true)));
+            }
+        }
+        if subgraph.is_some() {
+            // Nodes that live in another subgraph but are in this one too
+            let nested: Vec<usize> = children
+                .iter()
+                .map(|&c| self.indices(&self.objects[c], "nodes", self.objects.len()))
+                .collect::<Result<Vec<_>, _>>()?
+                .concat();
+            for gvid in self.indices(object, "nodes", self.objects.len())? {
+                let node = gvid - self.count;
+                if self.node_homes[node] != subgraph && !nested.contains(&gvid) {
+                    items.push((node, Item::Node(node, false)));
+                }
+            }
+        }
+        for &child in children {
+            let first = self
+                .indices(&self.objects[child], "nodes", self.objects.len())?
+                .into_iter()
+                .min()
+                .map_or(usize::MAX, |gvid| gvid - self.count);
+            items.push((first, Item::Subgraph(child)));
+        }
+        items.sort_by_key(|(first, _)| *first);
+
+        for (_, item) in items {
+            match item {
+                Item::Node(node, declared) => {
+                    let object = &self.objects[self.count + node];
+                    let id =
+                        object_name(object).ok_or_else(|| object.error("node without a name"))?;
+                    let skip: &[&str] = if self.laid_out { &NODE_SIZE } else { &[] };
+                    let attributes = if declared {
+                        object_attributes(object, skip)?
+                    } else {
+                        Vec::new()
+                    };
+                    statements.push(Statement::Node { id, attributes });
+                }
+                Item::Subgraph(child) => {
+                    self.subgraph_order.push(child);
+                    let object = &self.objects[child];
+                    statements.push(Statement::Subgraph {
+                        id: object_name(object),
+                        statements: self.statements(object, Some(child))?,
+                    });
+                }
+            }
+        }
+
+        for (e, edge) in self.edges.iter().enumerate() {
+            if self.edge_homes[e] != subgraph {
+                continue;
+            }
+            self.edge_order.push(e);
+            let end = |key: &str, port: &str| -> Result<NodeId, ParseError> {
+                let value = edge
+                    .get(key)
+                    .ok_or_else(|| edge.error(&format!("edge without a {}", key)))?;
+                let gvid = index(value, self.objects.len())?;
+                let object = self
+                    .objects
+                    .get(gvid)
+                    .filter(|_| gvid >= self.count)
+                    .ok_or_else(|| value.error(&format!("object {} is not a node", gvid)))?;
+                let id = object_name(object).ok_or_else(|| object.error("node without a name"))?;
+                let node = NodeId::from(id);
+                Ok(match edge.get(port).and_then(Value::as_str) {
+                    Some(port) => node.with_port_text(port),
+                    None => node,
+                })
+            };
+            statements.push(Statement::Edge {
+                from: end("tail", "tailport")?,
+                to: end("head", "headport")?,
+                attributes: object_attributes(edge, &["tail", "head", "tailport", "headport"])?,
+            });
+        }
+        Ok(statements)
+    }
+
+    fn layout(
+        &self,
+        root: &Value,
+        bb: &Value,
+        graph: &Graph,
+    ) -> Result<(Layout, DrawOps), ParseError> {
+        let (x0, y0, x1, y1) = match floats(bb)?[..] {
+            [x0, y0, x1, y1] => (x0, y0, x1, y1),
+            _ => return Err(bb.error("expected a bounding box")),
+        };
+        let flip = |x: f64, y: f64| Point::new(x - x0 + PAD, y1 - y + PAD);
+        let model = Model::new(graph);
+        let mut layout = Layout {
+            width: x1 - x0 + 2.0 * PAD,
+            height: y1 - y0 + 2.0 * PAD,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            clusters: Vec::new(),
+        };
+        let mut ops = DrawOps {
+            graph: draw_ops(root, &flip)?,
+            ..DrawOps::default()
+        };
+        let point = |object: &Value, key: &str| -> Result<Option<Point>, ParseError> {
+            match object.get(key) {
+                Some(value) => match floats(value)?[..] {
+                    [x, y, ..] => Ok(Some(flip(x, y))),
+                    _ => Err(value.error("expected a point")),
+                },
+                None => Ok(None),
+            }
+        };
+
+        let by_name: HashMap<&str, &Value> = self.objects[self.count..]
+            .iter()
+            .filter_map(|o| Some((o.get("name")?.as_str()?, o)))
+            .collect();
+        for node in &model.nodes {
+            let object = by_name[node.id.get()];
+            let size = |key: &str, default: f64| {
+                object
+                    .get(key)
+                    .and_then(|v| v.as_str()?.parse().ok())
+                    .unwrap_or(default)
+                    * POINTS_PER_INCH
+            };
+            layout.nodes.push(NodeLayout {
+                id: node.id.clone(),
+                center: point(object, "pos")?.ok_or_else(|| object.error("node without a pos"))?,
+                width: size("width", 0.75),
+                height: size("height", 0.5),
+            });
+            ops.nodes.push(draw_ops(object, &flip)?);
+        }
+
+        for (edge, &e) in model.edges.iter().zip(&self.edge_order) {
+            let object = &self.edges[e];
+            let pos = object
+                .get("pos")
+                .ok_or_else(|| object.error("edge without a pos"))?;
+            layout.edges.push(EdgeLayout {
+                tail: edge.from.id.clone(),
+                head: edge.to.id.clone(),
+                points: spline_points(pos, &flip)?,
+                label: point(object, "lp")?,
+            });
+            ops.edges.push(draw_ops(object, &flip)?);
+        }
+
+        for cluster in model.clusters() {
+            let object = &self.objects[self.subgraph_order[cluster]];
+            // Graphviz leaves out the bounding box of empty clusters
+            let bb = match object.get("bb") {
+                Some(bb) => floats(bb)?,
+                None => continue,
+            };
+            let (min, max) = match bb[..] {
+                [x0, y0, x1, y1] => (flip(x0, y1), flip(x1, y0)),
+                _ => return Err(object.error("expected a bounding box")),
+            };
+            let id = match &model.subgraphs[cluster].id {
+                Some(id) => id.clone(),
+                None => continue,
+            };
+            layout.clusters.push(ClusterLayout {
+                id,
+                min,
+                max,
+                label: point(object, "lp")?,
+            });
+            ops.clusters.push(draw_ops(object, &flip)?);
+        }
+        Ok((layout, ops))
+    }
+}
+
+fn index(value: &Value, limit: usize) -> Result<usize, ParseError> {
+    match value.as_f64() {
+        Some(n) if n >= 0.0 && n.fract() == 0.0 && (n as usize) < limit => Ok(n as usize),
+        _ => Err(value.error(&format!("expected an index below {}", limit))),
+    }
+}
+
+// The `name` of a graph or node, anonymous graphs have none
+fn object_name(object: &Value) -> Option<Id> {
+    let name = object.get("name")?.as_str()?;
+    let anonymous = name
+        .strip_prefix('%')
+        .is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()));
+    if anonymous {
+        None
+    } else {
+        Some(Id::from_dot(name))
+    }
+}
+
+// The attributes among an object's members
+fn object_attributes(object: &Value, skip: &[&str]) -> Result<Vec<Attribute>, ParseError> {
+    let mut attributes = Vec::new();
+    for (key, value) in object.members() {
+        let key = key.as_str();
+        if STRUCTURE.contains(&key) || LAYOUT.contains(&key) || skip.contains(&key) {
+            continue;
+        }
+        let text = match &value.kind {
+            Kind::String(s) => s.clone(),
+            Kind::Number(n) => n.to_string(),
+            Kind::Bool(b) => b.to_string(),
+            _ => return Err(value.error(&format!("expected a value for '{}'", key))),
+        };
+        let value = match text.strip_prefix('<').and_then(|t| t.strip_suffix('>')) {
+            Some(html) => Id::html(html),
+            None => Id::from_dot(text),
+        };
+        attributes.push(attribute(Id::from_dot(key), value));
+    }
+    Ok(attributes)
+}
+
+// The numbers of a `pos` or `bb`, without the `!` of pinned positions
+fn floats(value: &Value) -> Result<Vec<f64>, ParseError> {
+    let text = value
+        .as_str()
+        .ok_or_else(|| value.error("expected coordinates"))?;
+    text.trim_end_matches('!')
+        .split(',')
+        .map(|n| n.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| value.error(&format!("invalid coordinates '{}'", text)))
+}
+
+// The points of an edge's spline that lie on it, from the tail's arrowhead
+// tip, if any, to the head's. Only the first of several splines is used.
+fn spline_points(pos: &Value, flip: &impl Fn(f64, f64) -> Point) -> Result<Vec<Point>, ParseError> {
+    let text = pos
+        .as_str()
+        .ok_or_else(|| pos.error("expected an edge position"))?;
+    let invalid = || pos.error(&format!("invalid edge position '{}'", text));
+    let (mut start, mut end, mut controls) = (None, None, Vec::new());
+    for token in text.split(';').next().unwrap_or("").split_whitespace() {
+        let numbers: Vec<&str> = token.split(',').collect();
+        let (target, numbers) = match numbers[..] {
+            ["s", x, y] => (&mut start, [x, y]),
+            ["e", x, y] => (&mut end, [x, y]),
+            [x, y] => {
+                let x: f64 = x.parse().map_err(|_| invalid())?;
+                let y: f64 = y.parse().map_err(|_| invalid())?;
+                controls.push(flip(x, y));
+                continue;
+            }
+            _ => return Err(invalid()),
+        };
+        let x: f64 = numbers[0].parse().map_err(|_| invalid())?;
+        let y: f64 = numbers[1].parse().map_err(|_| invalid())?;
+        *target = Some(flip(x, y));
+    }
+    let mut points: Vec<Point> = start.into_iter().collect();
+    points.extend(controls.into_iter().step_by(3));
+    points.extend(end);
+    Ok(points)
+}
+
+// The operations of every xdot member of an object
+fn draw_ops(object: &Value, flip: &impl Fn(f64, f64) -> Point) -> Result<Vec<DrawOp>, ParseError> {
+    let mut ops = Vec::new();
+    for key in DRAW {
+        let list = match object.get(key) {
+            Some(list) => list
+                .as_array()
+                .ok_or_else(|| list.error("expected a list of drawing operations"))?,
+            None => continue,
+        };
+        for op in list {
+            ops.push(draw_op(op, flip)?);
+        }
+    }
+    Ok(ops)
+}
+
+fn draw_op(op: &Value, flip: &impl Fn(f64, f64) -> Point) -> Result<DrawOp, ParseError> {
+    let number = |key: &str| {
+        op.get(key)
+            .and_then(Value::as_f64)
+            .ok_or_else(|| op.error(&format!("expected a number for '{}'", key)))
+    };
+    let string = |key: &str| {
+        op.get(key)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| op.error(&format!("expected a string for '{}'", key)))
+    };
+    let numbers = |key: &str, n: usize| -> Result<Vec<f64>, ParseError> {
+        op.get(key)
+            .and_then(Value::as_array)
+            .filter(|values| values.len() == n)
+            .and_then(|values| values.iter().map(Value::as_f64).collect())
+            .ok_or_else(|| op.error(&format!("expected {} numbers for '{}'", n, key)))
+    };
+    let points = || -> Result<Vec<Point>, ParseError> {
+        let list = op
+            .get("points")
+            .and_then(Value::as_array)
+            .ok_or_else(|| op.error("expected a list of points"))?;
+        list.iter()
+            .map(|p| match p.as_array() {
+                Some([x, y]) => match (x.as_f64(), y.as_f64()) {
+                    (Some(x), Some(y)) => Ok(flip(x, y)),
+                    _ => Err(p.error("expected a point")),
+                },
+                _ => Err(p.error("expected a point")),
+            })
+            .collect()
+    };
+
+    let name = op
+        .get("op")
+        .and_then(Value::as_str)
+        .ok_or_else(|| op.error("expected an 'op'"))?;
+    Ok(match name {
+        "e" | "E" => {
+            let rect = numbers("rect", 4)?;
+            DrawOp::Ellipse {
+                filled: name == "E",
+                center: flip(rect[0], rect[1]),
+                rx: rect[2],
+                ry: rect[3],
+            }
+        }
+        "p" | "P" => DrawOp::Polygon {
+            filled: name == "P",
+            points: points()?,
+        },
+        "L" => DrawOp::Polyline(points()?),
+        "b" | "B" => DrawOp::Bezier {
+            filled: name == "B",
+            points: points()?,
+        },
+        "T" => {
+            let point = numbers("pt", 2)?;
+            DrawOp::Text {
+                point: flip(point[0], point[1]),
+                align: match string("align")?.as_str() {
+                    "l" => Align::Left,
+                    "r" => Align::Right,
+                    _ => Align::Center,
+                },
+                width: number("width")?,
+                text: string("text")?,
+            }
+        }
+        "c" | "C" => {
+            let color = match op.get("color").and_then(Value::as_str) {
+                Some(color) => color.to_string(),
+                None => op
+                    .get("stops")
+                    .and_then(Value::as_array)
+                    .and_then(|stops| stops.first()?.get("color")?.as_str())
+                    .map(str::to_string)
+                    .ok_or_else(|| op.error("expected a color"))?,
+            };
+            DrawOp::Color {
+                fill: name == "C",
+                color,
+            }
+        }
+        "F" => DrawOp::Font {
+            size: number("size")?,
+            face: string("face")?,
+        },
+        "S" => DrawOp::Style(string("style")?),
+        "t" => DrawOp::FontChar(number("fontchar")? as u32),
+        "I" => {
+            let pos = numbers("pos", 2)?;
+            let size = numbers("size", 2)?;
+            DrawOp::Image {
+                point: flip(pos[0], pos[1] + size[1]),
+                width: size[0],
+                height: size[1],
+                name: string("name")?,
+            }
+        }
+        _ => return Err(op.error(&format!("unknown drawing operation '{}'", name))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_graph;
+    use crate::printer::print_graph;
+
+    fn json(graph: &Graph) -> String {
+        let mut writer = Vec::new();
+        print_json(&mut writer, graph).unwrap();
+        String::from_utf8(writer).unwrap()
+    }
+
+    fn dot(graph: &Graph) -> String {
+        let mut writer = Vec::new();
+        print_graph(&mut writer, graph).unwrap();
+        String::from_utf8(writer).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let graph = parse_graph(
+            r#"digraph G {
+                rankdir=LR;
+                a [label=<<b>A</b>>];
+                subgraph cluster_x {
+                    label="X\nY";
+                    b;
+                    subgraph { rank=same; c; d; c -> d }
+                }
+                a:p:n -> b [arrowsize=0.5];
+            }"#,
+        )
+        .unwrap();
+        let written = json(&graph);
+        assert_eq!(
+            written,
+            r#"{
+  "name": "G",
+  "directed": true,
+  "strict": false,
+  "rankdir": "LR",
+  "_subgraph_cnt": 2,
+  "subgraphs": [0],
+  "objects": [
+    {
+      "_gvid": 0,
+      "name": "cluster_x",
+      "label": "X\\nY",
+      "subgraphs": [1],
+      "nodes": [3, 4, 5],
+      "edges": [0]
+    },
+    {
+      "_gvid": 1,
+      "name": "%2",
+      "rank": "same",
+      "nodes": [4, 5],
+      "edges": [0]
+    },
+    {
+      "_gvid": 2,
+      "name": "a",
+      "label": "<<b>A</b>>"
+    },
+    {
+      "_gvid": 3,
+      "name": "b"
+    },
+    {
+      "_gvid": 4,
+      "name": "c"
+    },
+    {
+      "_gvid": 5,
+      "name": "d"
+    }
+  ],
+  "edges": [
+    {
+      "_gvid": 0,
+      "tail": 4,
+      "head": 5
+    },
+    {
+      "_gvid": 1,
+      "tail": 2,
+      "head": 3,
+      "tailport": "p:n",
+      "arrowsize": "0.5"
+    }
+  ]
+}
+"#
+        );
+
+        let read = parse_json(&written).unwrap();
+        assert!(read.layout.is_none());
+        assert_eq!(json(&read.graph), written);
+        assert!(dot(&read.graph).contains("a:p:n -> b [arrowsize=0.5];"));
+        assert!(dot(&read.graph).contains("a [label=<<b>A</b>>];"));
+    }
+
+    #[test]
+    fn layout() {
+        let read = parse_json(
+            r##"{
+              "name": "G", "directed": true, "strict": false,
+              "bb": "0,0,80,160", "xdotversion": "1.7",
+              "_draw_": [
+                {"op": "c", "grad": "none", "color": "#fffffe00"},
+                {"op": "P", "points": [[0, 0], [0, 160], [80, 160], [80, 0]]}
+              ],
+              "_subgraph_cnt": 1,
+              "objects": [
+                {"_gvid": 0, "name": "cluster_x", "label": "X",
+                 "bb": "8,80,72,152", "lp": "40,140",
+                 "_draw_": [{"op": "p", "points": [[8, 80], [8, 152], [72, 152], [72, 80]]}],
+                 "_ldraw_": [
+                   {"op": "F", "size": 14, "face": "Times-Roman"},
+                   {"op": "T", "pt": [40, 135], "align": "c", "width": 8, "text": "X"}
+                 ],
+                 "nodes": [1], "edges": []},
+                {"_gvid": 1, "name": "a", "pos": "40,106", "width": "0.75", "height": "0.5",
+                 "_draw_": [{"op": "e", "rect": [40, 106, 27, 18]}]},
+                {"_gvid": 2, "name": "b", "pos": "40,18!", "width": "1", "height": "0.5",
+                 "_draw_": [{"op": "E", "rect": [40, 18, 36, 18]}]}
+              ],
+              "edges": [
+                {"_gvid": 0, "tail": 1, "head": 2, "label": "l",
+                 "pos": "e,40,36 40,88 40,76 40,60 40,46", "lp": "43,62",
+                 "_draw_": [{"op": "b", "points": [[40, 88], [40, 76], [40, 60], [40, 46]]}],
+                 "_hdraw_": [
+                   {"op": "S", "style": "solid"},
+                   {"op": "C", "grad": "linear", "p0": [0, 0, 0], "p1": [1, 1, 0],
+                    "stops": [{"frac": 0, "color": "red"}, {"frac": 1, "color": "blue"}]}
+                 ]}
+              ]
+            }"##,
+        )
+        .unwrap();
+        let text = dot(&read.graph);
+        assert!(!text.contains("pos") && !text.contains("bb") && !text.contains("_draw_"));
+        assert!(text.contains("a -> b [label=\"l\"];"));
+        assert!(!text.contains("width") && !text.contains("height"));
+
+        let layout = read.layout.unwrap();
+        assert_eq!((layout.width, layout.height), (88.0, 168.0));
+        let a = &layout.nodes[0];
+        assert_eq!(
+            (a.center, a.width, a.height),
+            (Point::new(44.0, 58.0), 54.0, 36.0)
+        );
+        assert_eq!(layout.nodes[1].width, 72.0);
+        let edge = &layout.edges[0];
+        assert_eq!(
+            edge.points,
+            [
+                Point::new(44.0, 76.0),
+                Point::new(44.0, 118.0),
+                Point::new(44.0, 128.0)
+            ]
+        );
+        assert_eq!(edge.label, Some(Point::new(47.0, 102.0)));
+        let cluster = &layout.clusters[0];
+        assert_eq!(
+            (cluster.min, cluster.max),
+            (Point::new(12.0, 12.0), Point::new(76.0, 84.0))
+        );
+        assert_eq!(cluster.label, Some(Point::new(44.0, 24.0)));
+
+        let ops = &read.ops;
+        assert_eq!(ops.graph.len(), 2);
+        assert_eq!(
+            ops.clusters[0][2],
+            DrawOp::Text {
+                point: Point::new(44.0, 29.0),
+                align: Align::Center,
+                width: 8.0,
+                text: "X".to_string(),
+            }
+        );
+        assert_eq!(
+            ops.nodes[1],
+            [DrawOp::Ellipse {
+                filled: true,
+                center: Point::new(44.0, 146.0),
+                rx: 36.0,
+                ry: 18.0,
+            }]
+        );
+        assert_eq!(
+            ops.edges[0][2],
+            DrawOp::Color {
+                fill: true,
+                color: "red".to_string(),
+            }
+        );
+
+        let error = parse_json("{\"objects\": [],\n \"_subgraph_cnt\": 2}")
+            .err()
+            .unwrap();
+        assert_eq!(error.message(), "expected an index below 1");
+        assert_eq!((error.line(), error.column()), (2, 19));
+    }
+}
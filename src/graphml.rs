@@ -0,0 +1,936 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+use crate::ast::{Attribute, AttributeScope, EdgeTarget, Graph, GraphType, Id, NodeId, Statement};
+use crate::escape::{self, escape_html};
+use crate::model::Model;
+use crate::parser::{attribute, parse_graph, ParseError};
+use crate::xml::{self, Element};
+
+// docs
+// * http://graphml.graphdrawing.org/primer/graphml-primer.html
+// * http://graphml.graphdrawing.org/specification.html
+//
+// GraphML keeps attributes as typed data declared by `<key>` elements, one key
+// per attribute name and element kind. Graphs are written the way graphviz
+// resolves them, so node and edge defaults end up on every node and edge.
+// Subgraphs become nodes holding a nested graph with the subgraph's ID, and
+// each node is written once, inside the innermost subgraph it belongs to.
+// The other subgraphs a node is in list it in a "members" data, and edges
+// are written in the graph of the subgraph they were written in.
+// Values are kept in DOT form, e.g. a label's `\n` stays an escape sequence.
+// HTML-like values get keys of their own, named with an "_html" suffix so
+// that they do not clash with the plain ones and marked with a `<desc>` of
+// "html". A strict graph has a boolean "strict" data on the root graph.
+//
+// The edges of a chain such as `a -> b -> c` or `{a b} -> c` have a "chain"
+// data of the chain's number and the arrow the edge belongs to, e.g. `0:1`
+// for `b -> c`, so that they are read back as a chain.
+
+const HTML: &str = "html";
+const HTML_SUFFIX: &str = "_html";
+const STRICT: &str = "strict";
+const MEMBERS: &str = "members";
+const CHAIN: &str = "chain";
+
+// The GraphML type of an attribute's value
+pub(crate) fn value_type(attribute: &Attribute) -> &'static str {
+    match attribute {
+        Attribute::Pin(_) | Attribute::Regular(_) => "boolean",
+        Attribute::Peripheries(_) | Attribute::Sides(_) => "int",
+        Attribute::Area(_)
+        | Attribute::ArrowSize(_)
+        | Attribute::Distortion(_)
+        | Attribute::Dpi(_)
+        | Attribute::K(_)
+        | Attribute::Length(_)
+        | Attribute::MinDist(_)
+        | Attribute::Orientation(_)
+        | Attribute::RankSep(_)
+        | Attribute::RepulsiveForce(_)
+        | Attribute::Skew(_) => "double",
+        _ => "string",
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct KeyName {
+    domain: &'static str,
+    name: String,
+    html: bool,
+}
+
+struct Key {
+    id: String,
+    value_type: &'static str,
+}
+
+pub fn print_graphml<W: io::Write>(writer: &mut W, graph: &Graph) -> io::Result<()> {
+    let model = Model::new(graph);
+    let mut printer = GraphmlPrinter {
+        writer,
+        model: &model,
+        strict: graph.strict,
+        keys: HashMap::new(),
+        order: Vec::new(),
+        homes: Vec::new(),
+        subgraph_ids: HashSet::new(),
+        edge_default: if model.directed {
+            "directed"
+        } else {
+            "undirected"
+        },
+    };
+    printer.place_nodes();
+    printer.collect_keys();
+    printer.document()
+}
+
+struct GraphmlPrinter<'a, W: io::Write> {
+    writer: &'a mut W,
+    model: &'a Model,
+    strict: bool,
+    keys: HashMap<KeyName, Key>,
+    // Keys in the order they were first needed
+    order: Vec<KeyName>,
+    // The subgraph each node is written in, `None` for the root graph
+    homes: Vec<Option<usize>>,
+    // Node IDs given to subgraphs, which must not clash with real nodes
+    subgraph_ids: HashSet<String>,
+    edge_default: &'static str,
+}
+
+impl<'a, W: io::Write> GraphmlPrinter<'a, W> {
+    fn collect_keys(&mut self) {
+        let model = self.model;
+        if self.strict {
+            self.add_key(Self::key(STRICT, "graph"), "boolean");
+        }
+        let graph_attributes =
+            std::iter::once(&model.attributes).chain(model.subgraphs.iter().map(|s| &s.attributes));
+        for attributes in graph_attributes {
+            self.add_keys("graph", attributes);
+        }
+        for node in &model.nodes {
+            self.add_keys("node", &node.attributes);
+        }
+        if (0..model.subgraphs.len()).any(|s| !self.members(s).is_empty()) {
+            self.add_key(Self::key(MEMBERS, "graph"), "string");
+        }
+        for edge in &model.edges {
+            if edge.chain.is_some() {
+                self.add_key(Self::key(CHAIN, "edge"), "string");
+            }
+            self.add_keys("edge", &edge.attributes);
+        }
+    }
+
+    fn add_keys(&mut self, domain: &'static str, attributes: &[Attribute]) {
+        for attribute in attributes {
+            let name = KeyName {
+                domain,
+                name: attribute.name().to_string(),
+                html: attribute.is_html(),
+            };
+            self.add_key(name, value_type(attribute));
+        }
+    }
+
+    fn add_key(&mut self, name: KeyName, value_type: &'static str) {
+        match self.keys.get_mut(&name) {
+            // A value that did not parse, e.g. pin=maybe, is a string
+            Some(key) if key.value_type != value_type => key.value_type = "string",
+            Some(_) => {}
+            None => {
+                let key = Key {
+                    id: format!("d{}", self.order.len()),
+                    value_type,
+                };
+                self.keys.insert(name.clone(), key);
+                self.order.push(name);
+            }
+        }
+    }
+
+    // A key of the format rather than of an attribute
+    fn key(name: &str, domain: &'static str) -> KeyName {
+        KeyName {
+            domain,
+            name: name.to_string(),
+            html: false,
+        }
+    }
+
+    // Subgraphs written as graphs, edge endpoints are part of their chain
+    fn written(&self, subgraph: usize) -> bool {
+        !self.model.subgraphs[subgraph].endpoint
+    }
+
+    // The nodes of a subgraph that are written elsewhere, neither inside it
+    // nor inside a subgraph of it
+    fn members(&self, subgraph: usize) -> Vec<usize> {
+        let model = self.model;
+        let nested = |node: usize| {
+            (0..model.subgraphs.len()).any(|s| {
+                model.subgraphs[s].parent == Some(subgraph)
+                    && self.written(s)
+                    && model.subgraphs[s].nodes.contains(&node)
+            })
+        };
+        let within = |node: usize| {
+            let mut home = self.homes[node];
+            while let Some(h) = home {
+                if h == subgraph {
+                    return true;
+                }
+                home = model.subgraphs[h].parent;
+            }
+            false
+        };
+        model.subgraphs[subgraph]
+            .nodes
+            .iter()
+            .copied()
+            .filter(|&node| !within(node) && !nested(node))
+            .collect()
+    }
+
+    // The graph an edge is written in, outside of any edge endpoint
+    fn edge_graph(&self, edge: usize) -> Option<usize> {
+        let mut subgraph = self.model.edges[edge].subgraph;
+        while let Some(s) = subgraph.filter(|&s| !self.written(s)) {
+            subgraph = self.model.subgraphs[s].parent;
+        }
+        subgraph
+    }
+
+    // Every node goes to the most deeply nested subgraph it is in, the first
+    // one when it is in several
+    fn place_nodes(&mut self) {
+        let model = self.model;
+        let depth = |mut subgraph: usize| {
+            let mut depth = 0;
+            while let Some(parent) = model.subgraphs[subgraph].parent {
+                depth += 1;
+                subgraph = parent;
+            }
+            depth
+        };
+        let depths: Vec<usize> = (0..model.subgraphs.len()).map(depth).collect();
+        self.homes = (0..model.nodes.len())
+            .map(|node| {
+                (0..model.subgraphs.len())
+                    .filter(|&s| self.written(s) && model.subgraphs[s].nodes.contains(&node))
+                    .fold(None, |home: Option<usize>, s| match home {
+                        Some(h) if depths[h] >= depths[s] => Some(h),
+                        _ => Some(s),
+                    })
+            })
+            .collect();
+    }
+
+    fn document(&mut self) -> io::Result<()> {
+        writeln!(self.writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
+            self.writer,
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" \
+             xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+             xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns \
+             http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">"
+        )?;
+        for name in &self.order {
+            let key = &self.keys[name];
+            write!(
+                self.writer,
+                "  <key id=\"{}\" for=\"{}\" attr.name=\"{}{}\" attr.type=\"{}\"",
+                key.id,
+                name.domain,
+                escape_html(&name.name),
+                if name.html { HTML_SUFFIX } else { "" },
+                key.value_type
+            )?;
+            if name.html {
+                writeln!(self.writer, "><desc>{}</desc></key>", HTML)?;
+            } else {
+                writeln!(self.writer, "/>")?;
+            }
+        }
+
+        let model = self.model;
+        write!(self.writer, "  <graph")?;
+        if let Some(id) = &model.id {
+            write!(self.writer, " id=\"{}\"", escape_html(id.get()))?;
+        }
+        writeln!(self.writer, " edgedefault=\"{}\">", self.edge_default)?;
+        if self.strict {
+            self.format_data(2, Self::key(STRICT, "graph"), "true")?;
+        }
+        self.data(2, "graph", &model.attributes)?;
+        self.contents(None, 2)?;
+        self.edges(None, 2)?;
+        writeln!(self.writer, "  </graph>")?;
+        writeln!(self.writer, "</graphml>")
+    }
+
+    fn edges(&mut self, subgraph: Option<usize>, depth: usize) -> io::Result<()> {
+        let model = self.model;
+        let indent = "  ".repeat(depth);
+        for (e, edge) in model.edges.iter().enumerate() {
+            if self.edge_graph(e) != subgraph {
+                continue;
+            }
+            write!(
+                self.writer,
+                "{}<edge source=\"{}\" target=\"{}\"",
+                indent,
+                escape_html(edge.from.id.get()),
+                escape_html(edge.to.id.get())
+            )?;
+            if let Some(port) = edge.from.port_text() {
+                write!(self.writer, " sourceport=\"{}\"", escape_html(&port))?;
+            }
+            if let Some(port) = edge.to.port_text() {
+                write!(self.writer, " targetport=\"{}\"", escape_html(&port))?;
+            }
+            if edge.attributes.is_empty() && edge.chain.is_none() {
+                writeln!(self.writer, "/>")?;
+                continue;
+            }
+            writeln!(self.writer, ">")?;
+            if let Some((chain, link)) = edge.chain {
+                let value = format!("{}:{}", chain, link);
+                self.format_data(depth + 1, Self::key(CHAIN, "edge"), &value)?;
+            }
+            self.data(depth + 1, "edge", &edge.attributes)?;
+            writeln!(self.writer, "{}</edge>", indent)?;
+        }
+        Ok(())
+    }
+
+    // The nodes and subgraphs directly inside a graph, in the order their
+    // first nodes appear
+    fn contents(&mut self, subgraph: Option<usize>, depth: usize) -> io::Result<()> {
+        let model = self.model;
+        let mut items: Vec<(usize, Result<usize, usize>)> = Vec::new();
+        for node in 0..model.nodes.len() {
+            if self.homes[node] == subgraph {
+                items.push((node, Ok(node)));
+            }
+        }
+        for (s, inner) in model.subgraphs.iter().enumerate() {
+            if inner.parent == subgraph && self.written(s) {
+                let first = inner.nodes.iter().min().copied().unwrap_or(usize::MAX);
+                items.push((first, Err(s)));
+            }
+        }
+        items.sort_by_key(|(first, _)| *first);
+        for (_, item) in items {
+            match item {
+                Ok(node) => self.node(node, depth)?,
+                Err(inner) => self.subgraph(inner, depth)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn node(&mut self, node: usize, depth: usize) -> io::Result<()> {
+        let model = self.model;
+        let indent = "  ".repeat(depth);
+        let id = model.nodes[node].id.get();
+        let ports: Vec<String> = {
+            let mut ports = Vec::new();
+            for edge in &model.edges {
+                for end in [&edge.from, &edge.to] {
+                    if end.id.get() == id {
+                        if let Some(port) = &end.port {
+                            let port = port.get().to_string();
+                            if !ports.contains(&port) {
+                                ports.push(port);
+                            }
+                        }
+                    }
+                }
+            }
+            ports
+        };
+        let attributes = &model.nodes[node].attributes;
+        write!(self.writer, "{}<node id=\"{}\"", indent, escape_html(id))?;
+        if attributes.is_empty() && ports.is_empty() {
+            return writeln!(self.writer, "/>");
+        }
+        writeln!(self.writer, ">")?;
+        self.data(depth + 1, "node", attributes)?;
+        for port in ports {
+            writeln!(
+                self.writer,
+                "{}  <port name=\"{}\"/>",
+                indent,
+                escape_html(&port)
+            )?;
+        }
+        writeln!(self.writer, "{}</node>", indent)
+    }
+
+    fn subgraph(&mut self, subgraph: usize, depth: usize) -> io::Result<()> {
+        let model = self.model;
+        let indent = "  ".repeat(depth);
+        let mut node_id = format!("subgraph{}", self.subgraph_ids.len());
+        while model.nodes.iter().any(|n| n.id.get() == node_id) {
+            node_id.push('_');
+        }
+        self.subgraph_ids.insert(node_id.clone());
+        writeln!(
+            self.writer,
+            "{}<node id=\"{}\">",
+            indent,
+            escape_html(&node_id)
+        )?;
+        write!(self.writer, "{}  <graph", indent)?;
+        if let Some(id) = &model.subgraphs[subgraph].id {
+            write!(self.writer, " id=\"{}\"", escape_html(id.get()))?;
+        }
+        writeln!(self.writer, " edgedefault=\"{}\">", self.edge_default)?;
+        self.data(depth + 2, "graph", &model.subgraphs[subgraph].attributes)?;
+        let members: Vec<String> = self
+            .members(subgraph)
+            .into_iter()
+            .map(|node| escape::id(&model.nodes[node].id).into_owned())
+            .collect();
+        if !members.is_empty() {
+            self.format_data(depth + 2, Self::key(MEMBERS, "graph"), &members.join(" "))?;
+        }
+        self.contents(Some(subgraph), depth + 2)?;
+        self.edges(Some(subgraph), depth + 2)?;
+        writeln!(self.writer, "{}  </graph>", indent)?;
+        writeln!(self.writer, "{}</node>", indent)
+    }
+
+    fn format_data(&mut self, depth: usize, key: KeyName, value: &str) -> io::Result<()> {
+        writeln!(
+            self.writer,
+            "{}<data key=\"{}\">{}</data>",
+            "  ".repeat(depth),
+            self.keys[&key].id,
+            escape_html(value)
+        )
+    }
+
+    fn data(
+        &mut self,
+        depth: usize,
+        domain: &'static str,
+        attributes: &[Attribute],
+    ) -> io::Result<()> {
+        for attribute in attributes {
+            let name = KeyName {
+                domain,
+                name: attribute.name().to_string(),
+                html: attribute.is_html(),
+            };
+            writeln!(
+                self.writer,
+                "{}<data key=\"{}\">{}</data>",
+                "  ".repeat(depth),
+                self.keys[&name].id,
+                escape_html(&attribute.value())
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// A key as declared in a document
+struct DeclaredKey {
+    domain: String,
+    name: Option<String>,
+    value_type: String,
+    html: bool,
+    default: Option<String>,
+}
+
+// Reads the first graph of a GraphML document. Data whose key has no
+// attr.name, e.g. the graphics of yEd, is left out.
+pub fn parse_graphml(input: &str) -> Result<Graph, ParseError> {
+    let root = xml::parse(input)?;
+    if root.name != "graphml" {
+        return Err(root.error("expected a graphml element"));
+    }
+    let mut keys = HashMap::new();
+    for key in root.children("key") {
+        let id = key
+            .attribute("id")
+            .ok_or_else(|| key.error("key without an id"))?;
+        let html = key
+            .child("desc")
+            .is_some_and(|desc| desc.text.trim() == HTML);
+        let name = key.attribute("attr.name").map(|name| match html {
+            true => name.strip_suffix(HTML_SUFFIX).unwrap_or(name).to_string(),
+            false => name.to_string(),
+        });
+        let declared = DeclaredKey {
+            domain: key.attribute("for").unwrap_or("all").to_string(),
+            name,
+            value_type: key.attribute("attr.type").unwrap_or("string").to_string(),
+            html,
+            default: key.child("default").map(|default| default.text.clone()),
+        };
+        keys.insert(id.to_string(), declared);
+    }
+    let graph = root
+        .child("graph")
+        .ok_or_else(|| root.error("expected a graph element"))?;
+    let gtype = match graph.attribute("edgedefault") {
+        Some("undirected") => GraphType::Undirected,
+        _ => GraphType::Directed,
+    };
+    let reader = GraphmlReader { keys };
+    let strict = graph.children("data").any(|data| {
+        match data.attribute("key").and_then(|id| reader.keys.get(id)) {
+            Some(key) if GraphmlReader::is_strict(key) => {
+                matches!(data.text.trim().to_lowercase().as_str(), "1" | "true")
+            }
+            _ => false,
+        }
+    });
+    let mut statements = Vec::new();
+    let defaults = reader.defaults("graph");
+    if !defaults.is_empty() {
+        statements.push(Statement::Attribute(AttributeScope::Graph, defaults));
+    }
+    statements.extend(reader.statements(graph)?);
+    Ok(Graph {
+        strict,
+        gtype,
+        id: graph.attribute("id").map(Id::from_dot),
+        statements,
+        engine: None,
+        format: None,
+    })
+}
+
+struct GraphmlReader {
+    keys: HashMap<String, DeclaredKey>,
+}
+
+impl GraphmlReader {
+    fn statements(&self, graph: &Element) -> Result<Vec<Statement>, ParseError> {
+        let mut statements = Vec::new();
+        let attributes = self.attributes(graph, "graph")?;
+        if !attributes.is_empty() {
+            statements.push(Statement::Attribute(AttributeScope::Graph, attributes));
+        }
+        for data in graph.children("data") {
+            if !self.is_format_key(data, MEMBERS) {
+                continue;
+            }
+            let members = parse_graph(&format!("graph {{ {} }}", data.text))
+                .map_err(|_| data.error("expected node IDs"))?;
+            statements.extend(members.statements);
+        }
+        let mut chain: Option<Chain> = None;
+        for element in &graph.children {
+            let link = match element.name.as_str() {
+                "edge" => self.link(element)?,
+                _ => None,
+            };
+            let continues = match (&chain, &link) {
+                (Some(chain), Some((number, _))) => &chain.number == number,
+                _ => false,
+            };
+            if !continues {
+                if let Some(chain) = chain.take() {
+                    statements.push(chain.statement());
+                }
+            }
+            match element.name.as_str() {
+                "node" => match element.child("graph") {
+                    Some(inner) => statements.push(Statement::Subgraph {
+                        id: inner.attribute("id").map(Id::from_dot),
+                        statements: self.statements(inner)?,
+                    }),
+                    None => {
+                        let id = element
+                            .attribute("id")
+                            .ok_or_else(|| element.error("node without an id"))?;
+                        let attributes = self.attributes(element, "node")?;
+                        statements.push(Statement::Node {
+                            id: Id::from_dot(id),
+                            attributes: self.with_defaults("node", attributes),
+                        });
+                    }
+                },
+                "edge" => {
+                    let end = |name: &str, port: &str| {
+                        let id = element
+                            .attribute(name)
+                            .ok_or_else(|| element.error(&format!("edge without a {}", name)))?;
+                        let node = NodeId::from(Id::from_dot(id));
+                        Ok(match element.attribute(port) {
+                            Some(port) => node.with_port_text(port),
+                            None => node,
+                        })
+                    };
+                    let attributes = self.attributes(element, "edge")?;
+                    let (from, to) = (end("source", "sourceport")?, end("target", "targetport")?);
+                    let attributes = self.with_defaults("edge", attributes);
+                    match link {
+                        Some((number, link)) => {
+                            let chain = chain.get_or_insert_with(|| Chain {
+                                number,
+                                edges: Vec::new(),
+                                attributes,
+                            });
+                            chain.edges.push((link, from, to));
+                        }
+                        None => statements.push(Statement::Edge {
+                            from,
+                            to,
+                            attributes,
+                        }),
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(chain) = chain {
+            statements.push(chain.statement());
+        }
+        Ok(statements)
+    }
+
+    // The chain an edge belongs to and its arrow in it
+    fn link(&self, edge: &Element) -> Result<Option<(String, usize)>, ParseError> {
+        let data = match edge.children("data").find(|d| self.is_format_key(d, CHAIN)) {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let link = data
+            .text
+            .trim()
+            .split_once(':')
+            .and_then(|(number, link)| Some((number.to_string(), link.parse().ok()?)));
+        link.map(Some).ok_or_else(|| data.error("invalid chain"))
+    }
+
+    fn is_format_key(&self, data: &Element, name: &str) -> bool {
+        let key = data.attribute("key").and_then(|id| self.keys.get(id));
+        key.is_some_and(|key| key.name.as_deref() == Some(name))
+    }
+
+    fn applies(key: &DeclaredKey, domain: &str) -> bool {
+        key.domain == domain || key.domain == "all"
+    }
+
+    // The keys of the format rather than of attributes
+    fn is_strict(key: &DeclaredKey) -> bool {
+        key.domain == "graph" && key.name.as_deref() == Some(STRICT)
+    }
+
+    fn is_format(key: &DeclaredKey) -> bool {
+        let name = key.name.as_deref();
+        Self::is_strict(key)
+            || (key.domain == "graph" && name == Some(MEMBERS))
+            || (key.domain == "edge" && name == Some(CHAIN))
+    }
+
+    // Values of keys with a default
+    fn defaults(&self, domain: &str) -> Vec<Attribute> {
+        let mut defaults: Vec<(&String, Attribute)> = self
+            .keys
+            .iter()
+            .filter(|(_, key)| Self::applies(key, domain) && !Self::is_format(key))
+            .filter_map(|(id, key)| Some((id, self.attribute(key, key.default.as_ref()?)?)))
+            .collect();
+        defaults.sort_by(|a, b| a.0.cmp(b.0));
+        defaults.into_iter().map(|(_, a)| a).collect()
+    }
+
+    // An element's data, after the defaults of the keys it has no data for
+    fn with_defaults(&self, domain: &str, attributes: Vec<Attribute>) -> Vec<Attribute> {
+        let mut defaults: Vec<Attribute> = self
+            .defaults(domain)
+            .into_iter()
+            .filter(|d| !attributes.iter().any(|a| a.name() == d.name()))
+            .collect();
+        defaults.extend(attributes);
+        defaults
+    }
+
+    fn attributes(&self, element: &Element, domain: &str) -> Result<Vec<Attribute>, ParseError> {
+        let mut attributes = Vec::new();
+        for data in element.children("data") {
+            let id = data
+                .attribute("key")
+                .ok_or_else(|| data.error("data without a key"))?;
+            let key = self
+                .keys
+                .get(id)
+                .ok_or_else(|| data.error(&format!("undeclared key '{}'", id)))?;
+            if !Self::applies(key, domain) {
+                return Err(data.error(&format!("key '{}' is not for {}s", id, domain)));
+            }
+            if Self::is_format(key) {
+                continue;
+            }
+            if let Some(attribute) = self.attribute(key, &data.text) {
+                attributes.push(attribute);
+            }
+        }
+        Ok(attributes)
+    }
+
+    fn attribute(&self, key: &DeclaredKey, value: &str) -> Option<Attribute> {
+        let name = key.name.as_ref()?;
+        let value = match key.value_type.as_str() {
+            "boolean" => match value.trim().to_lowercase().as_str() {
+                "1" | "true" => "true".to_string(),
+                "0" | "false" => "false".to_string(),
+                other => other.to_string(),
+            },
+            "int" | "long" | "float" | "double" => value.trim().to_string(),
+            _ => value.to_string(),
+        };
+        let value = if key.html {
+            Id::html(value)
+        } else {
+            Id::from_dot(value)
+        };
+        Some(attribute(Id::from_dot(name), value))
+    }
+}
+
+// A chain being read
+struct Chain {
+    number: String,
+    // Every edge with the arrow it belongs to
+    edges: Vec<(usize, NodeId, NodeId)>,
+    attributes: Vec<Attribute>,
+}
+
+impl Chain {
+    // The tails of the first arrow, then the heads of every arrow, with
+    // several nodes making a subgraph like `{a b}`
+    fn statement(self) -> Statement {
+        let links = self
+            .edges
+            .iter()
+            .map(|(link, ..)| link + 1)
+            .max()
+            .unwrap_or(0);
+        let mut ends: Vec<Vec<NodeId>> = vec![Vec::new(); links + 1];
+        for (link, from, to) in self.edges {
+            for (end, node) in [(link, from), (link + 1, to)] {
+                let same = |n: &NodeId| n.id == node.id && n.port_text() == node.port_text();
+                if !ends[end].iter().any(same) {
+                    ends[end].push(node);
+                }
+            }
+        }
+        let targets = ends
+            .into_iter()
+            .filter(|nodes| !nodes.is_empty())
+            .map(|mut nodes| match nodes.len() {
+                1 => EdgeTarget::Node(nodes.remove(0)),
+                _ => EdgeTarget::Subgraph {
+                    id: None,
+                    statements: nodes
+                        .into_iter()
+                        .map(|node| Statement::Node {
+                            id: node.id,
+                            attributes: Vec::new(),
+                        })
+                        .collect(),
+                },
+            })
+            .collect();
+        Statement::EdgeChain {
+            targets,
+            attributes: self.attributes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_graph;
+    use crate::printer::print_graph;
+
+    fn graphml(graph: &Graph) -> String {
+        let mut writer = Vec::new();
+        print_graphml(&mut writer, graph).unwrap();
+        String::from_utf8(writer).unwrap()
+    }
+
+    fn dot(graph: &Graph) -> String {
+        let mut writer = Vec::new();
+        print_graph(&mut writer, graph).unwrap();
+        String::from_utf8(writer).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let graph = parse_graph(
+            r#"digraph G {
+                rankdir=LR;
+                a [label=<<b>A</b>>, pin=true, peripheries=2];
+                subgraph cluster_x {
+                    label="X\nY"; node [shape=box];
+                    b [width=1.5];
+                    subgraph { rank=same; c; d }
+                }
+                a:p:n -> b [arrowsize=0.5, weight=2];
+                c -> d [label="x & y"];
+            }"#,
+        )
+        .unwrap();
+        let written = graphml(&graph);
+        assert_eq!(
+            written,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:schemaLocation="http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd">
+  <key id="d0" for="graph" attr.name="rankdir" attr.type="string"/>
+  <key id="d1" for="graph" attr.name="label" attr.type="string"/>
+  <key id="d2" for="graph" attr.name="rank" attr.type="string"/>
+  <key id="d3" for="node" attr.name="label_html" attr.type="string"><desc>html</desc></key>
+  <key id="d4" for="node" attr.name="pin" attr.type="boolean"/>
+  <key id="d5" for="node" attr.name="peripheries" attr.type="int"/>
+  <key id="d6" for="node" attr.name="shape" attr.type="string"/>
+  <key id="d7" for="node" attr.name="width" attr.type="string"/>
+  <key id="d8" for="edge" attr.name="arrowsize" attr.type="double"/>
+  <key id="d9" for="edge" attr.name="weight" attr.type="string"/>
+  <key id="d10" for="edge" attr.name="label" attr.type="string"/>
+  <graph id="G" edgedefault="directed">
+    <data key="d0">LR</data>
+    <node id="a">
+      <data key="d3">&lt;b&gt;A&lt;/b&gt;</data>
+      <data key="d4">true</data>
+      <data key="d5">2</data>
+      <port name="p"/>
+    </node>
+    <node id="subgraph0">
+      <graph id="cluster_x" edgedefault="directed">
+        <data key="d1">X\nY</data>
+        <node id="b">
+          <data key="d6">box</data>
+          <data key="d7">1.5</data>
+        </node>
+        <node id="subgraph1">
+          <graph edgedefault="directed">
+            <data key="d2">same</data>
+            <node id="c">
+              <data key="d6">box</data>
+            </node>
+            <node id="d">
+              <data key="d6">box</data>
+            </node>
+          </graph>
+        </node>
+      </graph>
+    </node>
+    <edge source="a" target="b" sourceport="p:n">
+      <data key="d8">0.5</data>
+      <data key="d9">2</data>
+    </edge>
+    <edge source="c" target="d">
+      <data key="d10">x &amp; y</data>
+    </edge>
+  </graph>
+</graphml>
+"#
+        );
+
+        let read = parse_graphml(&written).unwrap();
+        assert_eq!(graphml(&read), written);
+        assert!(dot(&read).contains("a:p:n -> b [arrowsize=0.5, weight=2];"));
+        assert!(dot(&read).contains("a [label=<<b>A</b>>, pin=true, peripheries=2];"));
+    }
+
+    #[test]
+    fn statements() {
+        let source = r#"digraph G {
+  subgraph cluster_x {
+    graph [label="X"];
+    a;
+    b;
+    c;
+    a -> b:p -> c [color=red];
+  }
+  subgraph cluster_y {
+    c;
+    d;
+    c -> d;
+  }
+  e;
+  {a b} -> {d e};
+  e -> {a c} -> b;
+}
+"#;
+        let graph = parse_graph(source).unwrap();
+        let written = graphml(&graph);
+        assert!(written.contains(
+            "      <graph id=\"cluster_y\" edgedefault=\"directed\">\n        \
+             <data key=\"d1\">c</data>"
+        ));
+        assert!(written.contains(
+            "        <edge source=\"b\" target=\"c\" sourceport=\"p\">\n          \
+             <data key=\"d2\">0:1</data>"
+        ));
+        let read = parse_graphml(&written).unwrap();
+        assert_eq!(dot(&graph), source);
+        assert_eq!(dot(&read), source);
+        assert_eq!(graphml(&read), written);
+    }
+
+    #[test]
+    fn strict_graphs() {
+        let graph = parse_graph("strict graph { a [label=<A>]; b [label=B]; a -- b }").unwrap();
+        let written = graphml(&graph);
+        assert!(written
+            .contains("<key id=\"d0\" for=\"graph\" attr.name=\"strict\" attr.type=\"boolean\"/>"));
+        assert!(written
+            .contains("<graph edgedefault=\"undirected\">\n    <data key=\"d0\">true</data>"));
+        assert!(written.contains("attr.name=\"label_html\""));
+
+        let read = parse_graphml(&written).unwrap();
+        assert!(read.strict);
+        assert_eq!(graphml(&read), written);
+        assert_eq!(
+            dot(&read),
+            "strict graph {\n  a [label=<A>];\n  b [label=\"B\"];\n  a -- b;\n}\n"
+        );
+        assert!(
+            !parse_graphml(&graphml(&parse_graph("graph { a }").unwrap()))
+                .unwrap()
+                .strict
+        );
+    }
+
+    #[test]
+    fn foreign_documents() {
+        let graph = parse_graphml(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <graphml xmlns="http://graphml.graphdrawing.org/xmlns" xmlns:y="http://www.yworks.com/xml/graphml">
+              <key id="d0" for="node" attr.name="color" attr.type="string"><default>yellow</default></key>
+              <key id="d1" for="node" yfiles.type="nodegraphics"/>
+              <key id="d2" for="edge" attr.name="len" attr.type="double"/>
+              <graph id="G" edgedefault="undirected">
+                <node id="n0"><data key="d1"><y:ShapeNode/></data></node>
+                <node id="n1"><data key="d0">red</data></node>
+                <edge source="n0" target="n1"><data key="d2">2.0</data></edge>
+              </graph>
+            </graphml>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            dot(&graph),
+            "graph G {\n  n0 [color=yellow];\n  n1 [color=red];\n  n0 -- n1 [len=2];\n}\n"
+        );
+
+        let error = parse_graphml("<graphml>\n<graph><node/></graph></graphml>")
+            .err()
+            .unwrap();
+        assert_eq!(error.message(), "node without an id");
+        assert_eq!((error.line(), error.column()), (2, 8));
+    }
+}
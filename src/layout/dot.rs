@@ -0,0 +1,1198 @@
+use std::collections::HashMap;
+
+use crate::ast::{Attribute, Graph};
+use crate::attributes::{Rank, RankDir};
+use crate::layout::{
+    clip, font_size, is_elliptical, midpoint, node_size, self_loop, text_size, ClusterLayout,
+    EdgeLayout, Layout, NodeLayout, Point, LOOP_SIZE, POINTS_PER_INCH,
+};
+use crate::model::{find, number, Model};
+
+// docs
+// * https://graphviz.org/docs/layouts/dot/
+// * Gansner, Koutsofios, North and Vo, "A Technique for Drawing Directed
+//   Graphs", 1993
+//
+// A layered drawing in the stages dot uses:
+// 1. cycles are broken by reversing the edges a depth-first search finds
+//    pointing back up,
+// 2. nodes are put on ranks along longest paths, then pulled towards the
+//    side where most of their edges go,
+// 3. edges spanning several ranks get a virtual node per rank in between
+//    and the order within each rank is improved by barycenter sweeps,
+// 4. positions within a rank come from a set of separation constraints,
+//    moved in rounds towards the neighbours of every node,
+// 5. edges are routed through their virtual nodes.
+//
+// Everything is computed top to bottom and turned at the end for `rankdir`.
+// Clusters keep their nodes next to each other on every rank and are kept in
+// the same order on every rank, so that their boxes never overlap.
+
+const CLUSTER_MARGIN: f64 = 8.0;
+const ORDERING_SWEEPS: usize = 12;
+const PLACEMENT_ROUNDS: usize = 8;
+
+pub fn dot(graph: &Graph) -> Layout {
+    layered(&Model::new(graph))
+}
+
+pub(crate) fn layered(model: &Model) -> Layout {
+    Layered::new(model).layout()
+}
+
+struct LayerNode {
+    // The model node, `None` for the bends of long edges and cluster fillers
+    node: Option<usize>,
+    rank: usize,
+    // Along and across the rank, i.e. width and height for rankdir=TB
+    width: f64,
+    height: f64,
+    // The clusters the node is drawn in, outermost first
+    clusters: Vec<usize>,
+    // Self loops drawn to the node's right
+    loops: usize,
+}
+
+impl LayerNode {
+    fn is_virtual(&self) -> bool {
+        self.node.is_none()
+    }
+}
+
+enum Route {
+    Loop {
+        node: usize,
+        // The loop's place among the node's loops
+        nth: usize,
+    },
+    // Between two nodes on the same rank
+    Flat {
+        tail: usize,
+        head: usize,
+    },
+    // Layer nodes from the upper end to the lower one
+    Chain {
+        nodes: Vec<usize>,
+        reversed: bool,
+        // Parallel edges between neighbouring ranks bend apart, the first
+        // one is straight
+        parallel: usize,
+    },
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+    TopBottom,
+    BottomTop,
+    LeftRight,
+    RightLeft,
+}
+
+impl Direction {
+    fn is_horizontal(&self) -> bool {
+        matches!(self, Direction::LeftRight | Direction::RightLeft)
+    }
+
+    // From the top to bottom drawing to the final one
+    fn turn(&self, p: Point) -> Point {
+        match self {
+            Direction::TopBottom => p,
+            Direction::BottomTop => Point::new(p.x, -p.y),
+            Direction::LeftRight => Point::new(p.y, p.x),
+            Direction::RightLeft => Point::new(-p.y, p.x),
+        }
+    }
+
+    // Sizes measured along and across the rank
+    fn along(&self, size: (f64, f64)) -> (f64, f64) {
+        if self.is_horizontal() {
+            (size.1, size.0)
+        } else {
+            size
+        }
+    }
+}
+
+struct Layered<'a> {
+    model: &'a Model,
+    direction: Direction,
+    nodesep: f64,
+    ranksep: f64,
+    nodes: Vec<LayerNode>,
+    // One per model edge
+    routes: Vec<Route>,
+    // Label sizes of the edges, along and across the rank
+    edge_labels: Vec<Option<(f64, f64)>>,
+    // The layer node an edge label is drawn next to
+    label_nodes: HashMap<usize, usize>,
+    layers: Vec<Vec<usize>>,
+    position: Vec<usize>,
+    up: Vec<Vec<usize>>,
+    down: Vec<Vec<usize>>,
+    // Clusters with nodes in them, as model subgraph indices
+    clusters: Vec<usize>,
+    cluster_labels: HashMap<usize, (f64, f64)>,
+}
+
+impl<'a> Layered<'a> {
+    fn new(model: &'a Model) -> Layered<'a> {
+        let direction = match find(&model.attributes, "rankdir") {
+            Some(Attribute::RankDir(RankDir::BottomTop)) => Direction::BottomTop,
+            Some(Attribute::RankDir(RankDir::LeftRight)) => Direction::LeftRight,
+            Some(Attribute::RankDir(RankDir::RightLeft)) => Direction::RightLeft,
+            _ => Direction::TopBottom,
+        };
+        let nodesep = number(&model.attributes, "nodesep")
+            .unwrap_or(0.25)
+            .max(0.02);
+        let ranksep = number(&model.attributes, "ranksep")
+            .unwrap_or(0.5)
+            .max(0.02);
+        let edge_labels = (0..model.edges.len())
+            .map(|edge| {
+                let label = model.edge_label(edge)?;
+                let size = text_size(&label, font_size(&model.edges[edge].attributes));
+                Some(direction.along(size))
+            })
+            .collect();
+        let cluster_labels = model
+            .clusters()
+            .filter_map(|cluster| {
+                let label = model.subgraph_label(cluster)?;
+                let size = text_size(&label, font_size(&model.subgraphs[cluster].attributes));
+                Some((cluster, size))
+            })
+            .collect();
+        Layered {
+            model,
+            direction,
+            nodesep: nodesep * POINTS_PER_INCH,
+            ranksep: ranksep * POINTS_PER_INCH,
+            nodes: Vec::new(),
+            routes: Vec::new(),
+            edge_labels,
+            label_nodes: HashMap::new(),
+            layers: Vec::new(),
+            position: Vec::new(),
+            up: Vec::new(),
+            down: Vec::new(),
+            clusters: Vec::new(),
+            cluster_labels,
+        }
+    }
+
+    fn layout(mut self) -> Layout {
+        let ranks = self.rank();
+        self.build_layers(ranks);
+        self.order();
+        let x = self.place();
+        let y = self.rank_positions();
+        self.finish(&x, &y)
+    }
+
+    // Stages 1 and 2: a rank for every model node
+    fn rank(&self) -> Vec<usize> {
+        let model = self.model;
+        let n = model.nodes.len();
+
+        // Nodes that share a rank are ranked as one
+        let mut group: Vec<usize> = (0..n).collect();
+        fn root(group: &mut [usize], node: usize) -> usize {
+            let mut node = node;
+            while group[node] != node {
+                group[node] = group[group[node]];
+                node = group[node];
+            }
+            node
+        }
+        let mut extreme: HashMap<usize, bool> = HashMap::new();
+        for subgraph in &model.subgraphs {
+            let rank = match subgraph.rank() {
+                Some(rank) => rank,
+                None => continue,
+            };
+            let first = match subgraph.nodes.first() {
+                Some(&first) => first,
+                None => continue,
+            };
+            for &node in &subgraph.nodes[1..] {
+                let (a, b) = (root(&mut group, first), root(&mut group, node));
+                group[b] = a;
+            }
+            match rank {
+                Rank::Min | Rank::Source => {
+                    extreme.insert(first, false);
+                }
+                Rank::Max | Rank::Sink => {
+                    extreme.insert(first, true);
+                }
+                Rank::Same => {}
+            }
+        }
+        let groups: Vec<usize> = (0..n).map(|node| root(&mut group, node)).collect();
+        // `true` for the bottom rank, `false` for the top one
+        let extreme: HashMap<usize, bool> = extreme
+            .into_iter()
+            .map(|(node, bottom)| (groups[node], bottom))
+            .collect();
+
+        let mut edges: Vec<(usize, usize)> = Vec::new();
+        for edge in &model.edges {
+            let (mut tail, mut head) = (groups[edge.tail], groups[edge.head]);
+            if tail == head {
+                continue;
+            }
+            if extreme.get(&head) == Some(&false) || extreme.get(&tail) == Some(&true) {
+                std::mem::swap(&mut tail, &mut head);
+            }
+            edges.push((tail, head));
+        }
+
+        // Reverse the edges a depth-first search sees going back up
+        let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for (i, &(tail, _)) in edges.iter().enumerate() {
+            outgoing[tail].push(i);
+        }
+        let mut state = vec![0u8; n];
+        for start in 0..n {
+            if groups[start] != start || state[start] != 0 {
+                continue;
+            }
+            state[start] = 1;
+            let mut stack = vec![(start, 0)];
+            while let Some(&(node, next)) = stack.last() {
+                match outgoing[node].get(next) {
+                    Some(&edge) => {
+                        stack.last_mut().unwrap().1 += 1;
+                        let head = edges[edge].1;
+                        match state[head] {
+                            0 => {
+                                state[head] = 1;
+                                stack.push((head, 0));
+                            }
+                            1 => edges[edge] = (head, node),
+                            _ => {}
+                        }
+                    }
+                    None => {
+                        state[node] = 2;
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        // Longest paths from the sources
+        let mut successors: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for &(tail, head) in &edges {
+            successors[tail].push(head);
+            predecessors[head].push(tail);
+        }
+        let mut incoming: Vec<usize> = predecessors.iter().map(Vec::len).collect();
+        let mut order: Vec<usize> = (0..n)
+            .filter(|&g| groups[g] == g && incoming[g] == 0)
+            .collect();
+        let mut i = 0;
+        while i < order.len() {
+            let node = order[i];
+            for &next in &successors[node] {
+                incoming[next] -= 1;
+                if incoming[next] == 0 {
+                    order.push(next);
+                }
+            }
+            i += 1;
+        }
+        let mut rank = vec![0usize; n];
+        for &node in &order {
+            for &next in &successors[node] {
+                rank[next] = rank[next].max(rank[node] + 1);
+            }
+        }
+
+        // Pull nodes with more edges below than above down towards them
+        for &node in order.iter().rev() {
+            if extreme.contains_key(&node) || successors[node].is_empty() {
+                continue;
+            }
+            if successors[node].len() > predecessors[node].len() {
+                let lowest = successors[node].iter().map(|&s| rank[s]).min().unwrap();
+                rank[node] = lowest - 1;
+            }
+        }
+        let bottom = order.iter().map(|&g| rank[g]).max().unwrap_or(0);
+        for (&node, &at_bottom) in &extreme {
+            rank[node] = if at_bottom { bottom } else { 0 };
+        }
+
+        let top = order.iter().map(|&g| rank[g]).min().unwrap_or(0);
+        // Labels get a rank of their own between the ends of an edge
+        let scale = if self.edge_labels.iter().any(Option::is_some) {
+            2
+        } else {
+            1
+        };
+        groups.iter().map(|&g| (rank[g] - top) * scale).collect()
+    }
+
+    // Stage 3a: layer nodes for the model nodes, the bends of long edges and
+    // the ranks clusters span without a node of their own
+    fn build_layers(&mut self, ranks: Vec<usize>) {
+        let model = self.model;
+        let direction = self.direction;
+        for (node, &rank) in ranks.iter().enumerate() {
+            let (width, height) = direction.along(node_size(model, node));
+            self.nodes.push(LayerNode {
+                node: Some(node),
+                rank,
+                width,
+                height,
+                clusters: cluster_path(model, model.cluster_of(node)),
+                loops: 0,
+            });
+        }
+
+        let mut parallel: HashMap<(usize, usize), usize> = HashMap::new();
+        for (i, edge) in model.edges.iter().enumerate() {
+            let (tail, head) = (edge.tail, edge.head);
+            if tail == head {
+                let nth = self.nodes[tail].loops;
+                self.nodes[tail].loops += 1;
+                self.routes.push(Route::Loop { node: tail, nth });
+                continue;
+            }
+            if ranks[tail] == ranks[head] {
+                self.routes.push(Route::Flat { tail, head });
+                continue;
+            }
+            let (top, bottom, reversed) = if ranks[tail] < ranks[head] {
+                (tail, head, false)
+            } else {
+                (head, tail, true)
+            };
+            let clusters = common_prefix(&self.nodes[top].clusters, &self.nodes[bottom].clusters);
+            let mut chain = vec![top];
+            for rank in ranks[top] + 1..ranks[bottom] {
+                chain.push(self.nodes.len());
+                self.nodes.push(LayerNode {
+                    node: None,
+                    rank,
+                    width: 0.0,
+                    height: 0.0,
+                    clusters: clusters.clone(),
+                    loops: 0,
+                });
+            }
+            chain.push(bottom);
+            if let Some((width, height)) = self.edge_labels[i] {
+                // The label sits to the right of the edge's middle bend
+                let middle = chain[chain.len() / 2];
+                self.nodes[middle].width = 2.0 * width + 8.0;
+                self.nodes[middle].height = height;
+                self.label_nodes.insert(i, middle);
+            }
+            let count = parallel.entry((top, bottom)).or_insert(0);
+            self.routes.push(Route::Chain {
+                nodes: chain,
+                reversed,
+                parallel: *count,
+            });
+            *count += 1;
+        }
+
+        // Clusters fill every rank between their top and bottom nodes
+        let mut spans: HashMap<usize, (usize, usize)> = HashMap::new();
+        let mut members: HashMap<(usize, usize), bool> = HashMap::new();
+        for node in &self.nodes {
+            for &cluster in &node.clusters {
+                let span = spans.entry(cluster).or_insert((node.rank, node.rank));
+                span.0 = span.0.min(node.rank);
+                span.1 = span.1.max(node.rank);
+                members.insert((cluster, node.rank), true);
+            }
+        }
+        let mut clusters: Vec<usize> = spans.keys().copied().collect();
+        clusters.sort_unstable();
+        for &cluster in &clusters {
+            let (top, bottom) = spans[&cluster];
+            for rank in top..=bottom {
+                if !members.contains_key(&(cluster, rank)) {
+                    let path = cluster_path(model, Some(cluster));
+                    for &c in &path {
+                        members.insert((c, rank), true);
+                    }
+                    self.nodes.push(LayerNode {
+                        node: None,
+                        rank,
+                        width: 0.0,
+                        height: 0.0,
+                        clusters: path,
+                        loops: 0,
+                    });
+                }
+            }
+        }
+        self.clusters = clusters;
+
+        let layer_count = self.nodes.iter().map(|n| n.rank + 1).max().unwrap_or(0);
+        self.layers = vec![Vec::new(); layer_count];
+        for (i, node) in self.nodes.iter().enumerate() {
+            self.layers[node.rank].push(i);
+        }
+        self.up = vec![Vec::new(); self.nodes.len()];
+        self.down = vec![Vec::new(); self.nodes.len()];
+        for route in &self.routes {
+            if let Route::Chain { nodes, .. } = route {
+                for pair in nodes.windows(2) {
+                    self.down[pair[0]].push(pair[1]);
+                    self.up[pair[1]].push(pair[0]);
+                }
+            }
+        }
+        self.position = vec![0; self.nodes.len()];
+        self.update_positions();
+    }
+
+    fn update_positions(&mut self) {
+        for layer in &self.layers {
+            for (i, &node) in layer.iter().enumerate() {
+                self.position[node] = i;
+            }
+        }
+    }
+
+    // Stage 3b: the order within every rank
+    fn order(&mut self) {
+        // A first order from the top down, starting in the order the nodes
+        // were created in
+        let order = self.cluster_order();
+        let keys: Vec<f64> = (0..self.nodes.len()).map(|n| n as f64).collect();
+        if let Some(first) = self.layers.first_mut() {
+            arrange(first, &keys, &self.nodes, &order);
+        }
+        self.update_positions();
+        for rank in 1..self.layers.len() {
+            let keys = self.barycenters(rank, true);
+            let order = self.cluster_order();
+            arrange(&mut self.layers[rank], &keys, &self.nodes, &order);
+            self.update_positions();
+        }
+
+        let mut best = self.layers.clone();
+        let mut fewest = self.crossings();
+        for sweep in 0..ORDERING_SWEEPS {
+            if fewest == 0 {
+                break;
+            }
+            let order = self.cluster_order();
+            let downwards = sweep % 2 == 0;
+            let ranks: Vec<usize> = if downwards {
+                (1..self.layers.len()).collect()
+            } else {
+                (0..self.layers.len().saturating_sub(1)).rev().collect()
+            };
+            for rank in ranks {
+                let keys = self.barycenters(rank, downwards);
+                arrange(&mut self.layers[rank], &keys, &self.nodes, &order);
+                self.update_positions();
+            }
+            let crossings = self.crossings();
+            if crossings < fewest {
+                fewest = crossings;
+                best = self.layers.clone();
+            }
+        }
+        self.layers = best;
+        self.update_positions();
+    }
+
+    // The mean position of a node's neighbours on the rank above or below,
+    // or its own position without any
+    fn barycenters(&self, rank: usize, from_above: bool) -> Vec<f64> {
+        let mut keys = vec![0.0; self.nodes.len()];
+        for &node in &self.layers[rank] {
+            let neighbours = if from_above {
+                &self.up[node]
+            } else {
+                &self.down[node]
+            };
+            keys[node] = if neighbours.is_empty() {
+                self.position[node] as f64
+            } else {
+                let sum: usize = neighbours.iter().map(|&n| self.position[n]).sum();
+                sum as f64 / neighbours.len() as f64
+            };
+        }
+        keys
+    }
+
+    // Where each cluster sits on average, so that sibling clusters can be
+    // put in the same order on every rank
+    fn cluster_order(&self) -> HashMap<usize, f64> {
+        let mut sums: HashMap<usize, (f64, usize)> = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            for &cluster in &node.clusters {
+                let sum = sums.entry(cluster).or_insert((0.0, 0));
+                sum.0 += self.position[i] as f64;
+                sum.1 += 1;
+            }
+        }
+        sums.into_iter()
+            .map(|(cluster, (sum, count))| (cluster, sum / count as f64))
+            .collect()
+    }
+
+    fn crossings(&self) -> usize {
+        let mut crossings = 0;
+        for layer in &self.layers {
+            let mut segments: Vec<(usize, usize)> = Vec::new();
+            for &node in layer {
+                for &below in &self.down[node] {
+                    segments.push((self.position[node], self.position[below]));
+                }
+            }
+            for (i, a) in segments.iter().enumerate() {
+                for b in &segments[i + 1..] {
+                    if (a.0 < b.0 && a.1 > b.1) || (a.0 > b.0 && a.1 < b.1) {
+                        crossings += 1;
+                    }
+                }
+            }
+        }
+        crossings
+    }
+
+    // Variables for the sides of cluster `k`, after the layer nodes
+    fn left(&self, k: usize) -> usize {
+        self.nodes.len() + 2 * k
+    }
+
+    fn right(&self, k: usize) -> usize {
+        self.nodes.len() + 2 * k + 1
+    }
+
+    // Room reserved for a cluster's label at its top, which is the left
+    // side while laying out left to right
+    fn label_room(&self, cluster: usize) -> (f64, f64) {
+        match self.cluster_labels.get(&cluster) {
+            Some(&(_, height)) if self.direction.is_horizontal() => (height, 0.0),
+            Some(&(_, height)) => (0.0, height),
+            None => (0.0, 0.0),
+        }
+    }
+
+    // Stage 4: the position of every layer node along its rank
+    fn place(&self) -> Vec<f64> {
+        let index: HashMap<usize, usize> = self
+            .clusters
+            .iter()
+            .enumerate()
+            .map(|(k, &c)| (c, k))
+            .collect();
+        let count = self.nodes.len() + 2 * self.clusters.len();
+        let mut constraints: Vec<(usize, usize, f64)> = Vec::new();
+
+        for layer in &self.layers {
+            for pair in layer.windows(2) {
+                let (a, b) = (&self.nodes[pair[0]], &self.nodes[pair[1]]);
+                let shared = common_prefix(&a.clusters, &b.clusters).len();
+                let mut gap = a.width / 2.0 + a.loops as f64 * LOOP_SIZE;
+                let mut from = pair[0];
+                if a.clusters.len() == shared && b.clusters.len() == shared {
+                    constraints.push((from, pair[1], gap + self.nodesep + b.width / 2.0));
+                    continue;
+                }
+                // Out through the right sides of a's clusters and in through
+                // the left sides of b's
+                for &cluster in a.clusters[shared..].iter().rev() {
+                    let right = self.right(index[&cluster]);
+                    constraints.push((from, right, gap + CLUSTER_MARGIN));
+                    from = right;
+                    gap = 0.0;
+                }
+                gap += self.nodesep;
+                for &cluster in &b.clusters[shared..] {
+                    let left = self.left(index[&cluster]);
+                    constraints.push((from, left, gap));
+                    from = left;
+                    gap = CLUSTER_MARGIN + self.label_room(cluster).0;
+                }
+                constraints.push((from, pair[1], gap + b.width / 2.0));
+            }
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            if let Some(&cluster) = node.clusters.last() {
+                let k = index[&cluster];
+                let room = CLUSTER_MARGIN + self.label_room(cluster).0;
+                constraints.push((self.left(k), i, room + node.width / 2.0));
+                let loops = node.loops as f64 * LOOP_SIZE;
+                constraints.push((i, self.right(k), node.width / 2.0 + loops + CLUSTER_MARGIN));
+            }
+        }
+        for (k, &cluster) in self.clusters.iter().enumerate() {
+            let label = self
+                .cluster_labels
+                .get(&cluster)
+                .filter(|_| !self.direction.is_horizontal())
+                .map_or(0.0, |&(width, _)| width + 2.0 * CLUSTER_MARGIN);
+            constraints.push((self.left(k), self.right(k), label));
+            if let Some(parent) = self.model.parent_cluster(cluster) {
+                let p = index[&parent];
+                let room = CLUSTER_MARGIN + self.label_room(parent).0;
+                constraints.push((self.left(p), self.left(k), room));
+                constraints.push((self.right(k), self.right(p), CLUSTER_MARGIN));
+            }
+        }
+
+        let solver = Constraints::new(count, constraints);
+        let mut x = solver.pack(&vec![None; count]);
+        for _ in 0..PLACEMENT_ROUNDS {
+            let desired: Vec<Option<f64>> = (0..count)
+                .map(|i| {
+                    if i >= self.nodes.len() {
+                        return None;
+                    }
+                    let mut sum = 0.0;
+                    let mut weights = 0.0;
+                    for &n in self.up[i].iter().chain(&self.down[i]) {
+                        let weight = match (self.nodes[i].is_virtual(), self.nodes[n].is_virtual())
+                        {
+                            (false, false) => 1.0,
+                            (true, true) => 8.0,
+                            _ => 2.0,
+                        };
+                        sum += weight * x[n];
+                        weights += weight;
+                    }
+                    Some(if weights > 0.0 { sum / weights } else { x[i] })
+                })
+                .collect();
+            x = solver.balance(&desired);
+        }
+        x
+    }
+
+    // Stage 4b: the position of every rank, leaving room for the tops and
+    // bottoms of clusters between ranks
+    fn rank_positions(&self) -> Vec<f64> {
+        let flipped = matches!(self.direction, Direction::BottomTop);
+        let mut starts: HashMap<usize, usize> = HashMap::new();
+        let mut ends: HashMap<usize, usize> = HashMap::new();
+        for node in &self.nodes {
+            for &cluster in &node.clusters {
+                let start = starts.entry(cluster).or_insert(node.rank);
+                *start = (*start).min(node.rank);
+                let end = ends.entry(cluster).or_insert(node.rank);
+                *end = (*end).max(node.rank);
+            }
+        }
+        let room = |cluster: usize, top: bool| {
+            let label = self.label_room(cluster).1;
+            CLUSTER_MARGIN + if top != flipped { label } else { 0.0 }
+        };
+        let mut above = vec![0.0f64; self.layers.len()];
+        let mut below = vec![0.0f64; self.layers.len()];
+        for node in &self.nodes {
+            let top: f64 = node
+                .clusters
+                .iter()
+                .filter(|c| starts[c] == node.rank)
+                .map(|&c| room(c, true))
+                .sum();
+            let bottom: f64 = node
+                .clusters
+                .iter()
+                .filter(|c| ends[c] == node.rank)
+                .map(|&c| room(c, false))
+                .sum();
+            above[node.rank] = above[node.rank].max(top);
+            below[node.rank] = below[node.rank].max(bottom);
+        }
+
+        let heights = self.layer_heights();
+        let ranksep = if self.edge_labels.iter().any(Option::is_some) {
+            self.ranksep / 2.0
+        } else {
+            self.ranksep
+        };
+        let mut y = Vec::new();
+        let mut next = 0.0;
+        for rank in 0..self.layers.len() {
+            let center = next + above[rank] + heights[rank] / 2.0;
+            y.push(center);
+            next = center + heights[rank] / 2.0 + below[rank] + ranksep;
+        }
+        y
+    }
+
+    fn layer_heights(&self) -> Vec<f64> {
+        self.layers
+            .iter()
+            .map(|layer| {
+                layer
+                    .iter()
+                    .map(|&n| self.nodes[n].height)
+                    .fold(0.0, f64::max)
+            })
+            .collect()
+    }
+
+    // Stage 5 and the turn to the final direction
+    fn finish(&self, x: &[f64], y: &[f64]) -> Layout {
+        let model = self.model;
+        let direction = self.direction;
+        let center = |n: usize| Point::new(x[n], y[self.nodes[n].rank]);
+        let size = |n: usize| (self.nodes[n].width, self.nodes[n].height);
+        let elliptical = |n: usize| self.nodes[n].node.is_some_and(|m| is_elliptical(model, m));
+        let heights = self.layer_heights();
+
+        let mut edges = Vec::new();
+        for (i, route) in self.routes.iter().enumerate() {
+            let edge = &model.edges[i];
+            let mut label = None;
+            let points = match route {
+                Route::Loop { node, nth } => {
+                    let c = center(*node);
+                    let points = self_loop(c, size(*node), elliptical(*node), *nth);
+                    if let Some((w, _)) = self.edge_labels[i] {
+                        label = Some(Point::new(points[1].x + w / 2.0 + 2.0, c.y));
+                    }
+                    points
+                }
+                Route::Flat { tail, head } => {
+                    let (a, b) = (center(*tail), center(*head));
+                    let adjacent =
+                        (self.position[*tail] as isize - self.position[*head] as isize).abs() == 1;
+                    let points = if adjacent {
+                        vec![
+                            clip(a, size(*tail), elliptical(*tail), b),
+                            clip(b, size(*head), elliptical(*head), a),
+                        ]
+                    } else {
+                        // Arch over the nodes in between
+                        let rank = self.nodes[*tail].rank;
+                        let top = y[rank] - heights[rank] / 2.0 - self.ranksep / 4.0;
+                        let middle = Point::new((a.x + b.x) / 2.0, top);
+                        vec![
+                            clip(a, size(*tail), elliptical(*tail), middle),
+                            middle,
+                            clip(b, size(*head), elliptical(*head), middle),
+                        ]
+                    };
+                    if let Some((_, h)) = self.edge_labels[i] {
+                        label = midpoint(&points).map(|p| Point::new(p.x, p.y - h / 2.0 - 2.0));
+                    }
+                    points
+                }
+                Route::Chain {
+                    nodes,
+                    reversed,
+                    parallel,
+                } => {
+                    let mut points: Vec<Point> = nodes.iter().map(|&n| center(n)).collect();
+                    if *parallel > 0 && points.len() == 2 {
+                        // Alternating sides, further out for every pair
+                        let side = if parallel % 2 == 1 { 1.0 } else { -1.0 };
+                        let offset = side * parallel.div_ceil(2) as f64 * self.nodesep / 2.0;
+                        let (a, b) = (points[0], points[1]);
+                        points.insert(1, Point::new((a.x + b.x) / 2.0 + offset, (a.y + b.y) / 2.0));
+                    }
+                    let nodes: Vec<usize> = vec![nodes[0], nodes[nodes.len() - 1]];
+                    let last = points.len() - 1;
+                    let (first, end) = (nodes[0], nodes[1]);
+                    points[0] = clip(points[0], size(first), elliptical(first), points[1]);
+                    points[last] = clip(points[last], size(end), elliptical(end), points[last - 1]);
+                    if let Some(&n) = self.label_nodes.get(&i) {
+                        let (w, _) = self.edge_labels[i].unwrap();
+                        label = Some(Point::new(x[n] + 4.0 + w / 2.0, y[self.nodes[n].rank]));
+                    }
+                    if *reversed {
+                        points.reverse();
+                    }
+                    points
+                }
+            };
+            edges.push(EdgeLayout {
+                tail: edge.from.id.clone(),
+                head: edge.to.id.clone(),
+                points: points.into_iter().map(|p| direction.turn(p)).collect(),
+                label: label.map(|p| direction.turn(p)),
+            });
+        }
+
+        let nodes = (0..model.nodes.len())
+            .map(|n| {
+                let (width, height) = direction.along(size(n));
+                NodeLayout {
+                    id: model.nodes[n].id.clone(),
+                    center: direction.turn(center(n)),
+                    width,
+                    height,
+                }
+            })
+            .collect();
+
+        let clusters = self.cluster_boxes(x, y, &heights);
+        let clusters = model
+            .clusters()
+            .filter_map(|cluster| {
+                let (min, max) = clusters.get(&cluster)?;
+                let (a, b) = (direction.turn(*min), direction.turn(*max));
+                let min = Point::new(a.x.min(b.x), a.y.min(b.y));
+                let max = Point::new(a.x.max(b.x), a.y.max(b.y));
+                let label = self.cluster_labels.get(&cluster).map(|&(_, height)| {
+                    Point::new(
+                        (min.x + max.x) / 2.0,
+                        min.y + CLUSTER_MARGIN / 2.0 + height / 2.0,
+                    )
+                });
+                Some(ClusterLayout {
+                    id: model.subgraphs[cluster].id.clone().unwrap(),
+                    min,
+                    max,
+                    label,
+                })
+            })
+            .collect();
+
+        let mut layout = Layout {
+            width: 0.0,
+            height: 0.0,
+            nodes,
+            edges,
+            clusters,
+        };
+        layout.fit();
+        layout
+    }
+
+    // Cluster boxes top to bottom, inner clusters first
+    fn cluster_boxes(
+        &self,
+        x: &[f64],
+        y: &[f64],
+        heights: &[f64],
+    ) -> HashMap<usize, (Point, Point)> {
+        let flipped = matches!(self.direction, Direction::BottomTop);
+        let mut spans: HashMap<usize, (f64, f64)> = HashMap::new();
+        for node in &self.nodes {
+            let top = y[node.rank] - heights[node.rank] / 2.0;
+            let bottom = y[node.rank] + heights[node.rank] / 2.0;
+            for &cluster in &node.clusters {
+                let span = spans.entry(cluster).or_insert((top, bottom));
+                span.0 = span.0.min(top);
+                span.1 = span.1.max(bottom);
+            }
+        }
+        let mut clusters: Vec<(usize, usize)> = self
+            .clusters
+            .iter()
+            .enumerate()
+            .map(|(k, &c)| (cluster_path(self.model, Some(c)).len(), k))
+            .collect();
+        clusters.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut boxes: HashMap<usize, (Point, Point)> = HashMap::new();
+        for (_, k) in clusters {
+            let cluster = self.clusters[k];
+            let (mut top, mut bottom) = spans[&cluster];
+            for (&other, (min, max)) in &boxes {
+                if self.model.parent_cluster(other) == Some(cluster) {
+                    top = top.min(min.y);
+                    bottom = bottom.max(max.y);
+                }
+            }
+            let label = self.label_room(cluster).1;
+            let (above, below) = if flipped { (0.0, label) } else { (label, 0.0) };
+            boxes.insert(
+                cluster,
+                (
+                    Point::new(x[self.left(k)], top - CLUSTER_MARGIN - above),
+                    Point::new(x[self.right(k)], bottom + CLUSTER_MARGIN + below),
+                ),
+            );
+        }
+        boxes
+    }
+}
+
+// The clusters a node is drawn in, outermost first
+fn cluster_path(model: &Model, cluster: Option<usize>) -> Vec<usize> {
+    let mut path = Vec::new();
+    let mut cluster = cluster;
+    while let Some(c) = cluster {
+        path.push(c);
+        cluster = model.parent_cluster(c);
+    }
+    path.reverse();
+    path
+}
+
+fn common_prefix(a: &[usize], b: &[usize]) -> Vec<usize> {
+    a.iter()
+        .zip(b)
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| *a)
+        .collect()
+}
+
+// Sorts a rank by the keys while keeping the nodes of every cluster together
+// and sibling clusters in their overall order
+fn arrange(
+    layer: &mut Vec<usize>,
+    keys: &[f64],
+    nodes: &[LayerNode],
+    cluster_order: &HashMap<usize, f64>,
+) {
+    *layer = arrange_level(layer.clone(), 0, keys, nodes, cluster_order).1;
+}
+
+// Returns the mean key of the nodes and their arrangement
+fn arrange_level(
+    members: Vec<usize>,
+    depth: usize,
+    keys: &[f64],
+    nodes: &[LayerNode],
+    cluster_order: &HashMap<usize, f64>,
+) -> (f64, Vec<usize>) {
+    enum Item {
+        Node(usize),
+        Cluster(usize, Vec<usize>),
+    }
+    let mut items: Vec<(f64, Item)> = Vec::new();
+    let mut groups: Vec<(usize, Vec<usize>)> = Vec::new();
+    for &node in &members {
+        match nodes[node].clusters.get(depth) {
+            None => items.push((keys[node], Item::Node(node))),
+            Some(&cluster) => match groups.iter_mut().find(|(c, _)| *c == cluster) {
+                Some((_, group)) => group.push(node),
+                None => groups.push((cluster, vec![node])),
+            },
+        }
+    }
+    for (cluster, group) in groups {
+        let (key, arranged) = arrange_level(group, depth + 1, keys, nodes, cluster_order);
+        items.push((key, Item::Cluster(cluster, arranged)));
+    }
+    items.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    // Clusters take the places they were sorted into, in their overall order
+    let mut slots: Vec<usize> = Vec::new();
+    let mut clusters: Vec<(usize, Vec<usize>)> = Vec::new();
+    for (i, (_, item)) in items.iter_mut().enumerate() {
+        if let Item::Cluster(cluster, arranged) = item {
+            slots.push(i);
+            clusters.push((*cluster, std::mem::take(arranged)));
+        }
+    }
+    clusters.sort_by(|a, b| {
+        let key = |c: &usize| cluster_order.get(c).copied().unwrap_or(0.0);
+        key(&a.0)
+            .partial_cmp(&key(&b.0))
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.0.cmp(&b.0))
+    });
+    for (slot, (cluster, arranged)) in slots.into_iter().zip(clusters) {
+        items[slot].1 = Item::Cluster(cluster, arranged);
+    }
+
+    let mean = members.iter().map(|&n| keys[n]).sum::<f64>() / members.len().max(1) as f64;
+    let mut arranged = Vec::new();
+    for (_, item) in items {
+        match item {
+            Item::Node(node) => arranged.push(node),
+            Item::Cluster(_, mut group) => arranged.append(&mut group),
+        }
+    }
+    (mean, arranged)
+}
+
+// Separation constraints `x[to] >= x[from] + gap`. The constraints never
+// form a cycle, as every rank is ordered and clusters are ordered the same
+// way on every rank.
+struct Constraints {
+    // Per variable, the variables it must stay right of and by how much
+    before: Vec<Vec<(usize, f64)>>,
+    after: Vec<Vec<(usize, f64)>>,
+    // A topological order of the variables
+    order: Vec<usize>,
+}
+
+impl Constraints {
+    fn new(count: usize, constraints: Vec<(usize, usize, f64)>) -> Constraints {
+        let mut before: Vec<Vec<(usize, f64)>> = vec![Vec::new(); count];
+        let mut after: Vec<Vec<(usize, f64)>> = vec![Vec::new(); count];
+        for (from, to, gap) in constraints {
+            before[to].push((from, gap));
+            after[from].push((to, gap));
+        }
+        let mut incoming: Vec<usize> = before.iter().map(Vec::len).collect();
+        let mut order: Vec<usize> = (0..count).filter(|&v| incoming[v] == 0).collect();
+        let mut i = 0;
+        while i < order.len() {
+            for &(next, _) in &after[order[i]] {
+                incoming[next] -= 1;
+                if incoming[next] == 0 {
+                    order.push(next);
+                }
+            }
+            i += 1;
+        }
+        Constraints {
+            before,
+            after,
+            order,
+        }
+    }
+
+    // Every variable as far left as the constraints and its desired
+    // position allow
+    fn pack(&self, desired: &[Option<f64>]) -> Vec<f64> {
+        let mut x = vec![f64::NEG_INFINITY; self.before.len()];
+        for &v in &self.order {
+            let lowest = self.before[v]
+                .iter()
+                .map(|&(u, gap)| x[u] + gap)
+                .fold(f64::NEG_INFINITY, f64::max);
+            x[v] = match desired[v] {
+                Some(d) => d.max(lowest),
+                None => lowest,
+            };
+        }
+        // Variables nothing pushes are pulled as close as they may be to
+        // the ones after them
+        for &v in self.order.iter().rev() {
+            if x[v] == f64::NEG_INFINITY {
+                x[v] = self.after[v]
+                    .iter()
+                    .map(|&(w, gap)| x[w] - gap)
+                    .fold(f64::INFINITY, f64::min);
+                if x[v] == f64::INFINITY {
+                    x[v] = 0.0;
+                }
+            }
+        }
+        x
+    }
+
+    // The mean of packing to the left and to the right of the desired
+    // positions, which satisfies the constraints as both do
+    fn balance(&self, desired: &[Option<f64>]) -> Vec<f64> {
+        let left = self.pack(desired);
+        let mirrored = Constraints {
+            before: self.after.clone(),
+            after: self.before.clone(),
+            order: self.order.iter().rev().copied().collect(),
+        };
+        let flipped: Vec<Option<f64>> = desired.iter().map(|d| d.map(|d| -d)).collect();
+        let right = mirrored.pack(&flipped);
+        left.iter().zip(right).map(|(l, r)| (l - r) / 2.0).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{layout, node};
+    use super::*;
+
+    #[test]
+    fn ranks_follow_edges() {
+        let layout = layout(dot, "digraph { a -> b -> c; a -> c; c -> a }");
+        let (a, b, c) = (node(&layout, "a"), node(&layout, "b"), node(&layout, "c"));
+        assert!(a.center.y < b.center.y && b.center.y < c.center.y);
+        // Every edge starts at its tail and ends at its head
+        for edge in &layout.edges {
+            let (tail, head) = (node(&layout, &edge.tail), node(&layout, &edge.head));
+            let (first, last) = (edge.points[0], *edge.points.last().unwrap());
+            assert!(first.distance(tail.center) < last.distance(tail.center));
+            assert!(last.distance(head.center) < first.distance(head.center));
+        }
+        assert_eq!(layout.edges[2].points.len(), 3);
+        let spline = layout.edges[2].spline();
+        assert_eq!(spline.len(), 7);
+    }
+
+    #[test]
+    fn rankdir_and_same_rank() {
+        let left_right = layout(
+            dot,
+            "digraph { rankdir=LR; a -> b; a -> c; { rank=same; b; c } c -> d }",
+        );
+        let (a, b, c, d) = (
+            node(&left_right, "a"),
+            node(&left_right, "b"),
+            node(&left_right, "c"),
+            node(&left_right, "d"),
+        );
+        assert!(a.center.x < b.center.x);
+        assert_eq!(b.center.x, c.center.x);
+        assert!(c.center.x < d.center.x);
+        assert!(a.width > a.height);
+
+        let bottom_top = layout(dot, "digraph { rankdir=BT; a -> b }");
+        assert!(node(&bottom_top, "a").center.y > node(&bottom_top, "b").center.y);
+    }
+
+    #[test]
+    fn no_overlaps() {
+        let layout = layout(dot, "digraph { a -> {b c d e}; b -> f; e -> f; c -> d }");
+        for (i, m) in layout.nodes.iter().enumerate() {
+            for n in &layout.nodes[i + 1..] {
+                let apart_x = (m.center.x - n.center.x).abs() >= (m.width + n.width) / 2.0;
+                let apart_y = (m.center.y - n.center.y).abs() >= (m.height + n.height) / 2.0;
+                assert!(apart_x || apart_y);
+            }
+        }
+        assert!(layout.width > 0.0 && layout.height > 0.0);
+    }
+
+    #[test]
+    fn clusters_hold_their_nodes() {
+        let layout = layout(
+            dot,
+            r#"digraph {
+                subgraph cluster_0 { label="one"; a -> b; subgraph cluster_inner { c } }
+                subgraph cluster_1 { d -> e }
+                x -> a; x -> d; b -> e; a -> c; e -> y; c -> y;
+            }"#,
+        );
+        assert_eq!(layout.clusters.len(), 3);
+        let inside = |cluster: &ClusterLayout, id: &str| {
+            let n = node(&layout, id);
+            cluster.min.x <= n.center.x - n.width / 2.0
+                && n.center.x + n.width / 2.0 <= cluster.max.x
+                && cluster.min.y <= n.center.y - n.height / 2.0
+                && n.center.y + n.height / 2.0 <= cluster.max.y
+        };
+        let (outer, inner, other) = (
+            &layout.clusters[0],
+            &layout.clusters[1],
+            &layout.clusters[2],
+        );
+        for id in ["a", "b", "c"] {
+            assert!(inside(outer, id), "{}", id);
+        }
+        assert!(inside(inner, "c"));
+        assert!(inside(other, "d") && inside(other, "e"));
+        for id in ["x", "y", "d", "e"] {
+            assert!(!inside(outer, id), "{}", id);
+        }
+        assert!(outer.max.x <= other.min.x || other.max.x <= outer.min.x);
+        assert!(outer.label.is_some() && other.label.is_none());
+    }
+
+    #[test]
+    fn labels_and_loops() {
+        let layout = layout(
+            dot,
+            "digraph { a -> b [label=\"long label\"]; a -> a; a -> b }",
+        );
+        let label = layout.edges[0].label.unwrap();
+        let (a, b) = (node(&layout, "a"), node(&layout, "b"));
+        assert!(a.center.y < label.y && label.y < b.center.y);
+        let edge = &layout.edges[1];
+        assert!(edge.points.iter().all(|p| p.x >= a.center.x));
+        assert_eq!(edge.points.len(), 4);
+    }
+}
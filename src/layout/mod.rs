@@ -0,0 +1,370 @@
+mod dot;
+mod neato;
+
+use crate::ast::{Attribute, Graph, Id, ToId};
+use crate::attributes::Shape;
+use crate::model::{find, number, Model};
+
+pub use dot::dot;
+pub use neato::neato;
+
+// docs
+// * https://graphviz.org/docs/attrs/width/
+// * https://graphviz.org/docs/attrs/fixedsize/
+// * https://graphviz.org/docs/attrs/fontsize/
+//
+// Layouts computed without graphviz. Coordinates are in points (1/72 inch)
+// with the origin at the top left corner and y growing downwards, the way
+// SVG and terminals draw. Nodes, edges and clusters are listed in the order
+// the graph first mentions them.
+
+pub(crate) const POINTS_PER_INCH: f64 = 72.0;
+// Space left around the drawing
+pub(crate) const PAD: f64 = 4.0;
+const DEFAULT_FONT_SIZE: f64 = 14.0;
+// Horizontal and vertical space between a label and the node outline
+const LABEL_MARGIN: (f64, f64) = (8.0, 4.0);
+// How far every self loop reaches out past the one before it
+pub(crate) const LOOP_SIZE: f64 = 18.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    pub(crate) fn distance(&self, other: Point) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+pub struct NodeLayout {
+    pub id: Id,
+    pub center: Point,
+    pub width: f64,
+    pub height: f64,
+}
+
+pub struct EdgeLayout {
+    pub tail: Id,
+    pub head: Id,
+    // From the tail's outline to the head's
+    pub points: Vec<Point>,
+    pub label: Option<Point>,
+}
+
+impl EdgeLayout {
+    // The polyline smoothed into a cubic B-spline in graphviz's form: a start
+    // point followed by two control points and an end point per segment
+    pub fn spline(&self) -> Vec<Point> {
+        spline(&self.points)
+    }
+}
+
+pub(crate) fn spline(points: &[Point]) -> Vec<Point> {
+    let mut spline = Vec::new();
+    if let Some(&first) = points.first() {
+        spline.push(first);
+    }
+    for i in 0..points.len().saturating_sub(1) {
+        let before = points[i.saturating_sub(1)];
+        let (from, to) = (points[i], points[i + 1]);
+        let after = points[(i + 2).min(points.len() - 1)];
+        spline.push(Point::new(
+            from.x + (to.x - before.x) / 6.0,
+            from.y + (to.y - before.y) / 6.0,
+        ));
+        spline.push(Point::new(
+            to.x - (after.x - from.x) / 6.0,
+            to.y - (after.y - from.y) / 6.0,
+        ));
+        spline.push(to);
+    }
+    spline
+}
+
+pub struct ClusterLayout {
+    pub id: Id,
+    // The top left and bottom right corners
+    pub min: Point,
+    pub max: Point,
+    pub label: Option<Point>,
+}
+
+pub struct Layout {
+    pub width: f64,
+    pub height: f64,
+    pub nodes: Vec<NodeLayout>,
+    pub edges: Vec<EdgeLayout>,
+    pub clusters: Vec<ClusterLayout>,
+}
+
+impl Layout {
+    // The node with this name, e.g. `layout.node("a")`
+    pub fn node<T: ToId>(&self, id: T) -> Option<&NodeLayout> {
+        let id = id.to_id();
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    pub fn cluster<T: ToId>(&self, id: T) -> Option<&ClusterLayout> {
+        let id = id.to_id();
+        self.clusters.iter().find(|cluster| cluster.id == id)
+    }
+
+    // Moves everything so that the drawing starts at (PAD, PAD) and sets the
+    // size to fit
+    pub(crate) fn fit(&mut self) {
+        let mut min = Point::new(f64::INFINITY, f64::INFINITY);
+        let mut max = Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY);
+        let mut extend = |p: Point| {
+            min = Point::new(min.x.min(p.x), min.y.min(p.y));
+            max = Point::new(max.x.max(p.x), max.y.max(p.y));
+        };
+        for node in &self.nodes {
+            extend(Point::new(
+                node.center.x - node.width / 2.0,
+                node.center.y - node.height / 2.0,
+            ));
+            extend(Point::new(
+                node.center.x + node.width / 2.0,
+                node.center.y + node.height / 2.0,
+            ));
+        }
+        for point in self.edges.iter().flat_map(|e| &e.points) {
+            extend(*point);
+        }
+        for cluster in &self.clusters {
+            extend(cluster.min);
+            extend(cluster.max);
+        }
+        if min.x > max.x {
+            min = Point::new(0.0, 0.0);
+            max = min;
+        }
+        let shift = |p: &mut Point| {
+            p.x += PAD - min.x;
+            p.y += PAD - min.y;
+        };
+        for node in &mut self.nodes {
+            shift(&mut node.center);
+        }
+        for edge in &mut self.edges {
+            edge.points.iter_mut().for_each(shift);
+            edge.label.iter_mut().for_each(shift);
+        }
+        for cluster in &mut self.clusters {
+            shift(&mut cluster.min);
+            shift(&mut cluster.max);
+            cluster.label.iter_mut().for_each(shift);
+        }
+        self.width = max.x - min.x + 2.0 * PAD;
+        self.height = max.y - min.y + 2.0 * PAD;
+    }
+}
+
+// The native layout closest to the graph's `layout` engine: neato for the
+// force directed engines and dot for the others
+pub fn layout(graph: &Graph) -> Layout {
+    let model = Model::new(graph);
+    match find(&model.attributes, "layout")
+        .map(Attribute::value)
+        .as_deref()
+    {
+        Some("neato") | Some("fdp") | Some("sfdp") => neato::stress(&model),
+        _ => dot::layered(&model),
+    }
+}
+
+pub(crate) fn font_size(attributes: &[Attribute]) -> f64 {
+    number(attributes, "fontsize").unwrap_or(DEFAULT_FONT_SIZE)
+}
+
+// An estimate of the space text takes up, without font metrics: an average
+// character is about half as wide as the font is tall
+pub(crate) fn text_size(text: &str, font_size: f64) -> (f64, f64) {
+    let lines = text.split('\n');
+    let widest = lines.clone().map(|l| l.chars().count()).max().unwrap_or(0);
+    let width = widest as f64 * font_size * 0.55;
+    let height = lines.count() as f64 * font_size * 1.2;
+    (width, height)
+}
+
+fn shape(model: &Model, node: usize) -> Option<&Shape> {
+    match find(&model.nodes[node].attributes, "shape") {
+        Some(Attribute::Shape(shape)) => Some(shape),
+        _ => None,
+    }
+}
+
+// Shapes whose outline is an ellipse rather than a polygon, ellipses being
+// the default
+pub(crate) fn is_elliptical(model: &Model, node: usize) -> bool {
+    match shape(model, node) {
+        None => true,
+        Some(shape) => matches!(
+            shape,
+            Shape::Ellipse
+                | Shape::Oval
+                | Shape::Circle
+                | Shape::DoubleCircle
+                | Shape::MCircle
+                | Shape::Point
+                | Shape::Egg
+        ),
+    }
+}
+
+// The width and height of a node in points: large enough for its label and
+// at least `width` by `height` inches, or exactly that with `fixedsize`
+pub(crate) fn node_size(model: &Model, node: usize) -> (f64, f64) {
+    let attributes = &model.nodes[node].attributes;
+    let shape = shape(model, node);
+    if let Some(Shape::Point) = shape {
+        let size = number(attributes, "width").unwrap_or(0.05) * POINTS_PER_INCH;
+        return (size, size);
+    }
+    let min_width = number(attributes, "width").unwrap_or(0.75) * POINTS_PER_INCH;
+    let min_height = number(attributes, "height").unwrap_or(0.5) * POINTS_PER_INCH;
+    let fixed = find(attributes, "fixedsize").is_some_and(|a| a.value() == "true");
+    if fixed {
+        return (min_width, min_height);
+    }
+
+    let (mut width, mut height) = text_size(&model.node_label(node), font_size(attributes));
+    let (min_width, min_height) = match shape {
+        Some(Shape::Plain) => (0.0, 0.0),
+        _ => {
+            width += 2.0 * LABEL_MARGIN.0;
+            height += 2.0 * LABEL_MARGIN.1;
+            (min_width, min_height)
+        }
+    };
+    if is_elliptical(model, node) {
+        // The ellipse around the label's box
+        width *= std::f64::consts::SQRT_2;
+        height *= std::f64::consts::SQRT_2;
+    }
+    let (width, height) = (width.max(min_width), height.max(min_height));
+    match shape {
+        Some(Shape::Circle)
+        | Some(Shape::DoubleCircle)
+        | Some(Shape::MCircle)
+        | Some(Shape::Square)
+        | Some(Shape::MSquare) => (width.max(height), width.max(height)),
+        _ => (width, height),
+    }
+}
+
+// Where the line from a node's center towards `toward` leaves its outline
+pub(crate) fn clip(center: Point, size: (f64, f64), elliptical: bool, toward: Point) -> Point {
+    let (dx, dy) = (toward.x - center.x, toward.y - center.y);
+    let (a, b) = (size.0 / 2.0, size.1 / 2.0);
+    if (dx == 0.0 && dy == 0.0) || a == 0.0 || b == 0.0 {
+        return center;
+    }
+    let t = if elliptical {
+        1.0 / ((dx / a).powi(2) + (dy / b).powi(2)).sqrt()
+    } else {
+        (a / dx.abs()).min(b / dy.abs())
+    };
+    // The target may lie inside the node
+    let t = t.min(1.0);
+    Point::new(center.x + dx * t, center.y + dy * t)
+}
+
+// A self loop on the right side of a node, outside the loops before it
+pub(crate) fn self_loop(
+    center: Point,
+    size: (f64, f64),
+    elliptical: bool,
+    nth: usize,
+) -> Vec<Point> {
+    let (width, height) = size;
+    let reach = center.x + width / 2.0 + LOOP_SIZE * (nth as f64 + 1.0);
+    let start = Point::new(center.x + width, center.y - height / 4.0);
+    let end = Point::new(center.x + width, center.y + height / 4.0);
+    vec![
+        clip(center, size, elliptical, start),
+        Point::new(reach, center.y - height / 3.0),
+        Point::new(reach, center.y + height / 3.0),
+        clip(center, size, elliptical, end),
+    ]
+}
+
+// The point halfway along a polyline
+pub(crate) fn midpoint(points: &[Point]) -> Option<Point> {
+    let length: f64 = points.windows(2).map(|w| w[0].distance(w[1])).sum();
+    let mut remaining = length / 2.0;
+    for w in points.windows(2) {
+        let segment = w[0].distance(w[1]);
+        if remaining <= segment && segment > 0.0 {
+            let t = remaining / segment;
+            return Some(Point::new(
+                w[0].x + (w[1].x - w[0].x) * t,
+                w[0].y + (w[1].y - w[0].y) * t,
+            ));
+        }
+        remaining -= segment;
+    }
+    points.first().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_graph;
+
+    // Fixtures for the tests of the engines as well
+    pub(super) fn layout(engine: fn(&Graph) -> Layout, dot_source: &str) -> Layout {
+        engine(&parse_graph(dot_source).unwrap())
+    }
+
+    pub(super) fn node<T: ToId>(layout: &Layout, id: T) -> &NodeLayout {
+        layout.node(id).unwrap()
+    }
+
+    #[test]
+    fn clipping() {
+        let center = Point::new(0.0, 0.0);
+        let p = clip(center, (40.0, 20.0), false, Point::new(100.0, 0.0));
+        assert_eq!(p, Point::new(20.0, 0.0));
+        let p = clip(center, (40.0, 20.0), false, Point::new(0.0, -50.0));
+        assert_eq!(p, Point::new(0.0, -10.0));
+        let p = clip(center, (40.0, 20.0), true, Point::new(30.0, 0.0));
+        assert_eq!(p, Point::new(20.0, 0.0));
+
+        let edge = EdgeLayout {
+            tail: Id::new("a"),
+            head: Id::new("b"),
+            points: vec![Point::new(0.0, 0.0), Point::new(0.0, 30.0)],
+            label: None,
+        };
+        assert_eq!(
+            edge.spline(),
+            [
+                Point::new(0.0, 0.0),
+                Point::new(0.0, 5.0),
+                Point::new(0.0, 25.0),
+                Point::new(0.0, 30.0)
+            ]
+        );
+        assert_eq!(midpoint(&edge.points), Some(Point::new(0.0, 15.0)));
+    }
+
+    #[test]
+    fn lookups() {
+        let layout = layout(
+            dot::dot,
+            r#"digraph { "C:\\x" -> "a\"b"; subgraph "cluster \\" { c } }"#,
+        );
+        assert!(layout.node(r"C:\x").is_some());
+        assert!(layout.node("a\"b").is_some());
+        assert!(layout.node("C:x").is_none());
+        assert!(layout.cluster(r"cluster \").is_some());
+    }
+}
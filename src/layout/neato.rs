@@ -0,0 +1,513 @@
+use crate::ast::{Attribute, Graph};
+use crate::attributes::{Overlap, Start};
+use crate::layout::{
+    clip, font_size, is_elliptical, midpoint, node_size, self_loop, text_size, EdgeLayout, Layout,
+    NodeLayout, Point, POINTS_PER_INCH,
+};
+use crate::model::{find, number, Model};
+
+// docs
+// * https://graphviz.org/docs/layouts/neato/
+// * Gansner, Koren and North, "Graph Drawing by Stress Majorization", 2004
+//
+// A drawing where the distance between two nodes matches the length of the
+// shortest path between them, every edge being `len` inches long:
+// 1. nodes start at their `pos`, or on a circle or at random positions as
+//    `start` says,
+// 2. stress majorization moves one node at a time to where its distances to
+//    all the others fit best, near nodes weighing more than far ones, until
+//    nothing moves any more. Pinned nodes stay where they are,
+// 3. overlapping nodes are pushed apart as `overlap` says,
+// 4. edges are drawn straight, parallel edges bending apart.
+//
+// Like neato, clusters are not drawn.
+
+// In inches
+const DEFAULT_LENGTH: f64 = 1.0;
+const MAX_ITERATIONS: usize = 200;
+// Positions are final once no node moves further than this
+const EPSILON: f64 = 0.01;
+// Space kept between nodes when removing overlaps, graphviz's default `sep`
+const SEP: f64 = 4.0;
+const OVERLAP_ROUNDS: usize = 100;
+const PARALLEL_SPACING: f64 = 12.0;
+// Used for `start=random` without a seed so that drawings are reproducible
+const DEFAULT_SEED: u64 = 1;
+
+pub fn neato(graph: &Graph) -> Layout {
+    stress(&Model::new(graph))
+}
+
+pub(crate) fn stress(model: &Model) -> Layout {
+    Stress::new(model).layout()
+}
+
+struct Stress<'a> {
+    model: &'a Model,
+    sizes: Vec<(f64, f64)>,
+    // The wanted distance between every pair of nodes
+    distances: Vec<Vec<f64>>,
+    pinned: Vec<bool>,
+    x: Vec<f64>,
+    y: Vec<f64>,
+}
+
+impl<'a> Stress<'a> {
+    fn new(model: &'a Model) -> Stress<'a> {
+        let n = model.nodes.len();
+        Stress {
+            model,
+            sizes: (0..n).map(|node| node_size(model, node)).collect(),
+            distances: Vec::new(),
+            pinned: vec![false; n],
+            x: vec![0.0; n],
+            y: vec![0.0; n],
+        }
+    }
+
+    fn layout(mut self) -> Layout {
+        self.shortest_paths();
+        self.start();
+        self.majorize();
+        match find(&self.model.attributes, "overlap") {
+            Some(Attribute::Overlap(Overlap::Scale)) => self.scale(false),
+            Some(Attribute::Overlap(Overlap::ScaleXY)) => self.scale(true),
+            Some(Attribute::Overlap(Overlap::Retain)) | None => {}
+            Some(_) => self.push_apart(),
+        }
+        self.finish()
+    }
+
+    fn length(&self, edge: usize) -> f64 {
+        let length = number(&self.model.edges[edge].attributes, "len").unwrap_or(DEFAULT_LENGTH);
+        length.max(0.0) * POINTS_PER_INCH
+    }
+
+    fn mean_length(&self) -> f64 {
+        let edges = self.model.edges.len();
+        if edges == 0 {
+            return DEFAULT_LENGTH * POINTS_PER_INCH;
+        }
+        (0..edges).map(|e| self.length(e)).sum::<f64>() / edges as f64
+    }
+
+    // Dijkstra from every node, edges going both ways. Nodes in different
+    // components are kept a little further apart than any connected pair.
+    fn shortest_paths(&mut self) {
+        let n = self.model.nodes.len();
+        let mut adjacent = vec![Vec::new(); n];
+        for (e, edge) in self.model.edges.iter().enumerate() {
+            if edge.tail != edge.head {
+                adjacent[edge.tail].push((edge.head, self.length(e)));
+                adjacent[edge.head].push((edge.tail, self.length(e)));
+            }
+        }
+
+        let mut distances = vec![vec![f64::INFINITY; n]; n];
+        for (source, row) in distances.iter_mut().enumerate() {
+            let mut done = vec![false; n];
+            row[source] = 0.0;
+            while let Some(node) = (0..n)
+                .filter(|&m| !done[m] && row[m].is_finite())
+                .min_by(|&a, &b| row[a].total_cmp(&row[b]))
+            {
+                done[node] = true;
+                for &(next, length) in &adjacent[node] {
+                    row[next] = row[next].min(row[node] + length);
+                }
+            }
+        }
+
+        let longest = distances
+            .iter()
+            .flatten()
+            .filter(|d| d.is_finite())
+            .fold(0.0, |a: f64, &b| a.max(b));
+        let apart = longest + self.mean_length();
+        for d in distances.iter_mut().flatten() {
+            if d.is_infinite() {
+                *d = apart;
+            }
+        }
+        self.distances = distances;
+    }
+
+    // Nodes with a `pos` start there, in points with y growing downwards,
+    // the others on a circle or scattered over a square
+    fn start(&mut self) {
+        let model = self.model;
+        let n = model.nodes.len();
+        let mut placed = vec![false; n];
+        for (node, model_node) in model.nodes.iter().enumerate() {
+            if let Some(Attribute::Pos(pos)) = find(&model_node.attributes, "pos") {
+                self.x[node] = pos.x * POINTS_PER_INCH;
+                self.y[node] = -pos.y * POINTS_PER_INCH;
+                let pin = matches!(
+                    find(&model_node.attributes, "pin"),
+                    Some(Attribute::Pin(true))
+                );
+                self.pinned[node] = pos.pinned || pin;
+                placed[node] = true;
+            }
+        }
+
+        let spread = self.mean_length() * (n as f64).sqrt();
+        match find(&model.attributes, "start") {
+            Some(Attribute::Start(Start::Regular)) => {
+                let radius = spread / 2.0;
+                for node in (0..n).filter(|&node| !placed[node]) {
+                    let angle = 2.0 * std::f64::consts::PI * node as f64 / n as f64;
+                    self.x[node] = radius * angle.cos();
+                    self.y[node] = radius * angle.sin();
+                }
+            }
+            start => {
+                let seed = match start {
+                    Some(Attribute::Start(Start::Random(Some(seed)))) => *seed,
+                    _ => DEFAULT_SEED,
+                };
+                let mut random = Random(seed);
+                for node in (0..n).filter(|&node| !placed[node]) {
+                    self.x[node] = random.next() * spread;
+                    self.y[node] = random.next() * spread;
+                }
+            }
+        }
+    }
+
+    // Moves every node in turn to the weighted mean of where each other node
+    // would have it: the wanted distance away, in the direction it is in now
+    fn majorize(&mut self) {
+        let n = self.model.nodes.len();
+        for _ in 0..MAX_ITERATIONS {
+            let mut moved: f64 = 0.0;
+            for i in 0..n {
+                if self.pinned[i] {
+                    continue;
+                }
+                let (mut sum_x, mut sum_y, mut sum_weight) = (0.0, 0.0, 0.0);
+                for j in (0..n).filter(|&j| j != i) {
+                    let wanted = self.distances[i][j];
+                    if wanted <= 0.0 {
+                        continue;
+                    }
+                    let weight = 1.0 / (wanted * wanted);
+                    let (dx, dy) = (self.x[i] - self.x[j], self.y[i] - self.y[j]);
+                    let actual = dx.hypot(dy);
+                    // Nodes on top of each other part in a direction of their own
+                    let (ux, uy) = if actual > 0.0 {
+                        (dx / actual, dy / actual)
+                    } else {
+                        ((i as f64).cos(), (i as f64).sin())
+                    };
+                    sum_x += weight * (self.x[j] + wanted * ux);
+                    sum_y += weight * (self.y[j] + wanted * uy);
+                    sum_weight += weight;
+                }
+                if sum_weight == 0.0 {
+                    continue;
+                }
+                let (x, y) = (sum_x / sum_weight, sum_y / sum_weight);
+                moved = moved.max((x - self.x[i]).hypot(y - self.y[i]));
+                self.x[i] = x;
+                self.y[i] = y;
+            }
+            if moved < EPSILON {
+                break;
+            }
+        }
+    }
+
+    // How far two nodes overlap along x and y, including the separation
+    // kept between them. They overlap when both are positive.
+    fn overlap(&self, a: usize, b: usize) -> (f64, f64) {
+        let (dx, dy) = (self.x[a] - self.x[b], self.y[a] - self.y[b]);
+        (
+            (self.sizes[a].0 + self.sizes[b].0) / 2.0 + SEP - dx.abs(),
+            (self.sizes[a].1 + self.sizes[b].1) / 2.0 + SEP - dy.abs(),
+        )
+    }
+
+    fn pairs(&self) -> impl Iterator<Item = (usize, usize)> {
+        let n = self.model.nodes.len();
+        (0..n).flat_map(move |a| (a + 1..n).map(move |b| (a, b)))
+    }
+
+    // Spreads the whole drawing, pinned nodes included, just enough for every
+    // pair of nodes to be apart along x or y. Nodes right on top of each
+    // other cannot be spread and are pushed apart afterwards.
+    fn scale(&mut self, separately: bool) {
+        let (mut scale_x, mut scale_y): (f64, f64) = (1.0, 1.0);
+        for (a, b) in self.pairs() {
+            let (ox, oy) = self.overlap(a, b);
+            if ox <= 0.0 || oy <= 0.0 {
+                continue;
+            }
+            let (dx, dy) = ((self.x[a] - self.x[b]).abs(), (self.y[a] - self.y[b]).abs());
+            let fx = if dx > 0.0 {
+                (dx + ox) / dx
+            } else {
+                f64::INFINITY
+            };
+            let fy = if dy > 0.0 {
+                (dy + oy) / dy
+            } else {
+                f64::INFINITY
+            };
+            if separately {
+                if fx <= fy && fx.is_finite() {
+                    scale_x = scale_x.max(fx);
+                } else if fy.is_finite() {
+                    scale_y = scale_y.max(fy);
+                }
+            } else if fx.min(fy).is_finite() {
+                scale_x = scale_x.max(fx.min(fy));
+                scale_y = scale_x;
+            }
+        }
+        self.x.iter_mut().for_each(|x| *x *= scale_x);
+        self.y.iter_mut().for_each(|y| *y *= scale_y);
+        self.push_apart();
+    }
+
+    // Moves overlapping nodes apart along the axis they overlap least on,
+    // each by half unless one of them is pinned
+    fn push_apart(&mut self) {
+        for _ in 0..OVERLAP_ROUNDS {
+            let mut moved = false;
+            for (a, b) in self.pairs().collect::<Vec<_>>() {
+                let (ox, oy) = self.overlap(a, b);
+                if ox <= 0.0 || oy <= 0.0 || (self.pinned[a] && self.pinned[b]) {
+                    continue;
+                }
+                let (share_a, share_b) = match (self.pinned[a], self.pinned[b]) {
+                    (true, _) => (0.0, 1.0),
+                    (_, true) => (1.0, 0.0),
+                    _ => (0.5, 0.5),
+                };
+                // `b` goes right or down when the two are level
+                if ox < oy {
+                    let side = if self.x[a] > self.x[b] { 1.0 } else { -1.0 };
+                    self.x[a] += side * ox * share_a;
+                    self.x[b] -= side * ox * share_b;
+                } else {
+                    let side = if self.y[a] > self.y[b] { 1.0 } else { -1.0 };
+                    self.y[a] += side * oy * share_a;
+                    self.y[b] -= side * oy * share_b;
+                }
+                moved = true;
+            }
+            if !moved {
+                break;
+            }
+        }
+    }
+
+    fn finish(&self) -> Layout {
+        let model = self.model;
+        let center = |n: usize| Point::new(self.x[n], self.y[n]);
+        let elliptical = |n: usize| is_elliptical(model, n);
+
+        let mut loops = vec![0; model.nodes.len()];
+        let mut edges = Vec::new();
+        for (e, edge) in model.edges.iter().enumerate() {
+            let (tail, head) = (edge.tail, edge.head);
+            let label_size = model
+                .edge_label(e)
+                .map(|label| text_size(&label, font_size(&edge.attributes)));
+            let (points, label) = if tail == head {
+                let points = self_loop(
+                    center(tail),
+                    self.sizes[tail],
+                    elliptical(tail),
+                    loops[tail],
+                );
+                loops[tail] += 1;
+                let label = label_size
+                    .map(|(w, _)| Point::new(points[1].x + w / 2.0 + 2.0, center(tail).y));
+                (points, label)
+            } else {
+                // Earlier edges between the same two nodes, either way round
+                let parallel = model.edges[..e]
+                    .iter()
+                    .filter(|other| {
+                        (other.tail, other.head) == (tail, head)
+                            || (other.tail, other.head) == (head, tail)
+                    })
+                    .count();
+                let (a, b) = (center(tail), center(head));
+                let mut points = vec![a, b];
+                if parallel > 0 {
+                    // Alternating sides, further out for every pair
+                    let side = if parallel % 2 == 1 { 1.0 } else { -1.0 };
+                    let offset = side * parallel.div_ceil(2) as f64 * PARALLEL_SPACING;
+                    let length = a.distance(b).max(1.0);
+                    let (nx, ny) = (-(b.y - a.y) / length, (b.x - a.x) / length);
+                    let middle = Point::new(
+                        (a.x + b.x) / 2.0 + nx * offset,
+                        (a.y + b.y) / 2.0 + ny * offset,
+                    );
+                    points.insert(1, middle);
+                }
+                let last = points.len() - 1;
+                points[0] = clip(a, self.sizes[tail], elliptical(tail), points[1]);
+                points[last] = clip(b, self.sizes[head], elliptical(head), points[last - 1]);
+                // Beside mostly vertical edges, above the others
+                let label = label_size.and_then(|(w, h)| {
+                    let p = midpoint(&points)?;
+                    if (b.y - a.y).abs() > (b.x - a.x).abs() {
+                        Some(Point::new(p.x + w / 2.0 + 2.0, p.y))
+                    } else {
+                        Some(Point::new(p.x, p.y - h / 2.0 - 2.0))
+                    }
+                });
+                (points, label)
+            };
+            edges.push(EdgeLayout {
+                tail: edge.from.id.clone(),
+                head: edge.to.id.clone(),
+                points,
+                label,
+            });
+        }
+
+        let nodes = model
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(n, node)| NodeLayout {
+                id: node.id.clone(),
+                center: center(n),
+                width: self.sizes[n].0,
+                height: self.sizes[n].1,
+            })
+            .collect();
+
+        let mut layout = Layout {
+            width: 0.0,
+            height: 0.0,
+            nodes,
+            edges,
+            clusters: Vec::new(),
+        };
+        layout.fit();
+        layout
+    }
+}
+
+// splitmix64, small and the same on every platform
+struct Random(u64);
+
+impl Random {
+    // Uniform in [0, 1)
+    fn next(&mut self) -> f64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{layout, node};
+    use super::*;
+
+    fn overlapping(layout: &Layout) -> bool {
+        let nodes = &layout.nodes;
+        (0..nodes.len()).any(|i| {
+            (i + 1..nodes.len()).any(|j| {
+                let (a, b) = (&nodes[i], &nodes[j]);
+                (a.center.x - b.center.x).abs() < (a.width + b.width) / 2.0
+                    && (a.center.y - b.center.y).abs() < (a.height + b.height) / 2.0
+            })
+        })
+    }
+
+    #[test]
+    fn edge_lengths() {
+        let layout = layout(neato, "graph { a -- b [len=2]; b -- c; c -- a [len=3] }");
+        let (a, b, c) = (node(&layout, "a"), node(&layout, "b"), node(&layout, "c"));
+        assert!((a.center.distance(b.center) - 144.0).abs() < 1.0);
+        assert!((b.center.distance(c.center) - 72.0).abs() < 1.0);
+        assert!((c.center.distance(a.center) - 216.0).abs() < 1.0);
+        for edge in &layout.edges {
+            assert_eq!(edge.points.len(), 2);
+        }
+    }
+
+    #[test]
+    fn entity_relation_diagram() {
+        // https://graphviz.org/Gallery/undirected/ER.html
+        let layout = layout(
+            neato,
+            r#"graph {
+                node [shape=box]; course; institute; student;
+                node [shape=ellipse]; name0 [label=name]; name1 [label=name]; name2 [label=name];
+                code; grade; number;
+                node [shape=diamond, style=filled, color=lightgrey]; "C-I"; "S-C"; "S-I";
+                name0 -- course; code -- course;
+                course -- "C-I" [label=n, len=1.00]; "C-I" -- institute [label=1, len=1.00];
+                institute -- name1; institute -- "S-I" [label=1, len=1.00];
+                "S-I" -- student [label=n, len=1.00];
+                student -- grade; student -- name2; student -- number;
+                student -- "S-C" [label=m, len=1.00]; "S-C" -- course [label=n, len=1.00];
+            }"#,
+        );
+        assert_eq!(layout.nodes.len(), 12);
+        assert_eq!(layout.edges.len(), 12);
+        let (course, student) = (node(&layout, "course"), node(&layout, "student"));
+        let sc = node(&layout, "S-C");
+        // S-C sits between the two entities it relates
+        assert!(sc.center.distance(course.center) < course.center.distance(student.center));
+        assert!(sc.center.distance(student.center) < course.center.distance(student.center));
+    }
+
+    #[test]
+    fn pinned_positions() {
+        let layout = layout(
+            neato,
+            r#"graph { a [pos="0,0!"]; b [pos="3,1", pin=true]; c [pos="1,1"]; a -- c -- b; a -- d }"#,
+        );
+        let (a, b) = (node(&layout, "a"), node(&layout, "b"));
+        assert!((b.center.x - a.center.x - 216.0).abs() < 1e-9);
+        assert!((a.center.y - b.center.y - 72.0).abs() < 1e-9);
+        let d = node(&layout, "d");
+        assert!((a.center.distance(d.center) - 72.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn seeds() {
+        let centers = |start: &str| {
+            let source = format!(
+                "graph {{ start={}; a -- b -- c -- d -- a; a -- c; e }}",
+                start
+            );
+            let layout = layout(neato, &source);
+            layout.nodes.iter().map(|n| n.center).collect::<Vec<_>>()
+        };
+        assert!(centers("random7") == centers("random7"));
+        assert!(centers("random7") != centers("8"));
+        assert!(centers("regular") == centers("regular"));
+    }
+
+    #[test]
+    fn overlaps() {
+        let source = |overlap: &str| {
+            format!(
+                "graph {{ overlap={}; node [shape=box, width=2]; a -- b [len=0.5]; b -- c [len=0.5]; a -- c [len=0.5]; c -- c }}",
+                overlap
+            )
+        };
+        assert!(overlapping(&layout(neato, &source("true"))));
+        for overlap in ["false", "prism", "scale", "scalexy"] {
+            assert!(
+                !overlapping(&layout(neato, &source(overlap))),
+                "{}",
+                overlap
+            );
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use crate::parser::ParseError;
+
+// https://graphviz.org/doc/info/lang.html
+
+pub(crate) const KEYWORDS: [&str; 6] = ["node", "edge", "graph", "digraph", "subgraph", "strict"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    // Trivia
+    Whitespace,
+    LineComment,
+    BlockComment,
+    Preprocessor,
+    // Punctuation
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Equals,
+    Semicolon,
+    Comma,
+    Colon,
+    Plus,
+    DirectedEdge,
+    UndirectedEdge,
+    // IDs
+    Identifier,
+    Numeral,
+    QuotedString,
+    Html,
+}
+
+impl TokenKind {
+    pub(crate) fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            TokenKind::Whitespace
+                | TokenKind::LineComment
+                | TokenKind::BlockComment
+                | TokenKind::Preprocessor
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Token<'a> {
+    pub(crate) kind: TokenKind,
+    pub(crate) text: &'a str,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+}
+
+pub(crate) fn tokenize(input: &str) -> Result<Vec<Token<'_>>, ParseError> {
+    let mut lexer = Lexer {
+        input,
+        offset: 0,
+        line: 1,
+        column: 1,
+    };
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.next_token()? {
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Lexer<'a> {
+    input: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.offset..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.rest().chars().nth(1)
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.input[..self.offset]
+            .chars()
+            .rev()
+            .take_while(|&c| c != '\n')
+            .all(|c| c == ' ' || c == '\t')
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn bump_while<P: Fn(char) -> bool>(&mut self, predicate: P) {
+        while let Some(c) = self.peek() {
+            if !predicate(c) {
+                break;
+            }
+            self.bump();
+        }
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError::new(message, self.line, self.column)
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token<'a>>, ParseError> {
+        let start = self.offset;
+        let (line, column) = (self.line, self.column);
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+        let kind = match c {
+            c if c.is_whitespace() => {
+                self.bump_while(char::is_whitespace);
+                TokenKind::Whitespace
+            }
+            '#' if self.at_line_start() => {
+                self.bump_while(|c| c != '\n');
+                TokenKind::Preprocessor
+            }
+            '/' if self.peek_second() == Some('/') => {
+                self.bump_while(|c| c != '\n');
+                TokenKind::LineComment
+            }
+            '/' if self.peek_second() == Some('*') => {
+                self.bump();
+                self.bump();
+                loop {
+                    if self.rest().starts_with("*/") {
+                        self.bump();
+                        self.bump();
+                        break;
+                    }
+                    if self.bump().is_none() {
+                        return Err(ParseError::new("unterminated comment", line, column));
+                    }
+                }
+                TokenKind::BlockComment
+            }
+            '{' => self.single(TokenKind::LBrace),
+            '}' => self.single(TokenKind::RBrace),
+            '[' => self.single(TokenKind::LBracket),
+            ']' => self.single(TokenKind::RBracket),
+            '=' => self.single(TokenKind::Equals),
+            ';' => self.single(TokenKind::Semicolon),
+            ',' => self.single(TokenKind::Comma),
+            ':' => self.single(TokenKind::Colon),
+            '+' => self.single(TokenKind::Plus),
+            '-' if self.peek_second() == Some('>') => {
+                self.bump();
+                self.bump();
+                TokenKind::DirectedEdge
+            }
+            '-' if self.peek_second() == Some('-') => {
+                self.bump();
+                self.bump();
+                TokenKind::UndirectedEdge
+            }
+            '-' | '.' | '0'..='9' => self.numeral()?,
+            '"' => {
+                self.bump();
+                loop {
+                    match self.bump() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            self.bump();
+                        }
+                        Some(_) => {}
+                        None => return Err(ParseError::new("unterminated string", line, column)),
+                    }
+                }
+                TokenKind::QuotedString
+            }
+            '<' => {
+                let mut depth = 0;
+                loop {
+                    match self.bump() {
+                        Some('<') => depth += 1,
+                        Some('>') => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        Some(_) => {}
+                        None => {
+                            return Err(ParseError::new("unterminated HTML string", line, column))
+                        }
+                    }
+                }
+                TokenKind::Html
+            }
+            c if is_identifier_start(c) => {
+                self.bump_while(is_identifier_continue);
+                TokenKind::Identifier
+            }
+            c => return Err(self.error(&format!("unexpected character '{}'", c))),
+        };
+        Ok(Some(Token {
+            kind,
+            text: &self.input[start..self.offset],
+            line,
+            column,
+        }))
+    }
+
+    fn single(&mut self, kind: TokenKind) -> TokenKind {
+        self.bump();
+        kind
+    }
+
+    // numeral : [-]?(.[0-9]⁺ | [0-9]⁺(.[0-9]*)? )
+    fn numeral(&mut self) -> Result<TokenKind, ParseError> {
+        if self.peek() == Some('-') {
+            self.bump();
+        }
+        let mut digits = 0;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() {
+                digits += 1;
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        if self.peek() == Some('.') {
+            self.bump();
+            while let Some(c) = self.peek() {
+                if c.is_ascii_digit() {
+                    digits += 1;
+                    self.bump();
+                } else {
+                    break;
+                }
+            }
+        }
+        if digits == 0 {
+            return Err(self.error("malformed numeral"));
+        }
+        if self.peek().is_some_and(is_identifier_start) {
+            return Err(self.error("identifier cannot start with a digit"));
+        }
+        Ok(TokenKind::Numeral)
+    }
+}
+
+pub(crate) fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+pub(crate) fn is_identifier_continue(c: char) -> bool {
+    is_identifier_start(c) || c.is_ascii_digit()
+}
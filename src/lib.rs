@@ -1,9 +1,40 @@
 mod ast;
 pub mod attributes;
 mod builder;
+mod cst;
+mod escape;
+mod gexf;
+mod graph_json;
+mod graphml;
+mod json;
+pub mod layout;
+mod lexer;
+mod mermaid;
+mod model;
+mod parser;
 mod printer;
+mod render;
+mod strict;
+mod svg;
+mod text;
+mod xml;
 
-pub use ast::{Graph, Id};
+pub use ast::{Compass, Graph, Id, NodeId, ToId, ToNodeId};
 pub use builder::{directed, undirected};
-pub use builder::{DirectedGraph, DotLayout, GraphBuilder, UnspecifiedOutput};
+pub use builder::{
+    AttributeBuilder, BitmapOutput, CircoLayout, ClusterContext, DirectedGraph, DotLayout,
+    EdgeContext, FdpLayout, GexfOutput, GraphBuilder, GraphContext, ImageMapOutput, JsonOutput,
+    LinkOutput, NeatoLayout, NodeContext, OsageLayout, PatchworkLayout, PdfOutput, PlainOutput,
+    PostscriptOutput, SVGOutput, SfdpLayout, StatementBuilder, SubgraphContext, TimedContext,
+    TwopiLayout, UndirectedGraph, UnspecifiedLayout, UnspecifiedOutput, XdotOutput,
+};
+pub use cst::SyntaxTree;
+pub use gexf::print_gexf;
+pub use graph_json::{parse_json, print_json, DrawOp, DrawOps, JsonGraph};
+pub use graphml::{parse_graphml, print_graphml};
+pub use mermaid::{print_mermaid, Element, MermaidWarning};
+pub use parser::{parse_graph, ParseError};
 pub use printer::print_graph;
+pub use render::{render, RenderError, Renderer};
+pub use svg::{print_svg, to_svg};
+pub use text::{print_text, to_text, Charset};
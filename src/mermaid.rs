@@ -0,0 +1,644 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::io;
+
+use crate::ast::{Attribute, Graph};
+use crate::attributes::{Color, ColorList, Dir, RankDir, Shape, Style};
+use crate::model::{find, number, record_text, styles, Model};
+
+// docs
+// * https://mermaid.js.org/syntax/flowchart.html
+//
+// Writes a graph as a Mermaid flowchart. Nodes, edges and clusters are taken
+// from the graph the way graphviz resolves it, so node and edge defaults
+// apply. Plain subgraphs have no Mermaid counterpart and only their nodes are
+// kept. Whatever Mermaid cannot show is left out or drawn the closest way it
+// can, and reported as a warning.
+
+// The part of a graph a warning is about, by DOT ID
+#[derive(Debug, Clone, PartialEq)]
+pub enum Element {
+    Graph,
+    Node(String),
+    Edge(String, String),
+    Cluster(String),
+    Subgraph(Option<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MermaidWarning {
+    // A node shape without a Mermaid shape, drawn as a rectangle
+    Shape {
+        node: String,
+        shape: String,
+    },
+    // An arrowhead without a Mermaid arrowhead, drawn as a normal one
+    Arrow {
+        tail: String,
+        head: String,
+        arrow: String,
+    },
+    // A port or compass point an edge ends at, the edge ends at the node
+    Port {
+        element: Element,
+        port: String,
+    },
+    // An attribute that was left out
+    Attribute {
+        element: Element,
+        name: String,
+        value: String,
+    },
+}
+
+impl Display for Element {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Element::Graph => f.write_str("graph"),
+            Element::Node(id) => write!(f, "node {}", id),
+            Element::Edge(tail, head) => write!(f, "edge {} -> {}", tail, head),
+            Element::Cluster(id) => write!(f, "cluster {}", id),
+            Element::Subgraph(Some(id)) => write!(f, "subgraph {}", id),
+            Element::Subgraph(None) => f.write_str("anonymous subgraph"),
+        }
+    }
+}
+
+impl Display for MermaidWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MermaidWarning::Shape { node, shape } => {
+                write!(f, "node {}: shape {} is drawn as a rectangle", node, shape)
+            }
+            MermaidWarning::Arrow { tail, head, arrow } => write!(
+                f,
+                "edge {} -> {}: arrow {} is drawn as a normal arrowhead",
+                tail, head, arrow
+            ),
+            MermaidWarning::Port { element, port } => {
+                write!(f, "{}: port {} is left out", element, port)
+            }
+            MermaidWarning::Attribute {
+                element,
+                name,
+                value,
+            } => write!(f, "{}: {}={} is left out", element, name, value),
+        }
+    }
+}
+
+// Attributes written to Mermaid for each kind of element. The layout engine
+// is set by the builder and says nothing about the drawing.
+const GRAPH_ATTRIBUTES: [&str; 3] = ["label", "layout", "rankdir"];
+const NODE_ATTRIBUTES: [&str; 7] = [
+    "color",
+    "fillcolor",
+    "fontcolor",
+    "label",
+    "penwidth",
+    "shape",
+    "style",
+];
+const EDGE_ATTRIBUTES: [&str; 8] = [
+    "arrowhead",
+    "arrowtail",
+    "color",
+    "dir",
+    "fontcolor",
+    "label",
+    "penwidth",
+    "style",
+];
+const CLUSTER_ATTRIBUTES: [&str; 8] = [
+    "bgcolor",
+    "color",
+    "fillcolor",
+    "fontcolor",
+    "label",
+    "pencolor",
+    "penwidth",
+    "style",
+];
+
+// Returns what could not be written as Mermaid
+pub fn print_mermaid<W: io::Write>(
+    writer: &mut W,
+    graph: &Graph,
+) -> io::Result<Vec<MermaidWarning>> {
+    let model = Model::new(graph);
+    let mut ids = HashSet::new();
+    let nodes: Vec<String> = model
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| mermaid_id(node.id.get(), &format!("n{}", i), &mut ids))
+        .collect();
+    let subgraphs: Vec<String> = model
+        .subgraphs
+        .iter()
+        .enumerate()
+        .map(|(i, subgraph)| {
+            let id = subgraph.id.as_ref().map_or("", |id| id.get());
+            mermaid_id(id, &format!("s{}", i), &mut ids)
+        })
+        .collect();
+    let mut printer = MermaidPrinter {
+        writer,
+        model: &model,
+        nodes,
+        subgraphs,
+        styles: Vec::new(),
+        warnings: Vec::new(),
+    };
+    printer.graph()?;
+    Ok(printer.warnings)
+}
+
+// A DOT ID kept as the Mermaid ID when it is one, e.g. `end` is not
+fn mermaid_id(id: &str, fallback: &str, ids: &mut HashSet<String>) -> String {
+    let valid = id.starts_with(|c: char| c.is_ascii_alphabetic())
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !["end", "graph", "flowchart", "subgraph", "style", "class"]
+            .iter()
+            .any(|k| id.eq_ignore_ascii_case(k));
+    let mut id = if valid {
+        id.to_string()
+    } else {
+        fallback.to_string()
+    };
+    while ids.contains(&id) {
+        id.push('_');
+    }
+    ids.insert(id.clone());
+    id
+}
+
+// Text between double quotes, where Mermaid reads entities and <br> tags
+fn text(s: &str) -> String {
+    let escaped = s
+        .replace('&', "#amp;")
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;");
+    format!("\"{}\"", escaped.replace('\n', "<br>"))
+}
+
+// A CSS color, none for colors without values here
+fn css(color: &Color) -> Option<String> {
+    match color.channels()? {
+        [_, _, _, 0] => Some("transparent".to_string()),
+        [r, g, b, 255] => Some(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+        [r, g, b, a] => Some(format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)),
+    }
+}
+
+struct MermaidPrinter<'a, W: io::Write> {
+    writer: &'a mut W,
+    model: &'a Model,
+    // Mermaid IDs of nodes and subgraphs
+    nodes: Vec<String>,
+    subgraphs: Vec<String>,
+    // style and linkStyle lines written after the elements
+    styles: Vec<String>,
+    warnings: Vec<MermaidWarning>,
+}
+
+impl<'a, W: io::Write> MermaidPrinter<'a, W> {
+    fn graph(&mut self) -> io::Result<()> {
+        let model = self.model;
+        if let Some(label) = find(&model.attributes, "label") {
+            writeln!(self.writer, "---")?;
+            writeln!(self.writer, "title: {}", label.value().replace('\n', " "))?;
+            writeln!(self.writer, "---")?;
+        }
+        let direction = match find(&model.attributes, "rankdir") {
+            Some(Attribute::RankDir(RankDir::LeftRight)) => "LR",
+            Some(Attribute::RankDir(RankDir::BottomTop)) => "BT",
+            Some(Attribute::RankDir(RankDir::RightLeft)) => "RL",
+            _ => "TD",
+        };
+        let keyword = if model.directed { "flowchart" } else { "graph" };
+        writeln!(self.writer, "{} {}", keyword, direction)?;
+        self.unsupported(Element::Graph, &model.attributes, &GRAPH_ATTRIBUTES);
+
+        for (i, subgraph) in model.subgraphs.iter().enumerate() {
+            if !subgraph.is_cluster() {
+                let id = subgraph.id.as_ref().map(|id| id.get().to_string());
+                self.unsupported(Element::Subgraph(id), &subgraph.attributes, &[]);
+            } else if model.parent_cluster(i).is_none() {
+                self.cluster(i, 1)?;
+            }
+        }
+        for node in 0..model.nodes.len() {
+            if model.cluster_of(node).is_none() {
+                self.node(node, 1)?;
+            }
+        }
+        for edge in 0..model.edges.len() {
+            self.edge(edge)?;
+        }
+        for line in std::mem::take(&mut self.styles) {
+            writeln!(self.writer, "    {}", line)?;
+        }
+        Ok(())
+    }
+
+    fn indent(&mut self, depth: usize) -> io::Result<()> {
+        write!(self.writer, "{}", "    ".repeat(depth))
+    }
+
+    fn cluster(&mut self, subgraph: usize, depth: usize) -> io::Result<()> {
+        let model = self.model;
+        let id = self.subgraphs[subgraph].clone();
+        let attributes = &model.subgraphs[subgraph].attributes;
+        let dot_id = model.subgraphs[subgraph].id.as_ref().unwrap().get();
+        let element = Element::Cluster(dot_id.to_string());
+        self.indent(depth)?;
+        match model.subgraph_label(subgraph) {
+            Some(label) => writeln!(self.writer, "subgraph {} [{}]", id, text(&label))?,
+            None => writeln!(self.writer, "subgraph {} [\" \"]", id)?,
+        }
+        for inner in model.clusters() {
+            if model.parent_cluster(inner) == Some(subgraph) {
+                self.cluster(inner, depth + 1)?;
+            }
+        }
+        for node in 0..model.nodes.len() {
+            if model.cluster_of(node) == Some(subgraph) {
+                self.node(node, depth + 1)?;
+            }
+        }
+        self.indent(depth)?;
+        writeln!(self.writer, "end")?;
+
+        let styles = styles(attributes);
+        let fill = if styles.contains(&Style::Filled) {
+            ["fillcolor", "bgcolor", "color"]
+                .iter()
+                .find_map(|name| self.color(attributes, name))
+        } else {
+            self.color(attributes, "bgcolor")
+        };
+        let stroke = self
+            .color(attributes, "pencolor")
+            .or_else(|| self.color(attributes, "color"));
+        self.style(&id, attributes, &styles, fill, stroke);
+        self.unsupported(element, attributes, &CLUSTER_ATTRIBUTES);
+        Ok(())
+    }
+
+    fn node(&mut self, node: usize, depth: usize) -> io::Result<()> {
+        let model = self.model;
+        let id = self.nodes[node].clone();
+        let attributes = &model.nodes[node].attributes;
+        let dot_id = model.nodes[node].id.get();
+        let element = Element::Node(dot_id.to_string());
+        let shape = match find(attributes, "shape") {
+            Some(Attribute::Shape(shape)) => shape.clone(),
+            _ => Shape::Ellipse,
+        };
+        let styles = styles(attributes);
+        let label = match shape {
+            Shape::Record | Shape::MRecord => record_text(&model.node_label(node)),
+            _ => model.node_label(node),
+        };
+        let label = text(&label);
+        let rounded = styles.contains(&Style::Rounded);
+        let shaped = match shape {
+            Shape::Box | Shape::Rect | Shape::Rectangle | Shape::Square | Shape::Record
+                if rounded =>
+            {
+                format!("({})", label)
+            }
+            Shape::Box | Shape::Rect | Shape::Rectangle | Shape::Square | Shape::Record => {
+                format!("[{}]", label)
+            }
+            Shape::MRecord => format!("({})", label),
+            Shape::Ellipse | Shape::Oval => format!("([{}])", label),
+            Shape::Circle => format!("(({}))", label),
+            Shape::DoubleCircle => format!("((({})))", label),
+            Shape::Diamond => format!("{{{}}}", label),
+            Shape::Hexagon => format!("{{{{{}}}}}", label),
+            Shape::Parallelogram => format!("[/{}/]", label),
+            Shape::Trapezium => format!("[/{}\\]", label),
+            Shape::InvTrapezium => format!("[\\{}/]", label),
+            Shape::Cylinder => format!("[({})]", label),
+            Shape::Component => format!("[[{}]]", label),
+            Shape::RArrow => format!(">{}]", label),
+            _ => {
+                self.warnings.push(MermaidWarning::Shape {
+                    node: dot_id.to_string(),
+                    shape: shape.to_string(),
+                });
+                format!("[{}]", label)
+            }
+        };
+        self.indent(depth)?;
+        writeln!(self.writer, "{}{}", id, shaped)?;
+
+        let fill = if styles.contains(&Style::Filled) {
+            self.color(attributes, "fillcolor")
+                .or_else(|| self.color(attributes, "color"))
+                .or_else(|| Some("lightgrey".to_string()))
+        } else {
+            None
+        };
+        let stroke = self.color(attributes, "color");
+        self.style(&id, attributes, &styles, fill, stroke);
+        self.unsupported(element, attributes, &NODE_ATTRIBUTES);
+        Ok(())
+    }
+
+    fn edge(&mut self, edge: usize) -> io::Result<()> {
+        let model = self.model;
+        let model_edge = &model.edges[edge];
+        let attributes = &model_edge.attributes;
+        let (tail_id, head_id) = (
+            model_edge.from.id.get().to_string(),
+            model_edge.to.id.get().to_string(),
+        );
+        let element = Element::Edge(tail_id.clone(), head_id.clone());
+        for end in [&model_edge.from, &model_edge.to] {
+            let port = match (&end.port, &end.compass) {
+                (Some(port), _) => port.get().to_string(),
+                (None, Some(compass)) => compass.to_string(),
+                (None, None) => continue,
+            };
+            self.warnings.push(MermaidWarning::Port {
+                element: element.clone(),
+                port,
+            });
+        }
+
+        let dir = match find(attributes, "dir") {
+            Some(Attribute::Dir(dir)) => dir.clone(),
+            _ if model.directed => Dir::Forward,
+            _ => Dir::None,
+        };
+        let arrow = |name: &str, warnings: &mut Vec<MermaidWarning>| match find(attributes, name) {
+            Some(attribute) => match attribute.value().as_str() {
+                "normal" => ">",
+                "none" => "",
+                "dot" | "odot" => "o",
+                arrow => {
+                    warnings.push(MermaidWarning::Arrow {
+                        tail: tail_id.clone(),
+                        head: head_id.clone(),
+                        arrow: arrow.to_string(),
+                    });
+                    ">"
+                }
+            },
+            None => ">",
+        };
+        // A single arrowhead at the tail reads as an edge the other way
+        let (tail, head, start, end) = match dir {
+            Dir::Forward => (
+                model_edge.tail,
+                model_edge.head,
+                "",
+                arrow("arrowhead", &mut self.warnings),
+            ),
+            Dir::Back => (
+                model_edge.head,
+                model_edge.tail,
+                "",
+                arrow("arrowtail", &mut self.warnings),
+            ),
+            Dir::Both => {
+                let start = match arrow("arrowtail", &mut self.warnings) {
+                    ">" => "<",
+                    start => start,
+                };
+                (
+                    model_edge.tail,
+                    model_edge.head,
+                    start,
+                    arrow("arrowhead", &mut self.warnings),
+                )
+            }
+            Dir::None => (model_edge.tail, model_edge.head, "", ""),
+        };
+
+        let styles = styles(attributes);
+        let link = if styles.contains(&Style::Invisible) {
+            "~~~".to_string()
+        } else if styles.contains(&Style::Dashed) || styles.contains(&Style::Dotted) {
+            format!("{}-.-{}", start, end)
+        } else if styles.contains(&Style::Bold) {
+            format!("{}=={}", start, if end.is_empty() { "=" } else { end })
+        } else {
+            format!("{}--{}", start, if end.is_empty() { "-" } else { end })
+        };
+        let label = match model.edge_label(edge) {
+            Some(label) if !styles.contains(&Style::Invisible) => format!("|{}|", text(&label)),
+            _ => String::new(),
+        };
+        writeln!(
+            self.writer,
+            "    {} {}{} {}",
+            self.nodes[tail], link, label, self.nodes[head]
+        )?;
+
+        let mut css = Vec::new();
+        if let Some(color) = self.color(attributes, "color") {
+            css.push(format!("stroke:{}", color));
+        }
+        if let Some(width) = number(attributes, "penwidth") {
+            css.push(format!("stroke-width:{}px", width));
+        }
+        if let Some(color) = self.color(attributes, "fontcolor") {
+            css.push(format!("color:{}", color));
+        }
+        if !css.is_empty() {
+            self.styles
+                .push(format!("linkStyle {} {}", edge, css.join(",")));
+        }
+        self.unsupported(element, attributes, &EDGE_ATTRIBUTES);
+        Ok(())
+    }
+
+    // A style line for a node or cluster
+    fn style(
+        &mut self,
+        id: &str,
+        attributes: &[Attribute],
+        styles: &[Style],
+        fill: Option<String>,
+        stroke: Option<String>,
+    ) {
+        let mut css = Vec::new();
+        if let Some(fill) = fill {
+            css.push(format!("fill:{}", fill));
+        }
+        if let Some(stroke) = stroke {
+            css.push(format!("stroke:{}", stroke));
+        }
+        let width = match number(attributes, "penwidth") {
+            Some(width) => Some(width),
+            None if styles.contains(&Style::Bold) => Some(2.0),
+            None => None,
+        };
+        if let Some(width) = width {
+            css.push(format!("stroke-width:{}px", width));
+        }
+        if styles.contains(&Style::Dashed) {
+            css.push("stroke-dasharray:5 2".to_string());
+        } else if styles.contains(&Style::Dotted) {
+            css.push("stroke-dasharray:1 5".to_string());
+        }
+        if let Some(color) = self.color(attributes, "fontcolor") {
+            css.push(format!("color:{}", color));
+        }
+        if !css.is_empty() {
+            self.styles.push(format!("style {} {}", id, css.join(",")));
+        }
+    }
+
+    // The first color of a color attribute as CSS
+    fn color(&self, attributes: &[Attribute], name: &str) -> Option<String> {
+        let colors = ColorList::parse(&find(attributes, name)?.value())?;
+        css(colors.first())
+    }
+
+    // Warns about attributes outside `supported`, and about styles and
+    // colors that are not drawn
+    fn unsupported(&mut self, element: Element, attributes: &[Attribute], supported: &[&str]) {
+        let mut seen = HashSet::new();
+        for attribute in attributes.iter().rev() {
+            let name = attribute.name();
+            if !seen.insert(name.to_string()) {
+                continue;
+            }
+            let value = attribute.value();
+            let dropped = if !supported.contains(&name) {
+                true
+            } else if name == "style" {
+                let edge = matches!(element, Element::Edge(..));
+                value
+                    .split(',')
+                    .any(|style| match Style::parse(style.trim()) {
+                        Some(Style::Bold) | Some(Style::Dashed) | Some(Style::Dotted)
+                        | Some(Style::Filled) | Some(Style::Rounded) | Some(Style::Solid) => false,
+                        Some(Style::Invisible) => !edge,
+                        _ => true,
+                    })
+            } else if name.ends_with("color") {
+                ColorList::parse(&value).is_none_or(|colors| css(colors.first()).is_none())
+            } else {
+                false
+            };
+            if dropped {
+                self.warnings.push(MermaidWarning::Attribute {
+                    element: element.clone(),
+                    name: name.to_string(),
+                    value,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_graph;
+
+    fn mermaid(dot_source: &str) -> (String, Vec<MermaidWarning>) {
+        let mut writer = Vec::new();
+        let warnings = print_mermaid(&mut writer, &parse_graph(dot_source).unwrap()).unwrap();
+        (String::from_utf8(writer).unwrap(), warnings)
+    }
+
+    #[test]
+    fn flowchart() {
+        let (s, warnings) = mermaid(
+            r##"digraph {
+                rankdir=LR;
+                node [shape=box];
+                subgraph cluster_build {
+                    label="Build";
+                    compile [label="compile \"all\""];
+                    subgraph cluster_inner { link [shape=cylinder] }
+                }
+                compile -> link [label=then, style=dashed];
+                link -> end [style=bold, color=red];
+                end [shape=diamond, style=filled, fillcolor="#00ff00"];
+                end -> compile [dir=both, arrowtail=odot];
+                compile -> compile [dir=back];
+            }"##,
+        );
+        assert_eq!(
+            s,
+            r#"flowchart LR
+    subgraph cluster_build ["Build"]
+        subgraph cluster_inner [" "]
+            link[("link")]
+        end
+        compile["compile #quot;all#quot;"]
+    end
+    n2{"end"}
+    compile -.->|"then"| link
+    link ==> n2
+    n2 o--> compile
+    compile --> compile
+    style n2 fill:#00ff00
+    linkStyle 1 stroke:#ff0000
+"#
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn warnings() {
+        let (s, warnings) = mermaid(
+            r#"graph G {
+                label="Title"; splines=ortho;
+                a [shape=star, style=diagonals];
+                { rank=same; b }
+                a:p -- b [arrowhead=crow, dir=forward];
+            }"#,
+        );
+        assert_eq!(
+            s,
+            "---\ntitle: Title\n---\ngraph TD\n    a[\"a\"]\n    b([\"b\"])\n    a --> b\n"
+        );
+        assert_eq!(
+            warnings,
+            vec![
+                MermaidWarning::Attribute {
+                    element: Element::Graph,
+                    name: "splines".to_string(),
+                    value: "ortho".to_string(),
+                },
+                MermaidWarning::Attribute {
+                    element: Element::Subgraph(None),
+                    name: "rank".to_string(),
+                    value: "same".to_string(),
+                },
+                MermaidWarning::Shape {
+                    node: "a".to_string(),
+                    shape: "star".to_string(),
+                },
+                MermaidWarning::Attribute {
+                    element: Element::Node("a".to_string()),
+                    name: "style".to_string(),
+                    value: "diagonals".to_string(),
+                },
+                MermaidWarning::Port {
+                    element: Element::Edge("a".to_string(), "b".to_string()),
+                    port: "p".to_string(),
+                },
+                MermaidWarning::Arrow {
+                    tail: "a".to_string(),
+                    head: "b".to_string(),
+                    arrow: "crow".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            warnings[2].to_string(),
+            "node a: shape star is drawn as a rectangle"
+        );
+    }
+}
@@ -0,0 +1,487 @@
+use std::collections::HashMap;
+
+use crate::ast::{edge_pairs, Attribute, AttributeScope, EdgeTarget, Graph, Id, NodeId, Statement};
+use crate::attributes::{Label, Rank, Style};
+
+// docs
+// * https://graphviz.org/doc/info/lang.html#subgraphs-and-clusters
+//
+// The statements of a graph resolved the way graphviz reads them: every node
+// once, in the order it was first mentioned, and every edge with the node and
+// edge defaults that were in scope where it was written. Layouts and other
+// output formats work on this instead of the statements.
+
+pub(crate) struct Model {
+    pub(crate) directed: bool,
+    pub(crate) id: Option<Id>,
+    // Graph attributes of the root graph
+    pub(crate) attributes: Vec<Attribute>,
+    pub(crate) nodes: Vec<ModelNode>,
+    pub(crate) edges: Vec<ModelEdge>,
+    pub(crate) subgraphs: Vec<ModelSubgraph>,
+}
+
+pub(crate) struct ModelNode {
+    pub(crate) id: Id,
+    pub(crate) attributes: Vec<Attribute>,
+    // The innermost cluster the node was first mentioned in
+    pub(crate) cluster: Option<usize>,
+}
+
+pub(crate) struct ModelEdge {
+    pub(crate) from: NodeId,
+    pub(crate) to: NodeId,
+    // Indices into `nodes`
+    pub(crate) tail: usize,
+    pub(crate) head: usize,
+    pub(crate) attributes: Vec<Attribute>,
+    // The innermost subgraph the edge was written in
+    pub(crate) subgraph: Option<usize>,
+    // For the edges of a chain or of subgraph endpoints, the chain's number
+    // and which of its arrows made the edge, e.g. 1 for `b -> c` in
+    // `a -> b -> c`
+    pub(crate) chain: Option<(usize, usize)>,
+}
+
+pub(crate) struct ModelSubgraph {
+    pub(crate) id: Option<Id>,
+    // The enclosing subgraph, `None` for the root graph
+    pub(crate) parent: Option<usize>,
+    pub(crate) attributes: Vec<Attribute>,
+    // Every node mentioned in the subgraph or the subgraphs inside it
+    pub(crate) nodes: Vec<usize>,
+    // Written as an edge endpoint like `{a b}` in `{a b} -> c`, or inside one
+    pub(crate) endpoint: bool,
+}
+
+impl ModelSubgraph {
+    pub(crate) fn is_cluster(&self) -> bool {
+        self.id
+            .as_ref()
+            .is_some_and(|id| id.get().starts_with("cluster"))
+    }
+
+    pub(crate) fn rank(&self) -> Option<&Rank> {
+        self.attributes.iter().rev().find_map(|a| match a {
+            Attribute::Rank(rank) => Some(rank),
+            _ => None,
+        })
+    }
+}
+
+impl Model {
+    pub(crate) fn new(graph: &Graph) -> Model {
+        let mut model = Model {
+            directed: matches!(graph.gtype, crate::ast::GraphType::Directed),
+            id: graph.id.clone(),
+            attributes: Vec::new(),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            subgraphs: Vec::new(),
+        };
+        let mut builder = ModelBuilder {
+            lookup: HashMap::new(),
+            chains: 0,
+        };
+        builder.statements(&mut model, &graph.statements, &mut Scope::default());
+        model
+    }
+
+    // The innermost cluster a node is drawn in
+    pub(crate) fn cluster_of(&self, node: usize) -> Option<usize> {
+        self.nodes[node].cluster
+    }
+
+    // The cluster a subgraph is drawn inside of, skipping plain subgraphs
+    pub(crate) fn parent_cluster(&self, subgraph: usize) -> Option<usize> {
+        let mut parent = self.subgraphs[subgraph].parent;
+        while let Some(index) = parent {
+            if self.subgraphs[index].is_cluster() {
+                return Some(index);
+            }
+            parent = self.subgraphs[index].parent;
+        }
+        None
+    }
+
+    pub(crate) fn clusters(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.subgraphs.len()).filter(move |&i| self.subgraphs[i].is_cluster())
+    }
+
+    fn graph_name(&self) -> &str {
+        self.id.as_ref().map_or("", Id::get)
+    }
+
+    // The text a node shows, `\N` by default
+    pub(crate) fn node_label(&self, node: usize) -> String {
+        let id = self.nodes[node].id.get();
+        match find(&self.nodes[node].attributes, "label") {
+            Some(Attribute::Label(label)) => self.label_text(label, id, "", ""),
+            _ => self.label_text(&Label::Text("\\N".to_string()), id, "", ""),
+        }
+    }
+
+    pub(crate) fn edge_label(&self, edge: usize) -> Option<String> {
+        let edge = &self.edges[edge];
+        match find(&edge.attributes, "label") {
+            Some(Attribute::Label(label)) => Some(self.label_text(
+                label,
+                "",
+                self.nodes[edge.tail].id.get(),
+                self.nodes[edge.head].id.get(),
+            )),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn subgraph_label(&self, subgraph: usize) -> Option<String> {
+        match find(&self.subgraphs[subgraph].attributes, "label") {
+            Some(Attribute::Label(label)) => Some(self.label_text(label, "", "", "")),
+            _ => None,
+        }
+    }
+
+    fn label_text(&self, label: &Label, node: &str, tail: &str, head: &str) -> String {
+        match label {
+            Label::Text(text) => {
+                // Names are substituted first and escapes in them are then
+                // processed like the rest of the label, as graphviz does
+                let arrow = if self.directed { "->" } else { "--" };
+                let mut substituted = String::new();
+                let mut chars = text.chars();
+                while let Some(c) = chars.next() {
+                    if c != '\\' {
+                        substituted.push(c);
+                        continue;
+                    }
+                    match chars.next() {
+                        Some('N') => substituted.push_str(node),
+                        Some('G') => substituted.push_str(self.graph_name()),
+                        Some('E') => {
+                            substituted.push_str(tail);
+                            substituted.push_str(arrow);
+                            substituted.push_str(head);
+                        }
+                        Some('T') => substituted.push_str(tail),
+                        Some('H') => substituted.push_str(head),
+                        Some(c) => {
+                            substituted.push('\\');
+                            substituted.push(c);
+                        }
+                        None => substituted.push('\\'),
+                    }
+                }
+                let mut resolved = String::new();
+                let mut chars = substituted.chars();
+                while let Some(c) = chars.next() {
+                    if c != '\\' {
+                        resolved.push(c);
+                        continue;
+                    }
+                    match chars.next() {
+                        Some('n') | Some('l') | Some('r') => resolved.push('\n'),
+                        Some(c) => resolved.push(c),
+                        None => resolved.push('\\'),
+                    }
+                }
+                // A trailing line break only justifies the last line
+                if resolved.ends_with('\n') {
+                    resolved.pop();
+                }
+                resolved
+            }
+            Label::Html(html) => html_text(html.get()),
+            Label::Record(record) => record_text(record.get()),
+        }
+    }
+}
+
+// The last value given for an attribute
+pub(crate) fn find<'a>(attributes: &'a [Attribute], name: &str) -> Option<&'a Attribute> {
+    attributes.iter().rev().find(|a| a.name() == name)
+}
+
+// A numeric attribute, typed or not
+pub(crate) fn number(attributes: &[Attribute], name: &str) -> Option<f64> {
+    find(attributes, name).and_then(|a| a.value().parse().ok())
+}
+
+// The styles of a comma separated style list that this crate knows
+pub(crate) fn styles(attributes: &[Attribute]) -> Vec<Style> {
+    find(attributes, "style").map_or(Vec::new(), |style| {
+        style
+            .value()
+            .split(',')
+            .filter_map(|s| Style::parse(s.trim()))
+            .collect()
+    })
+}
+
+// Sets attributes the way a later statement does, replacing earlier values
+fn merge(attributes: &mut Vec<Attribute>, new: &[Attribute]) {
+    for attribute in new {
+        attributes.retain(|a| a.name() != attribute.name());
+        attributes.push(attribute.clone());
+    }
+}
+
+// Fields read left to right, e.g. `a | b | c` for `{a|<p> b}|c`
+pub(crate) fn record_text(fields: &str) -> String {
+    let mut text = String::new();
+    let mut chars = fields.chars();
+    let mut in_port = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') | Some('l') | Some('r') => text.push(' '),
+                Some(c) if !in_port => text.push(c),
+                _ => {}
+            },
+            '<' => in_port = true,
+            '>' => in_port = false,
+            '{' | '}' => {}
+            '|' => text.push_str(" | "),
+            c if !in_port => text.push(c),
+            _ => {}
+        }
+    }
+    let words: Vec<&str> = text.split(' ').filter(|w| !w.is_empty()).collect();
+    words.join(" ")
+}
+
+// The text of HTML-like markup, with `<br/>` as line breaks
+fn html_text(markup: &str) -> String {
+    let mut text = String::new();
+    let mut rest = markup;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('>')
+            .map_or(rest.len(), |end| start + end + 1);
+        let tag = rest[start..end].to_ascii_lowercase();
+        if tag.starts_with("<br") || tag.starts_with("</tr") {
+            text.push('\n');
+        }
+        rest = &rest[end..];
+    }
+    text.push_str(rest);
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&");
+    text.trim_matches('\n').to_string()
+}
+
+#[derive(Clone, Default)]
+struct Scope {
+    node: Vec<Attribute>,
+    edge: Vec<Attribute>,
+    subgraph: Option<usize>,
+    // The innermost enclosing cluster
+    cluster: Option<usize>,
+}
+
+struct ModelBuilder {
+    lookup: HashMap<Id, usize>,
+    // Chains numbered so far
+    chains: usize,
+}
+
+impl ModelBuilder {
+    fn statements(&mut self, model: &mut Model, statements: &[Statement], scope: &mut Scope) {
+        for statement in statements {
+            match statement {
+                Statement::Attribute(AttributeScope::Graph, attributes) => match scope.subgraph {
+                    Some(subgraph) => merge(&mut model.subgraphs[subgraph].attributes, attributes),
+                    None => merge(&mut model.attributes, attributes),
+                },
+                Statement::Attribute(AttributeScope::Node, attributes) => {
+                    merge(&mut scope.node, attributes)
+                }
+                Statement::Attribute(AttributeScope::Edge, attributes) => {
+                    merge(&mut scope.edge, attributes)
+                }
+                Statement::Node { id, attributes } => {
+                    let node = self.node(model, id, scope);
+                    merge(&mut model.nodes[node].attributes, attributes);
+                }
+                Statement::Edge {
+                    from,
+                    to,
+                    attributes,
+                } => self.edge(model, from, to, attributes, scope, None),
+                Statement::EdgeChain {
+                    targets,
+                    attributes,
+                } => {
+                    for target in targets {
+                        match target {
+                            EdgeTarget::Node(node) => {
+                                self.node(model, &node.id, scope);
+                            }
+                            EdgeTarget::Subgraph { id, statements } => {
+                                self.subgraph(model, id, statements, scope, true)
+                            }
+                        }
+                    }
+                    // A lone `a -> b` is an edge like any other
+                    let chain = targets.len() > 2
+                        || targets
+                            .iter()
+                            .any(|t| matches!(t, EdgeTarget::Subgraph { .. }));
+                    let number = self.chains;
+                    if chain {
+                        self.chains += 1;
+                    }
+                    for link in 0..targets.len().saturating_sub(1) {
+                        for (from, to) in edge_pairs(&targets[link..link + 2]) {
+                            let chain = Some((number, link)).filter(|_| chain);
+                            self.edge(model, &from, &to, attributes, scope, chain);
+                        }
+                    }
+                }
+                Statement::Subgraph { id, statements } => {
+                    self.subgraph(model, id, statements, scope, false)
+                }
+            }
+        }
+    }
+
+    fn subgraph(
+        &mut self,
+        model: &mut Model,
+        id: &Option<Id>,
+        statements: &[Statement],
+        scope: &Scope,
+        endpoint: bool,
+    ) {
+        let index = model.subgraphs.len();
+        let endpoint = endpoint || scope.subgraph.is_some_and(|s| model.subgraphs[s].endpoint);
+        model.subgraphs.push(ModelSubgraph {
+            id: id.clone(),
+            parent: scope.subgraph,
+            attributes: Vec::new(),
+            nodes: Vec::new(),
+            endpoint,
+        });
+        let mut inner = scope.clone();
+        inner.subgraph = Some(index);
+        if model.subgraphs[index].is_cluster() {
+            inner.cluster = Some(index);
+        }
+        self.statements(model, statements, &mut inner);
+    }
+
+    fn node(&mut self, model: &mut Model, id: &Id, scope: &Scope) -> usize {
+        let index = match self.lookup.get(id) {
+            Some(&index) => index,
+            None => {
+                let index = model.nodes.len();
+                self.lookup.insert(id.clone(), index);
+                model.nodes.push(ModelNode {
+                    id: id.clone(),
+                    attributes: scope.node.clone(),
+                    cluster: None,
+                });
+                index
+            }
+        };
+        if model.nodes[index].cluster.is_none() {
+            model.nodes[index].cluster = scope.cluster;
+        }
+        let mut subgraph = scope.subgraph;
+        while let Some(s) = subgraph {
+            if !model.subgraphs[s].nodes.contains(&index) {
+                model.subgraphs[s].nodes.push(index);
+            }
+            subgraph = model.subgraphs[s].parent;
+        }
+        index
+    }
+
+    fn edge(
+        &mut self,
+        model: &mut Model,
+        from: &NodeId,
+        to: &NodeId,
+        attributes: &[Attribute],
+        scope: &Scope,
+        chain: Option<(usize, usize)>,
+    ) {
+        let tail = self.node(model, &from.id, scope);
+        let head = self.node(model, &to.id, scope);
+        let mut merged = scope.edge.clone();
+        merge(&mut merged, attributes);
+        model.edges.push(ModelEdge {
+            from: from.clone(),
+            to: to.clone(),
+            tail,
+            head,
+            attributes: merged,
+            subgraph: scope.subgraph,
+            chain,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_graph;
+
+    #[test]
+    fn defaults_and_clusters() {
+        let graph = parse_graph(
+            r#"digraph G {
+                node [shape=box];
+                a -> b [label="\T to \H"];
+                subgraph cluster_x {
+                    label="X";
+                    node [shape=circle];
+                    b; c [label="\N!\n"];
+                    subgraph { rank=same; d; e }
+                }
+                edge [color=red];
+                c -> { d e };
+            }"#,
+        )
+        .unwrap();
+        let model = Model::new(&graph);
+        let ids: Vec<&str> = model.nodes.iter().map(|n| n.id.get()).collect();
+        assert_eq!(ids, ["a", "b", "c", "d", "e"]);
+        let shape = |n: usize| find(&model.nodes[n].attributes, "shape").map(Attribute::value);
+        assert_eq!(shape(1).as_deref(), Some("box"));
+        assert_eq!(shape(2).as_deref(), Some("circle"));
+
+        assert_eq!(model.edges.len(), 3);
+        assert_eq!(model.edge_label(0).as_deref(), Some("a to b"));
+        assert!(find(&model.edges[0].attributes, "color").is_none());
+        assert!(find(&model.edges[2].attributes, "color").is_some());
+
+        assert_eq!(model.node_label(2), "c!");
+        assert_eq!(model.subgraph_label(0).as_deref(), Some("X"));
+        assert_eq!(model.clusters().collect::<Vec<_>>(), [0]);
+        assert_eq!(model.cluster_of(0), None);
+        assert_eq!(model.cluster_of(3), Some(0));
+        assert!(model.subgraphs[1].rank().is_some());
+        assert_eq!(model.subgraphs[1].nodes, [3, 4]);
+        assert_eq!(model.parent_cluster(1), Some(0));
+    }
+
+    #[test]
+    fn label_text() {
+        assert_eq!(
+            record_text(r"<f0> left|{<f1> mid\ dle|b}|right"),
+            "left | mid dle | b | right"
+        );
+        assert_eq!(html_text("<b>a &amp; b</b><br/>c"), "a & b\nc");
+
+        let graph = parse_graph(r#"digraph { "C:\\dir" -> "a\nb" [label="\E"]; c [label="\\N"] }"#);
+        let model = Model::new(&graph.unwrap());
+        assert_eq!(model.node_label(0), r"C:\dir");
+        assert_eq!(model.node_label(1), "a\nb");
+        assert_eq!(model.node_label(2), r"\N");
+        assert_eq!(model.edge_label(0).as_deref(), Some("C:\\dir->a\nb"));
+    }
+}
@@ -0,0 +1,390 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::ast::{
+    Attribute, AttributeScope, Compass, EdgeTarget, Graph, GraphType, Id, NodeId, Statement,
+};
+use crate::attributes::{
+    Arrow, ColorList, ColorScheme, Dir, FontNames, Html, Intervals, Label, Overlap, PackMode,
+    PageDir, Position, Rank, RankDir, Shape, Size, Start, Style,
+};
+use crate::cst::{NodeKind, SyntaxNode, SyntaxTree};
+use crate::lexer::TokenKind;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    message: String,
+    line: usize,
+    column: usize,
+}
+
+impl ParseError {
+    pub(crate) fn new(message: &str, line: usize, column: usize) -> Self {
+        ParseError {
+            message: message.to_string(),
+            line,
+            column,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+pub fn parse_graph(input: &str) -> Result<Graph, ParseError> {
+    SyntaxTree::parse(input).map(|tree| tree.to_graph())
+}
+
+pub(crate) fn lower_graph(graph: &SyntaxNode) -> Graph {
+    let keyword = |keyword: &str| {
+        graph
+            .tokens()
+            .any(|t| t.kind == TokenKind::Identifier && t.text.eq_ignore_ascii_case(keyword))
+    };
+    Graph {
+        strict: keyword("strict"),
+        gtype: if keyword("digraph") {
+            GraphType::Directed
+        } else {
+            GraphType::Undirected
+        },
+        id: graph
+            .nodes()
+            .find(|node| node.kind == NodeKind::Id)
+            .map(lower_id),
+        statements: lower_statements(graph),
+        engine: None,
+        format: None,
+    }
+}
+
+fn lower_statements(parent: &SyntaxNode) -> Vec<Statement> {
+    let mut statements = Vec::new();
+    for node in parent.nodes() {
+        match node.kind {
+            NodeKind::AttributeStatement => {
+                let keyword = node
+                    .tokens()
+                    .next()
+                    .expect("attribute statements start with a keyword");
+                let scope = if keyword.text.eq_ignore_ascii_case("graph") {
+                    AttributeScope::Graph
+                } else if keyword.text.eq_ignore_ascii_case("node") {
+                    AttributeScope::Node
+                } else {
+                    AttributeScope::Edge
+                };
+                statements.push(Statement::Attribute(scope, lower_attributes(node)));
+            }
+            NodeKind::NodeStatement => {
+                let node_id = node
+                    .nodes()
+                    .next()
+                    .expect("node statements start with a node id");
+                statements.push(Statement::Node {
+                    id: lower_node_id(node_id).id,
+                    attributes: lower_attributes(node),
+                });
+            }
+            NodeKind::EdgeStatement => lower_edge_statement(node, &mut statements),
+            NodeKind::IdAssignment => {
+                let mut ids = node.nodes();
+                let name = lower_id(ids.next().expect("assignments have a name"));
+                let value = lower_id(ids.next().expect("assignments have a value"));
+                statements.push(Statement::Attribute(
+                    AttributeScope::Graph,
+                    vec![attribute(name, value)],
+                ));
+            }
+            NodeKind::Subgraph => {
+                let (id, subgraph) = lower_subgraph(node);
+                statements.push(Statement::Subgraph {
+                    id,
+                    statements: subgraph,
+                });
+            }
+            _ => {}
+        }
+    }
+    statements
+}
+
+fn lower_subgraph(subgraph: &SyntaxNode) -> (Option<Id>, Vec<Statement>) {
+    let id = subgraph
+        .nodes()
+        .find(|node| node.kind == NodeKind::Id)
+        .map(lower_id);
+    (id, lower_statements(subgraph))
+}
+
+// A single `a -> b` becomes an edge, anything longer or with subgraph
+// operands an edge chain.
+fn lower_edge_statement(edge: &SyntaxNode, statements: &mut Vec<Statement>) {
+    let targets: Vec<EdgeTarget> = edge
+        .nodes()
+        .filter_map(|node| match node.kind {
+            NodeKind::NodeId => Some(EdgeTarget::Node(lower_node_id(node))),
+            NodeKind::Subgraph => {
+                let (id, statements) = lower_subgraph(node);
+                Some(EdgeTarget::Subgraph { id, statements })
+            }
+            _ => None,
+        })
+        .collect();
+    let attributes = lower_attributes(edge);
+
+    let statement = match <[EdgeTarget; 2]>::try_from(targets) {
+        Ok([EdgeTarget::Node(from), EdgeTarget::Node(to)]) => Statement::Edge {
+            from,
+            to,
+            attributes,
+        },
+        Ok(pair) => Statement::EdgeChain {
+            targets: pair.into(),
+            attributes,
+        },
+        Err(targets) => Statement::EdgeChain {
+            targets,
+            attributes,
+        },
+    };
+    statements.push(statement);
+}
+
+fn lower_node_id(node_id: &SyntaxNode) -> NodeId {
+    let mut ids = node_id.nodes().map(lower_id);
+    NodeId {
+        id: ids.next().expect("node ids start with an ID"),
+        port: ids.next(),
+        compass: ids.next().and_then(|id| Compass::parse(id.get())),
+    }
+}
+
+fn lower_attributes(statement: &SyntaxNode) -> Vec<Attribute> {
+    statement
+        .nodes()
+        .filter(|node| node.kind == NodeKind::AttributeList)
+        .flat_map(|list| list.nodes())
+        .map(|node| {
+            let mut ids = node.nodes();
+            let name = lower_id(ids.next().expect("attributes have a name"));
+            let value = lower_id(ids.next().expect("attributes have a value"));
+            attribute(name, value)
+        })
+        .collect()
+}
+
+// ID : identifier | numeral | quoted string ('+' quoted string)* | HTML string
+pub(crate) fn lower_id(id: &SyntaxNode) -> Id {
+    let mut tokens = id.tokens().peekable();
+    match tokens.peek() {
+        Some(token) if token.kind == TokenKind::Html => {
+            Id::html(&token.text[1..token.text.len() - 1])
+        }
+        Some(token) if token.kind == TokenKind::QuotedString => Id::from_dot(
+            tokens
+                .filter(|t| t.kind == TokenKind::QuotedString)
+                .map(|t| unquote(&t.text))
+                .collect::<String>(),
+        ),
+        Some(token) => Id::from_dot(&token.text),
+        None => unreachable!("IDs have at least one token"),
+    }
+}
+
+// In quoted strings the only escaped character is the double-quote. A backslash
+// followed by a newline continues the string on the next line. Every other
+// backslash is kept as is, since graphviz gives them meaning in labels.
+fn unquote(text: &str) -> String {
+    let inner = &text[1..text.len() - 1];
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some('"')) => {
+                value.push('"');
+                chars.next();
+            }
+            ('\\', Some('\n')) => {
+                chars.next();
+            }
+            ('\\', Some('\r')) => {
+                chars.next();
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+            }
+            ('\\', Some('\\')) => {
+                value.push_str("\\\\");
+                chars.next();
+            }
+            _ => value.push(c),
+        }
+    }
+    value
+}
+
+pub(crate) fn attribute(name: Id, value: Id) -> Attribute {
+    if name.html {
+        return Attribute::Other(name, value);
+    }
+    if value.html {
+        return match name.get() {
+            "label" => Attribute::Label(Label::Html(Html::from_dot(value.get()))),
+            _ => Attribute::Other(name, value),
+        };
+    }
+    let typed = match name.get() {
+        "area" => value.get().parse().ok().map(Attribute::Area),
+        "arrowhead" => Arrow::parse(value.get()).map(Attribute::ArrowHead),
+        "arrowsize" => value.get().parse().ok().map(Attribute::ArrowSize),
+        "arrowtail" => Arrow::parse(value.get()).map(Attribute::ArrowTail),
+        "bgcolor" => ColorList::parse(value.get()).map(Attribute::BgColor),
+        "class" => Some(Attribute::Class(value.clone())),
+        "color" => ColorList::parse(value.get()).map(Attribute::Color),
+        "colorscheme" => ColorScheme::parse(value.get()).map(Attribute::ColorScheme),
+        "dir" => Dir::parse(value.get()).map(Attribute::Dir),
+        "distortion" => value.get().parse().ok().map(Attribute::Distortion),
+        "dpi" => value.get().parse().ok().map(Attribute::Dpi),
+        "fillcolor" => ColorList::parse(value.get()).map(Attribute::FillColor),
+        "fontnames" => FontNames::parse(value.get()).map(Attribute::FontNames),
+        "fontpath" => Some(Attribute::FontPath(value.clone())),
+        "href" => Some(Attribute::Href(value.clone())),
+        "id" => Some(Attribute::Id(value.clone())),
+        "intervals" => Intervals::parse(value.get()).map(Attribute::Intervals),
+        "K" => value.get().parse().ok().map(Attribute::K),
+        "label" => Some(Attribute::Label(Label::from_dot(value.get()))),
+        "len" => value.get().parse().ok().map(Attribute::Length),
+        "mindist" => value.get().parse().ok().map(Attribute::MinDist),
+        "orientation" => value.get().parse().ok().map(Attribute::Orientation),
+        "overlap" => Overlap::parse(value.get()).map(Attribute::Overlap),
+        "packmode" => PackMode::parse(value.get()).map(Attribute::PackMode),
+        "page" => Size::parse(value.get()).map(Attribute::Page),
+        "pagedir" => PageDir::parse(value.get()).map(Attribute::PageDir),
+        "pin" => value.get().parse().ok().map(Attribute::Pin),
+        "pos" => Position::parse(value.get()).map(Attribute::Pos),
+        "peripheries" => value.get().parse().ok().map(Attribute::Peripheries),
+        "rank" => Rank::parse(value.get()).map(Attribute::Rank),
+        "rankdir" => RankDir::parse(value.get()).map(Attribute::RankDir),
+        "ranksep" => value.get().parse().ok().map(Attribute::RankSep),
+        "regular" => value.get().parse().ok().map(Attribute::Regular),
+        "repulsiveforce" => value.get().parse().ok().map(Attribute::RepulsiveForce),
+        "root" => Some(Attribute::Root(value.clone())),
+        "shape" => Shape::parse(value.get()).map(Attribute::Shape),
+        "sides" => value.get().parse().ok().map(Attribute::Sides),
+        "size" => Size::parse(value.get()).map(Attribute::Size),
+        "skew" => value.get().parse().ok().map(Attribute::Skew),
+        "style" => Style::parse(value.get()).map(Attribute::Style),
+        "start" => Start::parse(value.get()).map(Attribute::Start),
+        "stylesheet" => Some(Attribute::Stylesheet(value.clone())),
+        "target" => Some(Attribute::Target(value.clone())),
+        "tooltip" => Some(Attribute::Tooltip(value.clone())),
+        "URL" => Some(Attribute::Url(value.clone())),
+        _ => None,
+    };
+    typed.unwrap_or(Attribute::Other(name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::printer::print_graph;
+    use std::str;
+
+    fn print(graph: &Graph) -> String {
+        let mut writer = Vec::new();
+        print_graph(&mut writer, graph).unwrap();
+        str::from_utf8(&writer).unwrap().to_string()
+    }
+
+    #[test]
+    fn hello_world() {
+        let graph = parse_graph("digraph G {Hello->World}").unwrap();
+        assert_eq!(print(&graph), "digraph G {\n  Hello -> World;\n}\n");
+    }
+
+    #[test]
+    fn round_trip() {
+        let input = r#"
+            /* https://graphviz.org/Gallery/directed/cluster.html */
+            digraph G {
+              subgraph cluster_0 {
+                style=filled; color=lightgrey;
+                node [style=filled,color=white];
+                a0 -> a1 -> a2 -> a3;
+                label = "process #1";
+              }
+              start -> a0;
+              start [shape=Mdiamond];
+              end [shape=Msquare, fontname="Helvetica,Arial"];
+              a3:e -> end:sw:n [weight=2.5]
+              "node" [label="say \"hi\"\l", tooltip="C:\\dir\\"]
+            }
+        "#;
+        let printed = print(&parse_graph(input).unwrap());
+        assert_eq!(print(&parse_graph(&printed).unwrap()), printed);
+    }
+
+    #[test]
+    fn full_grammar() {
+        let input = r#"
+            # 1 "preprocessed.dot"
+            STRICT Graph "my graph" {
+              // line comment
+              graph [rankdir=LR] [size="7.5"];
+              node [label=<<b>bold</b>>];
+              edge [len=-.5; color=red]
+              rankdir = "T" + "B"
+              subgraph { a -- b } -- {c; d} -- e [style=dashed]
+              f:p1:ne -- g
+            }
+        "#;
+        let graph = parse_graph(input).unwrap();
+        assert!(graph.strict);
+        assert_eq!(graph.id.as_ref().map(Id::get), Some("my graph"));
+        let printed = print(&graph);
+        assert!(printed.contains("label=<<b>bold</b>>"));
+        assert!(printed.contains("rankdir=TB"));
+        assert!(printed.contains("{a -- b} -- {c d} -- e [style=dashed];"));
+        assert!(printed.contains("f:p1:ne -- g;"));
+    }
+
+    #[test]
+    fn errors_report_position() {
+        let error = parse_graph("digraph {\n  a -> b\n  c -- d\n}")
+            .err()
+            .unwrap();
+        assert_eq!((error.line(), error.column()), (3, 5));
+
+        let error = parse_graph("graph {\n  a [label=]\n}").err().unwrap();
+        assert_eq!((error.line(), error.column()), (2, 12));
+
+        let error = parse_graph("graph { \"a }").err().unwrap();
+        assert_eq!((error.line(), error.column()), (1, 9));
+
+        let error = parse_graph("digraph { a:p:up -> b }").err().unwrap();
+        assert_eq!((error.line(), error.column()), (1, 15));
+        assert_eq!(error.message(), "invalid compass point 'up'");
+
+        let error = parse_graph("graph { a").err().unwrap();
+        assert_eq!(error.message(), "expected '}', found end of input");
+    }
+}
@@ -1,6 +1,8 @@
 use std::io;
 
-use crate::ast::{Attribute, AttributeScope, Graph, GraphType, Statement};
+use crate::ast::{Attribute, AttributeScope, EdgeTarget, Graph, GraphType, Id, NodeId, Statement};
+use crate::attributes::Label;
+use crate::escape;
 
 trait AstVisitor<T> {
     fn visit_graph(&mut self, graph: &Graph) -> T;
@@ -16,6 +18,7 @@ pub fn print_graph<W: io::Write>(writer: &mut W, graph: &Graph) -> io::Result<()
     let mut printer = Printer {
         arrow: arrow.to_string(),
         depth: 0,
+        inline: false,
         writer,
     };
     printer.visit_graph(graph)
@@ -24,12 +27,17 @@ pub fn print_graph<W: io::Write>(writer: &mut W, graph: &Graph) -> io::Result<()
 struct Printer<W: io::Write> {
     arrow: String,
     depth: i32,
+    // Subgraphs used as edge endpoints are printed on a single line
+    inline: bool,
     writer: W,
 }
 
 impl<W: io::Write> Printer<W> {
     #[inline]
     fn indent(&mut self) -> io::Result<()> {
+        if self.inline {
+            return Ok(());
+        }
         for _ in 0..self.depth {
             self.writer.write_all(b"  ")?;
         }
@@ -37,19 +45,169 @@ impl<W: io::Write> Printer<W> {
     }
 
     #[inline]
-    fn arrow(&mut self, left: &str, right: &str) -> io::Result<()> {
-        write!(self.writer, "\"{}\" {} \"{}\"", left, self.arrow, right)
+    fn end_statement(&mut self) -> io::Result<()> {
+        if self.inline {
+            return Ok(());
+        }
+        self.writer.write_all(b";\n")
+    }
+
+    #[inline]
+    fn arrow(&mut self, left: &NodeId, right: &NodeId) -> io::Result<()> {
+        self.node_id(left)?;
+        write!(self.writer, " {} ", self.arrow)?;
+        self.node_id(right)
+    }
+
+    fn edge_target(&mut self, target: &EdgeTarget) -> io::Result<()> {
+        match target {
+            EdgeTarget::Node(node_id) => self.node_id(node_id),
+            EdgeTarget::Subgraph { id, statements } => {
+                let inline = self.inline;
+                self.inline = true;
+                self.subgraph(id.as_ref(), statements)?;
+                self.inline = inline;
+                Ok(())
+            }
+        }
+    }
+
+    fn subgraph(&mut self, id: Option<&Id>, statements: &[Statement]) -> io::Result<()> {
+        // Anonymous subgraphs print as a bare { }
+        if let Some(id) = id {
+            self.writer.write_all(b"subgraph ")?;
+            self.id(id)?;
+            self.writer.write_all(b" ")?;
+        }
+        if self.inline {
+            self.writer.write_all(b"{")?;
+            for (i, statement) in statements.iter().enumerate() {
+                if i > 0 {
+                    self.writer.write_all(b" ")?;
+                }
+                self.visit_statement(statement)?;
+            }
+            return self.writer.write_all(b"}");
+        }
+        self.writer.write_all(b"{\n")?;
+        self.depth += 1;
+        for statement in statements {
+            self.visit_statement(statement)?;
+        }
+        self.depth -= 1;
+        self.indent()?;
+        self.writer.write_all(b"}\n")
+    }
+
+    fn id(&mut self, id: &Id) -> io::Result<()> {
+        self.writer.write_all(escape::id(id).as_bytes())
+    }
+
+    fn node_id(&mut self, node_id: &NodeId) -> io::Result<()> {
+        self.id(&node_id.id)?;
+        if let Some(port) = &node_id.port {
+            self.writer.write_all(b":")?;
+            self.id(port)?;
+        }
+        if let Some(compass) = &node_id.compass {
+            write!(self.writer, ":{}", compass)?;
+        }
+        Ok(())
     }
 
     fn print_attribute(&mut self, attribute: &Attribute) -> io::Result<()> {
         match attribute {
-            Attribute::Color(c) => write!(self.writer, "color={}", c),
-            Attribute::Label(l) => write!(self.writer, "label=\"{}\"", l),
-            Attribute::Length(l) => write!(self.writer, "len={:.2}", l),
+            Attribute::ArrowHead(a) => write!(self.writer, "arrowhead={}", a),
+            Attribute::ArrowSize(s) => write!(self.writer, "arrowsize={}", s),
+            Attribute::ArrowTail(a) => write!(self.writer, "arrowtail={}", a),
+            Attribute::Area(a) => write!(self.writer, "area={}", a),
+            Attribute::BgColor(c) => {
+                self.writer.write_all(b"bgcolor=")?;
+                self.id(&Id::from_dot(c))
+            }
+            Attribute::Class(v) => {
+                self.writer.write_all(b"class=")?;
+                self.id(v)
+            }
+            Attribute::Color(c) => {
+                self.writer.write_all(b"color=")?;
+                self.id(&Id::from_dot(c))
+            }
+            Attribute::ColorScheme(s) => write!(self.writer, "colorscheme={}", s),
+            Attribute::Dir(d) => write!(self.writer, "dir={}", d),
+            Attribute::Dpi(d) => write!(self.writer, "dpi={}", d),
+            Attribute::FillColor(c) => {
+                self.writer.write_all(b"fillcolor=")?;
+                self.id(&Id::from_dot(c))
+            }
+            Attribute::FontNames(n) => write!(self.writer, "fontnames={}", n),
+            Attribute::FontPath(v) => {
+                self.writer.write_all(b"fontpath=")?;
+                self.id(v)
+            }
+            Attribute::Href(v) => {
+                self.writer.write_all(b"href=")?;
+                self.id(v)
+            }
+            Attribute::Id(v) => {
+                self.writer.write_all(b"id=")?;
+                self.id(v)
+            }
+            Attribute::Label(Label::Text(l)) => write!(self.writer, "label={}", escape::quote(l)),
+            Attribute::Label(Label::Html(h)) => write!(self.writer, "label=<{}>", h.get()),
+            Attribute::Label(Label::Record(r)) => {
+                write!(self.writer, "label={}", escape::quote(r.get()))
+            }
+            // Left out of DOT by `visit_attributes`
+            Attribute::Intervals(_) => Ok(()),
+            Attribute::K(k) => write!(self.writer, "K={}", k),
+            Attribute::Length(l) => write!(self.writer, "len={}", l),
+            Attribute::MinDist(d) => write!(self.writer, "mindist={}", d),
+            Attribute::Overlap(o) => write!(self.writer, "overlap={}", o),
+            Attribute::PackMode(p) => write!(self.writer, "packmode={}", p),
+            Attribute::Page(p) => write!(self.writer, "page={}", p),
+            Attribute::PageDir(d) => write!(self.writer, "pagedir={}", d),
+            Attribute::Pin(p) => write!(self.writer, "pin={}", p),
+            Attribute::Pos(p) => write!(self.writer, "pos=\"{}\"", p),
+            Attribute::Rank(r) => write!(self.writer, "rank={}", r),
             Attribute::RankDir(rd) => write!(self.writer, "rankdir={}", rd),
+            Attribute::RankSep(s) => write!(self.writer, "ranksep={}", s),
+            Attribute::RepulsiveForce(f) => write!(self.writer, "repulsiveforce={}", f),
+            Attribute::Root(r) => {
+                self.writer.write_all(b"root=")?;
+                self.id(r)
+            }
             Attribute::Style(s) => write!(self.writer, "style={}", s),
             Attribute::Shape(s) => write!(self.writer, "shape={}", s),
             Attribute::Size(s) => write!(self.writer, "size={}", s),
+            Attribute::Start(s) => write!(self.writer, "start={}", s),
+            Attribute::Stylesheet(v) => {
+                self.writer.write_all(b"stylesheet=")?;
+                self.id(v)
+            }
+            Attribute::Target(v) => {
+                self.writer.write_all(b"target=")?;
+                self.id(v)
+            }
+            Attribute::Tooltip(v) => {
+                self.writer.write_all(b"tooltip=")?;
+                self.id(v)
+            }
+            Attribute::Url(v) => {
+                self.writer.write_all(b"URL=")?;
+                self.id(v)
+            }
+            Attribute::Sides(s) => write!(self.writer, "sides={}", s),
+            Attribute::Skew(s) => write!(self.writer, "skew={}", s),
+            Attribute::Distortion(d) => write!(self.writer, "distortion={}", d),
+            Attribute::Orientation(o) => write!(self.writer, "orientation={}", o),
+            Attribute::Regular(r) => write!(self.writer, "regular={}", r),
+            Attribute::Peripheries(p) => write!(self.writer, "peripheries={}", p),
+            Attribute::Other(name, value) => {
+                self.id(name)?;
+                self.writer.write_all(b"=")?;
+                self.id(value)
+            }
         }
     }
 }
@@ -65,7 +223,8 @@ impl<W: io::Write> AstVisitor<io::Result<()>> for Printer<W> {
         }
 
         if let Some(id) = &graph.id {
-            write!(self.writer, " {}", id)?;
+            self.writer.write_all(b" ")?;
+            self.id(id)?;
         }
         self.writer.write_all(b" {\n")?;
         self.depth += 1;
@@ -87,13 +246,13 @@ impl<W: io::Write> AstVisitor<io::Result<()>> for Printer<W> {
                     AttributeScope::Edge => self.writer.write_all(b"edge")?,
                 }
                 self.visit_attributes(attributes)?;
-                self.writer.write_all(b";\n")?;
+                self.end_statement()?;
             }
             Statement::Node { id, attributes } => {
                 self.indent()?;
-                write!(self.writer, "\"{}\"", id.get())?;
+                self.id(id)?;
                 self.visit_attributes(attributes)?;
-                self.writer.write_all(b";\n")?;
+                self.end_statement()?;
             }
             Statement::Edge {
                 from,
@@ -101,31 +260,39 @@ impl<W: io::Write> AstVisitor<io::Result<()>> for Printer<W> {
                 attributes,
             } => {
                 self.indent()?;
-                self.arrow(from.get(), to.get())?;
+                self.arrow(from, to)?;
                 self.visit_attributes(attributes)?;
-                self.writer.write_all(b";\n")?;
+                self.end_statement()?;
             }
-            Statement::Subgraph { id, statements } => {
+            Statement::EdgeChain {
+                targets,
+                attributes,
+            } => {
                 self.indent()?;
-                self.writer.write_all(b"subgraph")?;
-                if let Some(id) = id {
-                    write!(self.writer, " {}", id.get())?;
-                }
-                self.writer.write_all(b" {\n")?;
-                self.depth += 1;
-                for statement in statements {
-                    self.visit_statement(statement)?;
+                for (i, target) in targets.iter().enumerate() {
+                    if i > 0 {
+                        write!(self.writer, " {} ", self.arrow)?;
+                    }
+                    self.edge_target(target)?;
                 }
-                self.depth -= 1;
+                self.visit_attributes(attributes)?;
+                self.end_statement()?;
+            }
+            Statement::Subgraph { id, statements } => {
                 self.indent()?;
-                self.writer.write_all(b"}\n")?;
+                self.subgraph(id.as_ref(), statements)?;
             }
         }
         Ok(())
     }
 
     fn visit_attributes(&mut self, attributes: &[Attribute]) -> io::Result<()> {
-        match attributes {
+        // Intervals are for GEXF alone, graphviz does not know them
+        let attributes: Vec<&Attribute> = attributes
+            .iter()
+            .filter(|a| !matches!(a, Attribute::Intervals(_)))
+            .collect();
+        match attributes.as_slice() {
             [] => {}
             [attribute] => {
                 self.writer.write_all(b" [")?;
@@ -0,0 +1,243 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+
+use crate::ast::Graph;
+use crate::printer::print_graph;
+
+// docs
+// * https://graphviz.org/doc/info/command.html
+//
+// The graph is printed to the stdin of a graphviz executable, `dot` from the
+// PATH unless told otherwise, and the drawing is read back from its stdout.
+// The engine and format come from the layout and output the graph was built
+// with and can be overridden, e.g. to pick gif out of the bitmap formats.
+
+#[derive(Debug)]
+pub enum RenderError {
+    // The executable could not be started, usually because graphviz is not
+    // installed or not on the PATH
+    Spawn {
+        executable: PathBuf,
+        source: io::Error,
+    },
+    Io(io::Error),
+    // The graph was built without an output and no format was given
+    NoFormat,
+    // graphviz ran but rejected the graph or failed to draw it
+    Graphviz {
+        status: ExitStatus,
+        stderr: String,
+    },
+}
+
+impl Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderError::Spawn { executable, source } => {
+                write!(f, "could not run {}: {}", executable.display(), source)
+            }
+            RenderError::Io(e) => write!(f, "could not talk to graphviz: {}", e),
+            RenderError::NoFormat => f.write_str("no output format given"),
+            RenderError::Graphviz { status, stderr } => {
+                write!(f, "graphviz failed ({}): {}", status, stderr.trim_end())
+            }
+        }
+    }
+}
+
+impl Error for RenderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RenderError::Spawn { source, .. } => Some(source),
+            RenderError::Io(e) => Some(e),
+            RenderError::NoFormat | RenderError::Graphviz { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for RenderError {
+    fn from(e: io::Error) -> Self {
+        RenderError::Io(e)
+    }
+}
+
+pub struct Renderer {
+    executable: PathBuf,
+    engine: Option<String>,
+    format: Option<String>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer::new()
+    }
+}
+
+impl Renderer {
+    pub fn new() -> Renderer {
+        Renderer {
+            executable: PathBuf::from("dot"),
+            engine: None,
+            format: None,
+        }
+    }
+
+    pub fn executable<P: Into<PathBuf>>(&mut self, executable: P) -> &mut Renderer {
+        self.executable = executable.into();
+        self
+    }
+
+    // The `-K` layout engine, e.g. "neato"
+    pub fn engine<T: ToString>(&mut self, engine: T) -> &mut Renderer {
+        self.engine = Some(engine.to_string());
+        self
+    }
+
+    // The `-T` output format, e.g. "png" or "svg:cairo"
+    pub fn format<T: ToString>(&mut self, format: T) -> &mut Renderer {
+        self.format = Some(format.to_string());
+        self
+    }
+
+    pub fn render(&self, graph: &Graph) -> Result<Vec<u8>, RenderError> {
+        let format = self
+            .format
+            .as_deref()
+            .or(graph.format)
+            .ok_or(RenderError::NoFormat)?;
+        let mut command = Command::new(&self.executable);
+        if let Some(engine) = self.engine.as_deref().or(graph.engine) {
+            command.arg(format!("-K{}", engine));
+        }
+        command
+            .arg(format!("-T{}", format))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = command.spawn().map_err(|source| RenderError::Spawn {
+            executable: self.executable.clone(),
+            source,
+        })?;
+
+        let mut input = Vec::new();
+        print_graph(&mut input, graph)?;
+        // Written from another thread so that a large drawing filling up
+        // stdout cannot block graphviz while we are still writing stdin
+        let mut stdin = child.stdin.take().unwrap();
+        let writer = thread::spawn(move || stdin.write_all(&input));
+        let output = child.wait_with_output()?;
+        let written = writer.join().unwrap();
+
+        if !output.status.success() {
+            return Err(RenderError::Graphviz {
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            });
+        }
+        written?;
+        Ok(output.stdout)
+    }
+}
+
+// Renders with `dot` from the PATH in the graph's own engine and format
+pub fn render(graph: &Graph) -> Result<Vec<u8>, RenderError> {
+    Renderer::new().render(graph)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::builder::directed;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+    use std::sync::{Mutex, MutexGuard};
+
+    // Only these tests start processes. Holding the lock while a script is
+    // written and run keeps another test from forking while the script is
+    // still open for writing, which would make it busy to execute.
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    // A stand-in for dot, deleted when the test is done with it
+    struct Script {
+        path: PathBuf,
+        _lock: MutexGuard<'static, ()>,
+    }
+
+    impl Script {
+        fn new(name: &str, body: &str) -> Script {
+            let lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            let path =
+                std::env::temp_dir().join(format!("graphe-render-{}-{}", std::process::id(), name));
+            fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            Script { path, _lock: lock }
+        }
+    }
+
+    impl Drop for Script {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    fn render_with(executable: &Path, graph: &Graph) -> Result<String, RenderError> {
+        Renderer::new()
+            .executable(executable)
+            .render(graph)
+            .map(|bytes| String::from_utf8(bytes).unwrap())
+    }
+
+    #[test]
+    fn render_through_executable() {
+        let script = Script::new("echo", "echo \"$@\"; cat");
+        let echo = &script.path;
+        let mut builder = directed().neato().svg();
+        builder.edge_("a", "b");
+        let output = render_with(echo, &builder.build()).unwrap();
+        assert_eq!(
+            output,
+            "-Kneato -Tsvg\ndigraph {\n  graph [layout=neato];\n  a -> b;\n}\n"
+        );
+
+        let mut builder = directed().bitmap();
+        builder.node_("a");
+        let output = Renderer::new()
+            .executable(echo)
+            .format("gif")
+            .render(&builder.build())
+            .unwrap();
+        assert!(output.starts_with(b"-Tgif\n"));
+
+        assert!(matches!(
+            render_with(echo, &directed().build()),
+            Err(RenderError::NoFormat)
+        ));
+    }
+
+    #[test]
+    fn render_errors() {
+        let fail = Script::new(
+            "fail",
+            "cat >/dev/null; echo 'syntax error in line 1' >&2; exit 1",
+        );
+        match render_with(&fail.path, &directed().svg().build()) {
+            Err(RenderError::Graphviz { status, stderr }) => {
+                assert_eq!(status.code(), Some(1));
+                assert_eq!(stderr, "syntax error in line 1\n");
+            }
+            _ => panic!("expected a graphviz error"),
+        }
+
+        let missing = Path::new("/nonexistent/dot");
+        assert!(matches!(
+            render_with(missing, &directed().svg().build()),
+            Err(RenderError::Spawn { .. })
+        ));
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use crate::ast::{edge_pairs, Attribute, EdgeTarget, Id, NodeId, Statement};
+
+// docs
+// * https://graphviz.org/doc/info/lang.html#lexical-and-semantic-notes
+//
+// A strict graph has at most one edge between a tail and a head (in either
+// direction for undirected graphs). Graphviz hands back the existing edge for
+// every duplicate and sets the duplicate's attributes and ports on it, so the
+// first edge survives with the attribute values that were given last.
+
+pub(crate) fn merge_edges(statements: Vec<Statement>, directed: bool) -> Vec<Statement> {
+    let mut edges = StrictEdges {
+        directed,
+        edges: Vec::new(),
+        lookup: HashMap::new(),
+        kept: Vec::new(),
+    };
+    edges.collect(&statements);
+    edges.rebuild(statements, &mut 0, false)
+}
+
+struct MergedEdge {
+    from: NodeId,
+    to: NodeId,
+    attributes: Vec<Attribute>,
+    merged: bool,
+}
+
+struct StrictEdges {
+    directed: bool,
+    edges: Vec<MergedEdge>,
+    lookup: HashMap<(Id, Id), usize>,
+    // For every edge in statement order, the merged edge it is kept as or
+    // `None` for a duplicate
+    kept: Vec<Option<usize>>,
+}
+
+impl StrictEdges {
+    fn collect(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            match statement {
+                Statement::Edge {
+                    from,
+                    to,
+                    attributes,
+                } => self.add(from, to, attributes),
+                Statement::EdgeChain {
+                    targets,
+                    attributes,
+                } => {
+                    // Graphviz reads subgraph endpoints before it joins them
+                    for target in targets {
+                        if let EdgeTarget::Subgraph { statements, .. } = target {
+                            self.collect(statements);
+                        }
+                    }
+                    for (from, to) in edge_pairs(targets) {
+                        self.add(&from, &to, attributes);
+                    }
+                }
+                Statement::Subgraph { statements, .. } => self.collect(statements),
+                Statement::Attribute(..) | Statement::Node { .. } => {}
+            }
+        }
+    }
+
+    fn add(&mut self, from: &NodeId, to: &NodeId, attributes: &[Attribute]) {
+        let key = (from.id.clone(), to.id.clone());
+        let existing = match self.lookup.get(&key) {
+            Some(&index) => Some((index, false)),
+            None if !self.directed => self
+                .lookup
+                .get(&(to.id.clone(), from.id.clone()))
+                .map(|&index| (index, true)),
+            None => None,
+        };
+
+        let (index, reversed) = match existing {
+            Some(existing) => existing,
+            None => {
+                self.lookup.insert(key, self.edges.len());
+                self.kept.push(Some(self.edges.len()));
+                self.edges.push(MergedEdge {
+                    from: from.clone(),
+                    to: to.clone(),
+                    attributes: attributes.to_vec(),
+                    merged: false,
+                });
+                return;
+            }
+        };
+
+        self.kept.push(None);
+        let edge = &mut self.edges[index];
+        // Ports belong to the node they were given for, whichever way round
+        // an undirected duplicate was written
+        let (tail, head) = if reversed { (to, from) } else { (from, to) };
+        merge_endpoint(&mut edge.from, tail);
+        merge_endpoint(&mut edge.to, head);
+        for attribute in attributes {
+            edge.attributes.retain(|a| a.name() != attribute.name());
+            edge.attributes.push(attribute.clone());
+        }
+        edge.merged |= !attributes.is_empty() || has_port(tail) || has_port(head);
+    }
+
+    fn rebuild(
+        &self,
+        statements: Vec<Statement>,
+        next: &mut usize,
+        nested: bool,
+    ) -> Vec<Statement> {
+        let mut rebuilt = Vec::new();
+        for statement in statements {
+            match statement {
+                Statement::Edge { from, to, .. } => {
+                    self.edge(&mut rebuilt, &from, &to, next, nested)
+                }
+                Statement::EdgeChain {
+                    targets,
+                    attributes,
+                } => {
+                    // Chains stay as written unless one of their edges is a
+                    // duplicate or picked up attributes from one
+                    let pairs = edge_pairs(&targets);
+                    let targets: Vec<EdgeTarget> = targets
+                        .into_iter()
+                        .map(|target| match target {
+                            EdgeTarget::Subgraph { id, statements } => EdgeTarget::Subgraph {
+                                id,
+                                statements: self.rebuild(statements, next, true),
+                            },
+                            target => target,
+                        })
+                        .collect();
+                    let unchanged = self.kept[*next..*next + pairs.len()]
+                        .iter()
+                        .all(|kept| kept.is_some_and(|index| !self.edges[index].merged));
+                    if unchanged {
+                        *next += pairs.len();
+                        rebuilt.push(Statement::EdgeChain {
+                            targets,
+                            attributes,
+                        });
+                    } else {
+                        // Subgraph endpoints keep their own statements, e.g.
+                        // the node attributes in {a [color=red] b} -> c
+                        for target in targets {
+                            if let EdgeTarget::Subgraph { id, statements } = target {
+                                rebuilt.push(Statement::Subgraph { id, statements });
+                            }
+                        }
+                        for (from, to) in pairs {
+                            self.edge(&mut rebuilt, &from, &to, next, nested);
+                        }
+                    }
+                }
+                Statement::Subgraph { id, statements } => rebuilt.push(Statement::Subgraph {
+                    id,
+                    statements: self.rebuild(statements, next, true),
+                }),
+                statement => rebuilt.push(statement),
+            }
+        }
+        rebuilt
+    }
+
+    fn edge(
+        &self,
+        rebuilt: &mut Vec<Statement>,
+        from: &NodeId,
+        to: &NodeId,
+        next: &mut usize,
+        nested: bool,
+    ) {
+        match self.kept[*next] {
+            Some(index) => {
+                let edge = &self.edges[index];
+                rebuilt.push(Statement::Edge {
+                    from: edge.from.clone(),
+                    to: edge.to.clone(),
+                    attributes: edge.attributes.clone(),
+                });
+            }
+            // The edge itself lives on elsewhere but its nodes still belong
+            // to this subgraph
+            None if nested => {
+                for id in [&from.id, &to.id] {
+                    rebuilt.push(Statement::Node {
+                        id: id.clone(),
+                        attributes: Vec::new(),
+                    });
+                }
+            }
+            None => {}
+        }
+        *next += 1;
+    }
+}
+
+fn has_port(node: &NodeId) -> bool {
+    node.port.is_some() || node.compass.is_some()
+}
+
+fn merge_endpoint(endpoint: &mut NodeId, duplicate: &NodeId) {
+    if duplicate.port.is_some() {
+        endpoint.port = duplicate.port.clone();
+    }
+    if duplicate.compass.is_some() {
+        endpoint.compass = duplicate.compass;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_graph;
+    use crate::printer::print_graph;
+
+    fn strict(source: &str) -> String {
+        let mut graph = parse_graph(source).unwrap();
+        graph.statements = merge_edges(graph.statements, true);
+        let mut writer = Vec::new();
+        print_graph(&mut writer, &graph).unwrap();
+        String::from_utf8(writer).unwrap()
+    }
+
+    #[test]
+    fn subgraph_endpoints() {
+        let source = "strict digraph {\n  {a [color=red] b} -> c;\n}\n";
+        assert_eq!(strict(source), source);
+        // Expanded for the duplicate, the endpoint keeps its statements
+        assert_eq!(
+            strict("strict digraph { {a [color=red] b -> d; b -> d} -> c; a -> c [label=x] }"),
+            "strict digraph {\n  {\n    a [color=red];\n    b -> d;\n    b;\n    d;\n  }\n  a -> c [label=\"x\"];\n  b -> c;\n  d -> c;\n}\n"
+        );
+    }
+}
@@ -0,0 +1,1363 @@
+use std::f64::consts::PI;
+use std::io::{self, Write};
+
+use crate::ast::{Attribute, Graph};
+use crate::attributes::{
+    Arrow, ArrowShape, Color, ColorList, Dir, Fill, Label, Shape, Side, Style,
+};
+use crate::escape::escape_html;
+use crate::layout::{font_size, layout, spline, Layout, NodeLayout, Point};
+use crate::model::{find, number, record_text, styles, Model};
+
+// docs
+// * https://graphviz.org/docs/outputs/svg/
+// * https://graphviz.org/doc/info/shapes.html
+// * https://graphviz.org/doc/info/arrows.html
+//
+// Draws a laid out graph as a standalone SVG document without graphviz. The
+// document is organised like graphviz's own: a group per cluster, node and
+// edge, each with a title, in that order. Colors are written as hex values so
+// that X11 names that SVG does not know still come out right; Brewer colors
+// have no values here and are drawn black.
+
+const DEFAULT_FONT: &str = "Times,serif";
+// Between the outlines of a node with several peripheries
+const PERIPHERY_GAP: f64 = 4.0;
+// Length of a normal arrowhead at arrowsize=1, and its half width
+const ARROW_LENGTH: f64 = 10.0;
+const ARROW_WIDTH: f64 = 3.5;
+const CORNER_RADIUS: f64 = 12.0;
+
+// The layout must be one of this graph, e.g. from `layout::layout`
+pub fn print_svg<W: Write>(writer: &mut W, graph: &Graph, layout: &Layout) -> io::Result<()> {
+    let model = Model::new(graph);
+    let mut svg = Svg {
+        writer,
+        model: &model,
+    };
+    svg.document(layout)
+}
+
+// Lays the graph out natively and draws it. The output format the graph was
+// built for is not checked: parsed graphs have none, and a format only limits
+// which attributes the builder offers, so whatever SVG cannot show is left
+// out. Builders for SVG draw through `GraphBuilder::build_svg` instead.
+pub fn to_svg(graph: &Graph) -> String {
+    let mut writer = Vec::new();
+    print_svg(&mut writer, graph, &layout(graph)).unwrap();
+    String::from_utf8(writer).unwrap()
+}
+
+enum Outline {
+    Ellipse(Point, f64, f64),
+    Polygon(Vec<Point>),
+    Path(String),
+}
+
+// The stroke of outlines and lines
+struct Pen {
+    color: (String, Option<f64>),
+    width: f64,
+    dash: Option<&'static str>,
+}
+
+impl Pen {
+    fn new(color: Option<ColorList>, styles: &[Style], attributes: &[Attribute]) -> Pen {
+        let mut width = number(attributes, "penwidth").unwrap_or(1.0);
+        if styles.contains(&Style::Bold) {
+            width = width.max(2.0);
+        }
+        let dash = if styles.contains(&Style::Dashed) {
+            Some("5,2")
+        } else if styles.contains(&Style::Dotted) {
+            Some("1,5")
+        } else {
+            None
+        };
+        Pen {
+            color: color.map_or(("black".to_string(), None), |c| paint(c.first())),
+            width,
+            dash,
+        }
+    }
+
+    fn attributes(&self) -> String {
+        let mut s = format!(" stroke=\"{}\"", self.color.0);
+        if let Some(opacity) = self.color.1 {
+            s.push_str(&format!(" stroke-opacity=\"{}\"", num(opacity)));
+        }
+        if self.width != 1.0 {
+            s.push_str(&format!(" stroke-width=\"{}\"", num(self.width)));
+        }
+        if let Some(dash) = self.dash {
+            s.push_str(&format!(" stroke-dasharray=\"{}\"", dash));
+        }
+        s
+    }
+
+    // For arrowheads, which are never dashed
+    fn solid(&self) -> Pen {
+        Pen {
+            color: self.color.clone(),
+            width: self.width,
+            dash: None,
+        }
+    }
+}
+
+struct Svg<'a, W: Write> {
+    writer: &'a mut W,
+    model: &'a Model,
+}
+
+impl<'a, W: Write> Svg<'a, W> {
+    fn document(&mut self, layout: &Layout) -> io::Result<()> {
+        let model = self.model;
+        let (width, height) = (num(layout.width), num(layout.height));
+        writeln!(
+            self.writer,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>"
+        )?;
+        writeln!(
+            self.writer,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}pt\" height=\"{h}pt\" viewBox=\"0 0 {w} {h}\">",
+            w = width,
+            h = height
+        )?;
+        writeln!(self.writer, "<g id=\"graph0\" class=\"graph\">")?;
+        if let Some(id) = &model.id {
+            writeln!(self.writer, "<title>{}</title>", escape_html(id.get()))?;
+        }
+        let background = color_list(&model.attributes, "bgcolor")
+            .map_or(("white".to_string(), None), |c| paint(c.first()));
+        writeln!(
+            self.writer,
+            "<rect x=\"0\" y=\"0\" width=\"{}\" height=\"{}\"{} stroke=\"none\"/>",
+            width,
+            height,
+            fill(&background)
+        )?;
+
+        for (i, cluster) in layout.clusters.iter().enumerate() {
+            let subgraph = model
+                .clusters()
+                .find(|&s| model.subgraphs[s].id.as_ref() == Some(&cluster.id));
+            if let Some(subgraph) = subgraph {
+                self.cluster(i, subgraph, cluster.min, cluster.max, cluster.label)?;
+            }
+        }
+        for (i, node) in layout.nodes.iter().enumerate().take(model.nodes.len()) {
+            self.node(i, node)?;
+        }
+        for (i, edge) in layout.edges.iter().enumerate().take(model.edges.len()) {
+            self.edge(i, &edge.points, edge.label)?;
+        }
+        writeln!(self.writer, "</g>")?;
+        writeln!(self.writer, "</svg>")
+    }
+
+    fn cluster(
+        &mut self,
+        i: usize,
+        subgraph: usize,
+        min: Point,
+        max: Point,
+        label: Option<Point>,
+    ) -> io::Result<()> {
+        let model = self.model;
+        let attributes = &model.subgraphs[subgraph].attributes;
+        let styles = styles(attributes);
+        if styles.contains(&Style::Invisible) {
+            return Ok(());
+        }
+        writeln!(self.writer, "<g id=\"clust{}\" class=\"cluster\">", i + 1)?;
+        if let Some(id) = &model.subgraphs[subgraph].id {
+            writeln!(self.writer, "<title>{}</title>", escape_html(id.get()))?;
+        }
+        let pen_color =
+            color_list(attributes, "pencolor").or_else(|| color_list(attributes, "color"));
+        let pen = Pen::new(pen_color, &styles, attributes);
+        let fill_color = if styles.contains(&Style::Filled) {
+            Some(
+                color_list(attributes, "fillcolor")
+                    .or_else(|| color_list(attributes, "bgcolor"))
+                    .or_else(|| color_list(attributes, "color"))
+                    .unwrap_or_else(lightgrey),
+            )
+        } else {
+            color_list(attributes, "bgcolor")
+        };
+        let corners = vec![min, Point::new(max.x, min.y), max, Point::new(min.x, max.y)];
+        let rounded = styles.contains(&Style::Rounded);
+        if let (true, Some(colors)) = (styles.contains(&Style::Striped), &fill_color) {
+            self.stripes(&corners, colors)?;
+            self.outline(&Outline::Polygon(corners), None, &pen, rounded)?;
+        } else {
+            let fill_color = fill_color.as_ref().map(|c| paint(c.first()));
+            self.outline(&Outline::Polygon(corners), fill_color, &pen, rounded)?;
+        }
+        if let (Some(text), Some(label)) = (model.subgraph_label(subgraph), label) {
+            self.text(label, &text, attributes)?;
+        }
+        writeln!(self.writer, "</g>")
+    }
+
+    fn node(&mut self, i: usize, layout: &NodeLayout) -> io::Result<()> {
+        let model = self.model;
+        let attributes = &model.nodes[i].attributes;
+        let styles = styles(attributes);
+        if styles.contains(&Style::Invisible) {
+            return Ok(());
+        }
+        writeln!(self.writer, "<g id=\"node{}\" class=\"node\">", i + 1)?;
+        writeln!(
+            self.writer,
+            "<title>{}</title>",
+            escape_html(model.nodes[i].id.get())
+        )?;
+
+        let shape = match find(attributes, "shape") {
+            Some(Attribute::Shape(shape)) => shape.clone(),
+            _ => Shape::Ellipse,
+        };
+        let (c, w, h) = (layout.center, layout.width, layout.height);
+        let color = color_list(attributes, "color");
+        let pen = Pen::new(color.clone(), &styles, attributes);
+        let filled = styles.contains(&Style::Filled)
+            || styles.contains(&Style::Striped)
+            || styles.contains(&Style::Wedged)
+            || matches!(shape, Shape::Point);
+        let fill_colors = if filled {
+            let default = if matches!(shape, Shape::Point) {
+                black
+            } else {
+                lightgrey
+            };
+            Some(
+                color_list(attributes, "fillcolor")
+                    .or(color)
+                    .unwrap_or_else(default),
+            )
+        } else {
+            None
+        };
+
+        let (outline, decorations) = node_outline(&shape, attributes, c, w, h);
+        let peripheries = number(attributes, "peripheries")
+            .map(|p| p as usize)
+            .unwrap_or_else(|| default_peripheries(&shape));
+        let rounded = styles.contains(&Style::Rounded) || matches!(shape, Shape::MRecord);
+        if let Some(outline) = &outline {
+            let outer_pen = if peripheries == 0 {
+                Pen {
+                    color: ("none".to_string(), None),
+                    ..pen.solid()
+                }
+            } else {
+                pen.solid()
+            };
+            let outer_pen = Pen {
+                dash: pen.dash,
+                ..outer_pen
+            };
+            match (&fill_colors, outline) {
+                (Some(colors), Outline::Polygon(points)) if styles.contains(&Style::Striped) => {
+                    self.stripes(points, colors)?;
+                    self.outline(outline, None, &outer_pen, rounded)?;
+                }
+                (Some(colors), Outline::Ellipse(c, rx, ry)) if styles.contains(&Style::Wedged) => {
+                    self.wedges(*c, *rx, *ry, colors)?;
+                    self.outline(outline, None, &outer_pen, rounded)?;
+                }
+                _ => {
+                    let fill_color = fill_colors.as_ref().map(|c| paint(c.first()));
+                    self.outline(outline, fill_color, &outer_pen, rounded)?;
+                }
+            }
+            for k in 1..peripheries {
+                if let Some(inner) = shrink(outline, c, w, h, k as f64 * PERIPHERY_GAP) {
+                    self.outline(&inner, None, &pen, rounded)?;
+                }
+            }
+        }
+        let mut decorations = decorations;
+        if styles.contains(&Style::Diagonals) {
+            if let Some(Outline::Polygon(points)) = &outline {
+                decorations.extend(diagonals(points));
+            }
+        }
+        for d in decorations {
+            writeln!(
+                self.writer,
+                "<path fill=\"none\"{} d=\"{}\"/>",
+                pen.attributes(),
+                d
+            )?;
+        }
+
+        let record = match (&shape, find(attributes, "label")) {
+            (Shape::Record, Some(Attribute::Label(label)))
+            | (Shape::MRecord, Some(Attribute::Label(label))) => match label {
+                Label::Record(record) => Some(record.get()),
+                Label::Text(text) => Some(text.as_str()),
+                Label::Html(_) => None,
+            },
+            _ => None,
+        };
+        match (&shape, record) {
+            (_, Some(fields)) => self.record(layout, fields, attributes, &pen)?,
+            (Shape::Point, _) => {}
+            _ => self.text(c, &model.node_label(i), attributes)?,
+        }
+        writeln!(self.writer, "</g>")
+    }
+
+    // The top level fields of a record side by side, or stacked when ranks
+    // run from left to right, each as wide as its text asks for
+    fn record(
+        &mut self,
+        layout: &NodeLayout,
+        fields: &str,
+        attributes: &[Attribute],
+        pen: &Pen,
+    ) -> io::Result<()> {
+        let fields: Vec<String> = split_fields(fields)
+            .iter()
+            .map(|f| record_text(f))
+            .collect();
+        let stacked = matches!(
+            find(&self.model.attributes, "rankdir")
+                .map(Attribute::value)
+                .as_deref(),
+            Some("LR") | Some("RL")
+        );
+        let size = font_size(attributes);
+        let wants: Vec<f64> = fields
+            .iter()
+            .map(|f| {
+                if stacked {
+                    size * 1.2 + 8.0
+                } else {
+                    f.chars().count().max(1) as f64 * size * 0.55 + 16.0
+                }
+            })
+            .collect();
+        let total: f64 = wants.iter().sum();
+        let (c, w, h) = (layout.center, layout.width, layout.height);
+        let mut start = if stacked {
+            c.y - h / 2.0
+        } else {
+            c.x - w / 2.0
+        };
+        for (i, (field, want)) in fields.iter().zip(&wants).enumerate() {
+            let length = want / total * if stacked { h } else { w };
+            if i > 0 {
+                let (a, b) = if stacked {
+                    (
+                        Point::new(c.x - w / 2.0, start),
+                        Point::new(c.x + w / 2.0, start),
+                    )
+                } else {
+                    (
+                        Point::new(start, c.y - h / 2.0),
+                        Point::new(start, c.y + h / 2.0),
+                    )
+                };
+                writeln!(
+                    self.writer,
+                    "<path fill=\"none\"{} d=\"M{}L{}\"/>",
+                    pen.attributes(),
+                    pt(a),
+                    pt(b)
+                )?;
+            }
+            let middle = start + length / 2.0;
+            let center = if stacked {
+                Point::new(c.x, middle)
+            } else {
+                Point::new(middle, c.y)
+            };
+            self.text(center, field, attributes)?;
+            start += length;
+        }
+        Ok(())
+    }
+
+    fn edge(&mut self, i: usize, points: &[Point], label: Option<Point>) -> io::Result<()> {
+        let model = self.model;
+        let edge = &model.edges[i];
+        let attributes = &edge.attributes;
+        let styles = styles(attributes);
+        if styles.contains(&Style::Invisible) || points.len() < 2 {
+            return Ok(());
+        }
+        writeln!(self.writer, "<g id=\"edge{}\" class=\"edge\">", i + 1)?;
+        let arrow = if model.directed { "->" } else { "--" };
+        writeln!(
+            self.writer,
+            "<title>{}</title>",
+            escape_html(&format!(
+                "{}{}{}",
+                edge.from.id.get(),
+                arrow,
+                edge.to.id.get()
+            ))
+        )?;
+
+        let pen = Pen::new(color_list(attributes, "color"), &styles, attributes);
+        let dir = match find(attributes, "dir") {
+            Some(Attribute::Dir(dir)) => dir.clone(),
+            _ if model.directed => Dir::Forward,
+            _ => Dir::None,
+        };
+        let scale = number(attributes, "arrowsize").unwrap_or(1.0);
+        let arrow = |name: &str| match find(attributes, name) {
+            Some(Attribute::ArrowHead(arrow)) | Some(Attribute::ArrowTail(arrow)) => arrow.clone(),
+            _ => Arrow::parse("normal").unwrap(),
+        };
+        let head = match dir {
+            Dir::Forward | Dir::Both => Some(arrow("arrowhead")),
+            _ => None,
+        };
+        let tail = match dir {
+            Dir::Back | Dir::Both => Some(arrow("arrowtail")),
+            _ => None,
+        };
+
+        // The line stops where the arrowheads begin
+        let mut line = points.to_vec();
+        let mut heads = Vec::new();
+        if let Some(head) = &head {
+            let last = line.len() - 1;
+            if let Some(tip) = shorten(&mut line, last, last - 1, head, scale) {
+                heads.push((tip, head));
+            }
+        }
+        if let Some(tail) = &tail {
+            if let Some(tip) = shorten(&mut line, 0, 1, tail, scale) {
+                heads.push((tip, tail));
+            }
+        }
+        let path = spline(&line);
+        let mut d = format!("M{}", pt(path[0]));
+        for curve in path[1..].chunks(3) {
+            d.push('C');
+            let curve: Vec<String> = curve.iter().map(|&p| pt(p)).collect();
+            d.push_str(&curve.join(" "));
+        }
+        writeln!(
+            self.writer,
+            "<path fill=\"none\"{} d=\"{}\"/>",
+            pen.attributes(),
+            d
+        )?;
+
+        for ((tip, from), arrow) in heads {
+            self.arrowhead(tip, from, arrow, scale, &pen.solid())?;
+        }
+        if let (Some(text), Some(label)) = (model.edge_label(i), label) {
+            self.text(label, &text, attributes)?;
+        }
+        writeln!(self.writer, "</g>")
+    }
+
+    // The shapes of an arrow from `tip` back towards `from`, the first one at
+    // the tip
+    fn arrowhead(
+        &mut self,
+        tip: Point,
+        from: Point,
+        arrow: &Arrow,
+        scale: f64,
+        pen: &Pen,
+    ) -> io::Result<()> {
+        let length = tip.distance(from);
+        if length == 0.0 {
+            return Ok(());
+        }
+        // Towards the tip, and to its left looking along the edge
+        let u = Point::new((tip.x - from.x) / length, (tip.y - from.y) / length);
+        let n = Point::new(u.y, -u.x);
+        let at = |p: Point, along: f64, across: f64| {
+            Point::new(
+                p.x + u.x * along + n.x * across,
+                p.y + u.y * along + n.y * across,
+            )
+        };
+        let width = ARROW_WIDTH * scale;
+        let mut t = tip;
+        for part in arrow.parts() {
+            let len = ARROW_LENGTH * scale * part.shape.length();
+            let b = at(t, -len, 0.0);
+            let (left, right) = match part.side {
+                Side::Both => (width, width),
+                Side::Left => (width, 0.0),
+                Side::Right => (0.0, width),
+            };
+            let fill_color = match part.fill {
+                Fill::Filled => Some(pen.color.clone()),
+                Fill::Open => None,
+            };
+            let stem = format!("M{}L{}", pt(b), pt(t));
+            let polygon = match part.shape {
+                ArrowShape::Normal => Some(vec![t, at(b, 0.0, left), at(b, 0.0, -right)]),
+                ArrowShape::Inv => Some(vec![b, at(t, 0.0, left), at(t, 0.0, -right)]),
+                ArrowShape::Vee => Some(vec![
+                    t,
+                    at(b, 0.0, left),
+                    at(b, len * 0.25, 0.0),
+                    at(b, 0.0, -right),
+                ]),
+                ArrowShape::Crow => Some(vec![
+                    b,
+                    at(t, 0.0, left),
+                    at(t, -len * 0.25, 0.0),
+                    at(t, 0.0, -right),
+                ]),
+                ArrowShape::Box => {
+                    let m = at(b, len * 0.2, 0.0);
+                    self.line(&format!("M{}L{}", pt(b), pt(m)), pen)?;
+                    Some(vec![
+                        at(m, 0.0, left),
+                        at(t, 0.0, left),
+                        at(t, 0.0, -right),
+                        at(m, 0.0, -right),
+                    ])
+                }
+                ArrowShape::Diamond => {
+                    let m = at(b, len / 2.0, 0.0);
+                    Some(vec![t, at(m, 0.0, left), b, at(m, 0.0, -right)])
+                }
+                ArrowShape::Tee => {
+                    let (near, far) = (at(t, -len * 0.2, 0.0), at(t, -len * 0.6, 0.0));
+                    self.line(&stem, pen)?;
+                    Some(vec![
+                        at(near, 0.0, left),
+                        at(far, 0.0, left),
+                        at(far, 0.0, -right),
+                        at(near, 0.0, -right),
+                    ])
+                }
+                ArrowShape::Dot => {
+                    let m = at(b, len / 2.0, 0.0);
+                    writeln!(
+                        self.writer,
+                        "<ellipse cx=\"{}\" cy=\"{}\" rx=\"{r}\" ry=\"{r}\"{}{}/>",
+                        num(m.x),
+                        num(m.y),
+                        fill_attributes(fill_color.as_ref()),
+                        pen.attributes(),
+                        r = num(len / 2.0)
+                    )?;
+                    None
+                }
+                ArrowShape::Curve | ArrowShape::ICurve => {
+                    let m = at(b, len / 2.0, 0.0);
+                    let control = if part.shape == ArrowShape::Curve {
+                        t
+                    } else {
+                        b
+                    };
+                    self.line(&stem, pen)?;
+                    self.line(
+                        &format!(
+                            "M{}Q{} {}",
+                            pt(at(m, 0.0, left)),
+                            pt(control),
+                            pt(at(m, 0.0, -right))
+                        ),
+                        pen,
+                    )?;
+                    None
+                }
+                ArrowShape::None => {
+                    self.line(&stem, pen)?;
+                    None
+                }
+            };
+            if let Some(points) = polygon {
+                writeln!(
+                    self.writer,
+                    "<polygon{}{} points=\"{}\"/>",
+                    fill_attributes(fill_color.as_ref()),
+                    pen.attributes(),
+                    points_list(&points)
+                )?;
+            }
+            t = b;
+        }
+        Ok(())
+    }
+
+    fn line(&mut self, d: &str, pen: &Pen) -> io::Result<()> {
+        writeln!(
+            self.writer,
+            "<path fill=\"none\"{} d=\"{}\"/>",
+            pen.attributes(),
+            d
+        )
+    }
+
+    fn outline(
+        &mut self,
+        outline: &Outline,
+        fill_color: Option<(String, Option<f64>)>,
+        pen: &Pen,
+        rounded: bool,
+    ) -> io::Result<()> {
+        let fill = fill_attributes(fill_color.as_ref());
+        let stroke = pen.attributes();
+        match outline {
+            Outline::Ellipse(c, rx, ry) => writeln!(
+                self.writer,
+                "<ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\"{}{}/>",
+                num(c.x),
+                num(c.y),
+                num(*rx),
+                num(*ry),
+                fill,
+                stroke
+            ),
+            Outline::Polygon(points) if rounded => writeln!(
+                self.writer,
+                "<path{}{} d=\"{}\"/>",
+                fill,
+                stroke,
+                rounded_path(points)
+            ),
+            Outline::Polygon(points) => writeln!(
+                self.writer,
+                "<polygon{}{} points=\"{}\"/>",
+                fill,
+                stroke,
+                points_list(points)
+            ),
+            Outline::Path(d) => writeln!(self.writer, "<path{}{} d=\"{}\"/>", fill, stroke, d),
+        }
+    }
+
+    // Vertical bands across the bounding box of `points`, one per color
+    fn stripes(&mut self, points: &[Point], colors: &ColorList) -> io::Result<()> {
+        let (min, max) = bounds(points);
+        let mut x = min.x;
+        for (color, share) in colors.shares() {
+            let width = (max.x - min.x) * share;
+            writeln!(
+                self.writer,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{} stroke=\"none\"/>",
+                num(x),
+                num(min.y),
+                num(width),
+                num(max.y - min.y),
+                fill(&paint(color))
+            )?;
+            x += width;
+        }
+        Ok(())
+    }
+
+    // Pie slices clockwise from the top, one per color
+    fn wedges(&mut self, c: Point, rx: f64, ry: f64, colors: &ColorList) -> io::Result<()> {
+        let at = |angle: f64| Point::new(c.x + rx * angle.sin(), c.y - ry * angle.cos());
+        let mut angle: f64 = 0.0;
+        for (color, share) in colors.shares() {
+            let sweep = 2.0 * PI * share;
+            if sweep <= 0.0 {
+                continue;
+            }
+            let large = if sweep > PI { 1 } else { 0 };
+            let d = if sweep >= 2.0 * PI - 1e-9 {
+                format!(
+                    "M{}A{} {} 0 1 1 {}A{} {} 0 1 1 {}Z",
+                    pt(at(0.0)),
+                    num(rx),
+                    num(ry),
+                    pt(at(PI)),
+                    num(rx),
+                    num(ry),
+                    pt(at(0.0))
+                )
+            } else {
+                format!(
+                    "M{}L{}A{} {} 0 {} 1 {}Z",
+                    pt(c),
+                    pt(at(angle)),
+                    num(rx),
+                    num(ry),
+                    large,
+                    pt(at(angle + sweep))
+                )
+            };
+            writeln!(
+                self.writer,
+                "<path{} stroke=\"none\" d=\"{}\"/>",
+                fill(&paint(color)),
+                d
+            )?;
+            angle += sweep;
+        }
+        Ok(())
+    }
+
+    // Centered lines of text around `center`
+    fn text(&mut self, center: Point, text: &str, attributes: &[Attribute]) -> io::Result<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        let size = font_size(attributes);
+        let font = find(attributes, "fontname").map_or(DEFAULT_FONT.to_string(), Attribute::value);
+        let color = color_list(attributes, "fontcolor")
+            .map_or(("black".to_string(), None), |c| paint(c.first()));
+        let lines: Vec<&str> = text.split('\n').collect();
+        let line_height = size * 1.2;
+        let first = center.y - (lines.len() - 1) as f64 * line_height / 2.0 + size * 0.35;
+        for (k, line) in lines.iter().enumerate() {
+            writeln!(
+                self.writer,
+                "<text text-anchor=\"middle\" x=\"{}\" y=\"{}\" font-family=\"{}\" font-size=\"{}\"{}>{}</text>",
+                num(center.x),
+                num(first + k as f64 * line_height),
+                escape_html(&font),
+                num(size),
+                fill(&color),
+                escape_html(line)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn color_list(attributes: &[Attribute], name: &str) -> Option<ColorList> {
+    find(attributes, name).and_then(|a| ColorList::parse(&a.value()))
+}
+
+fn lightgrey() -> ColorList {
+    Color::x11("lightgrey").unwrap().into()
+}
+
+fn black() -> ColorList {
+    Color::x11("black").unwrap().into()
+}
+
+// An SVG color and its opacity when not opaque
+fn paint(color: &Color) -> (String, Option<f64>) {
+    match color.channels() {
+        Some([_, _, _, 0]) => ("none".to_string(), None),
+        Some([r, g, b, a]) => (
+            format!("#{:02x}{:02x}{:02x}", r, g, b),
+            if a < 255 {
+                Some(a as f64 / 255.0)
+            } else {
+                None
+            },
+        ),
+        None => ("black".to_string(), None),
+    }
+}
+
+fn fill(color: &(String, Option<f64>)) -> String {
+    match color.1 {
+        Some(opacity) => format!(" fill=\"{}\" fill-opacity=\"{}\"", color.0, num(opacity)),
+        None => format!(" fill=\"{}\"", color.0),
+    }
+}
+
+fn fill_attributes(color: Option<&(String, Option<f64>)>) -> String {
+    color.map_or(" fill=\"none\"".to_string(), fill)
+}
+
+// Two decimals at most, without trailing zeros
+pub(crate) fn num(x: f64) -> String {
+    let s = format!("{:.2}", x);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    match s {
+        "-0" => "0".to_string(),
+        s => s.to_string(),
+    }
+}
+
+fn pt(p: Point) -> String {
+    format!("{},{}", num(p.x), num(p.y))
+}
+
+fn points_list(points: &[Point]) -> String {
+    points.iter().map(|&p| pt(p)).collect::<Vec<_>>().join(" ")
+}
+
+fn bounds(points: &[Point]) -> (Point, Point) {
+    let mut min = Point::new(f64::INFINITY, f64::INFINITY);
+    let mut max = Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for p in points {
+        min = Point::new(min.x.min(p.x), min.y.min(p.y));
+        max = Point::new(max.x.max(p.x), max.y.max(p.y));
+    }
+    (min, max)
+}
+
+// Moves the end of the line at `end` back for an arrowhead, returning the
+// arrowhead's tip and the point it points away from
+fn shorten(
+    line: &mut [Point],
+    end: usize,
+    before: usize,
+    arrow: &Arrow,
+    scale: f64,
+) -> Option<(Point, Point)> {
+    if arrow
+        .parts()
+        .iter()
+        .all(|part| part.shape == ArrowShape::None)
+    {
+        return None;
+    }
+    let length: f64 = arrow
+        .parts()
+        .iter()
+        .map(|part| ARROW_LENGTH * scale * part.shape.length())
+        .sum();
+    let (tip, from) = (line[end], line[before]);
+    let distance = tip.distance(from);
+    if distance == 0.0 {
+        return None;
+    }
+    let t = (length / distance).min(1.0);
+    line[end] = Point::new(tip.x + (from.x - tip.x) * t, tip.y + (from.y - tip.y) * t);
+    Some((tip, from))
+}
+
+fn default_peripheries(shape: &Shape) -> usize {
+    match shape {
+        Shape::PlainText | Shape::Plain | Shape::None | Shape::Underline => 0,
+        Shape::DoubleCircle | Shape::DoubleOctagon => 2,
+        Shape::TripleOctagon => 3,
+        _ => 1,
+    }
+}
+
+// The top level fields of a record label, split at '|' outside braces
+fn split_fields(fields: &str) -> Vec<&str> {
+    let mut split = Vec::new();
+    let (mut depth, mut start, mut escaped) = (0, 0, false);
+    for (i, c) in fields.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            '|' if depth == 0 => {
+                split.push(&fields[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    split.push(&fields[start..]);
+    split
+}
+
+// A polygon in graphviz's fashion: `sides` corners around a circle with a
+// flat bottom, widened at the top by `distortion`, slanted by `skew`,
+// turned clockwise by `orientation` degrees and stretched over the node
+fn polygon(
+    c: Point,
+    w: f64,
+    h: f64,
+    sides: usize,
+    skew: f64,
+    distortion: f64,
+    orientation: f64,
+) -> Vec<Point> {
+    let sides = sides.max(3);
+    let turn = orientation.to_radians();
+    let corners: Vec<(f64, f64)> = (0..sides)
+        .map(|k| {
+            let angle = -PI / 2.0 + PI / sides as f64 + 2.0 * PI * k as f64 / sides as f64;
+            let (x, y) = (angle.cos(), angle.sin());
+            let x = x * (1.0 + distortion * y) + skew * y;
+            (
+                x * turn.cos() + y * turn.sin(),
+                -x * turn.sin() + y * turn.cos(),
+            )
+        })
+        .collect();
+    stretch(&corners, c, w, h)
+}
+
+// Points in a y-up unit space fitted to the node's box
+fn stretch(corners: &[(f64, f64)], c: Point, w: f64, h: f64) -> Vec<Point> {
+    let min_x = corners.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    let max_x = corners
+        .iter()
+        .map(|p| p.0)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let min_y = corners.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let max_y = corners
+        .iter()
+        .map(|p| p.1)
+        .fold(f64::NEG_INFINITY, f64::max);
+    corners
+        .iter()
+        .map(|&(x, y)| {
+            Point::new(
+                c.x + ((x - min_x) / (max_x - min_x).max(1e-9) - 0.5) * w,
+                c.y - ((y - min_y) / (max_y - min_y).max(1e-9) - 0.5) * h,
+            )
+        })
+        .collect()
+}
+
+fn star(c: Point, w: f64, h: f64) -> Vec<Point> {
+    let corners: Vec<(f64, f64)> = (0..10)
+        .map(|k| {
+            let angle = PI / 2.0 + PI * k as f64 / 5.0;
+            let radius = if k % 2 == 0 { 1.0 } else { 0.38 };
+            (radius * angle.cos(), radius * angle.sin())
+        })
+        .collect();
+    stretch(&corners, c, w, h)
+}
+
+// The outline of a shape and the lines drawn over it
+fn node_outline(
+    shape: &Shape,
+    attributes: &[Attribute],
+    c: Point,
+    w: f64,
+    h: f64,
+) -> (Option<Outline>, Vec<String>) {
+    let (x0, y0, x1, y1) = (c.x - w / 2.0, c.y - h / 2.0, c.x + w / 2.0, c.y + h / 2.0);
+    let p = Point::new;
+    let rect = vec![p(x0, y0), p(x1, y0), p(x1, y1), p(x0, y1)];
+    let regular = |sides: usize| polygon(c, w, h, sides, 0.0, 0.0, 0.0);
+    let glyph_size = w.min(h) * 0.3;
+    let glyph = |spec: &str, mirror: bool| {
+        let rect = Outline::Polygon(rect.clone());
+        (Some(rect), vec![glyph(c, glyph_size, mirror, spec)])
+    };
+    let ellipse = Outline::Ellipse(c, w / 2.0, h / 2.0);
+    match shape {
+        Shape::Ellipse | Shape::Oval | Shape::Circle | Shape::DoubleCircle | Shape::Point => {
+            (Some(ellipse), Vec::new())
+        }
+        Shape::MCircle => {
+            let (dx, dy) = (w / 2.0 * 0.6, h / 2.0 * 0.8);
+            let chords = vec![
+                format!(
+                    "M{}L{}",
+                    pt(p(c.x - dx, c.y - dy)),
+                    pt(p(c.x + dx, c.y - dy))
+                ),
+                format!(
+                    "M{}L{}",
+                    pt(p(c.x - dx, c.y + dy)),
+                    pt(p(c.x + dx, c.y + dy))
+                ),
+            ];
+            (Some(ellipse), chords)
+        }
+        Shape::Egg => (
+            Some(Outline::Polygon(polygon(c, w, h, 40, 0.0, -0.3, 0.0))),
+            Vec::new(),
+        ),
+        Shape::Box
+        | Shape::Rect
+        | Shape::Rectangle
+        | Shape::Square
+        | Shape::Record
+        | Shape::MRecord => (Some(Outline::Polygon(rect)), Vec::new()),
+        Shape::Polygon => {
+            let sides = number(attributes, "sides").unwrap_or(4.0) as usize;
+            let skew = number(attributes, "skew").unwrap_or(0.0);
+            let distortion = number(attributes, "distortion").unwrap_or(0.0);
+            let orientation = number(attributes, "orientation").unwrap_or(0.0);
+            let points = polygon(c, w, h, sides, skew, distortion, orientation);
+            (Some(Outline::Polygon(points)), Vec::new())
+        }
+        Shape::Triangle => (Some(Outline::Polygon(regular(3))), Vec::new()),
+        Shape::InvTriangle => (
+            Some(Outline::Polygon(polygon(c, w, h, 3, 0.0, 0.0, 180.0))),
+            Vec::new(),
+        ),
+        Shape::Diamond | Shape::MDiamond => {
+            let points = polygon(c, w, h, 4, 0.0, 0.0, 45.0);
+            let decorations = match shape {
+                Shape::MDiamond => diagonals(&points),
+                _ => Vec::new(),
+            };
+            (Some(Outline::Polygon(points)), decorations)
+        }
+        Shape::MSquare => {
+            let decorations = diagonals(&rect);
+            (Some(Outline::Polygon(rect)), decorations)
+        }
+        Shape::Trapezium => (
+            Some(Outline::Polygon(polygon(c, w, h, 4, 0.0, -0.4, 0.0))),
+            Vec::new(),
+        ),
+        Shape::InvTrapezium => (
+            Some(Outline::Polygon(polygon(c, w, h, 4, 0.0, -0.4, 180.0))),
+            Vec::new(),
+        ),
+        Shape::Parallelogram => (
+            Some(Outline::Polygon(polygon(c, w, h, 4, 0.6, 0.0, 0.0))),
+            Vec::new(),
+        ),
+        Shape::House => (
+            Some(Outline::Polygon(polygon(c, w, h, 5, 0.0, -0.64, 0.0))),
+            Vec::new(),
+        ),
+        Shape::InvHouse => (
+            Some(Outline::Polygon(polygon(c, w, h, 5, 0.0, -0.64, 180.0))),
+            Vec::new(),
+        ),
+        Shape::Pentagon => (Some(Outline::Polygon(regular(5))), Vec::new()),
+        Shape::Hexagon => (Some(Outline::Polygon(regular(6))), Vec::new()),
+        Shape::Septagon => (Some(Outline::Polygon(regular(7))), Vec::new()),
+        Shape::Octagon | Shape::DoubleOctagon | Shape::TripleOctagon => {
+            (Some(Outline::Polygon(regular(8))), Vec::new())
+        }
+        Shape::Star => (Some(Outline::Polygon(star(c, w, h))), Vec::new()),
+        Shape::PlainText | Shape::Plain | Shape::None => (None, Vec::new()),
+        Shape::Underline => (None, vec![format!("M{}L{}", pt(p(x0, y1)), pt(p(x1, y1)))]),
+        Shape::Cylinder => {
+            let ry = (h * 0.1).min(w / 4.0);
+            let rx = w / 2.0;
+            let arc =
+                |to: Point, sweep: u8| format!("A{} {} 0 0 {} {}", num(rx), num(ry), sweep, pt(to));
+            let outline = format!(
+                "M{}{}L{}{}Z",
+                pt(p(x0, y0 + ry)),
+                arc(p(x1, y0 + ry), 1),
+                pt(p(x1, y1 - ry)),
+                arc(p(x0, y1 - ry), 1)
+            );
+            let rim = format!("M{}{}", pt(p(x0, y0 + ry)), arc(p(x1, y0 + ry), 0));
+            (Some(Outline::Path(outline)), vec![rim])
+        }
+        Shape::Note => {
+            let fold = 6.0_f64.min(w / 4.0).min(h / 4.0);
+            let outline = vec![
+                p(x0, y0),
+                p(x1 - fold, y0),
+                p(x1, y0 + fold),
+                p(x1, y1),
+                p(x0, y1),
+            ];
+            let corner = format!(
+                "M{}L{}L{}",
+                pt(p(x1 - fold, y0)),
+                pt(p(x1 - fold, y0 + fold)),
+                pt(p(x1, y0 + fold))
+            );
+            (Some(Outline::Polygon(outline)), vec![corner])
+        }
+        Shape::Tab => {
+            let tab = format!(
+                "M{}L{}L{}L{}",
+                pt(p(x0 + 3.0, y0)),
+                pt(p(x0 + 3.0, y0 + 4.0)),
+                pt(p(x0 + 11.0, y0 + 4.0)),
+                pt(p(x0 + 11.0, y0))
+            );
+            (Some(Outline::Polygon(rect)), vec![tab])
+        }
+        Shape::Folder => {
+            let t = 4.0;
+            let outline = vec![
+                p(x0, y1),
+                p(x0, y0 + t),
+                p(x1 - 22.0, y0 + t),
+                p(x1 - 18.0, y0),
+                p(x1 - 6.0, y0),
+                p(x1 - 2.0, y0 + t),
+                p(x1, y0 + t),
+                p(x1, y1),
+            ];
+            (Some(Outline::Polygon(outline)), Vec::new())
+        }
+        Shape::Box3D => {
+            let d = 4.0;
+            let outline = vec![
+                p(x0, y0 + d),
+                p(x0 + d, y0),
+                p(x1, y0),
+                p(x1, y1 - d),
+                p(x1 - d, y1),
+                p(x0, y1),
+            ];
+            let edges = format!(
+                "M{}L{}L{}M{}L{}",
+                pt(p(x0, y0 + d)),
+                pt(p(x1 - d, y0 + d)),
+                pt(p(x1 - d, y1)),
+                pt(p(x1 - d, y0 + d)),
+                pt(p(x1, y0))
+            );
+            (Some(Outline::Polygon(outline)), vec![edges])
+        }
+        Shape::Component => {
+            let tab = |y: f64| {
+                format!(
+                    "M{}L{}L{}L{}Z",
+                    pt(p(x0 - 4.0, y - 3.0)),
+                    pt(p(x0 + 4.0, y - 3.0)),
+                    pt(p(x0 + 4.0, y + 3.0)),
+                    pt(p(x0 - 4.0, y + 3.0))
+                )
+            };
+            let tabs = vec![tab(y0 + h * 0.25), tab(y0 + h * 0.75)];
+            (Some(Outline::Polygon(rect)), tabs)
+        }
+        Shape::Promoter => glyph("M-1 1L-1 -0.6L0.8 -0.6M0.4 -1L0.8 -0.6L0.4 -0.2", false),
+        Shape::LPromoter => glyph("M-1 1L-1 -0.6L0.8 -0.6M0.4 -1L0.8 -0.6L0.4 -0.2", true),
+        Shape::RPromoter => glyph(
+            "M-1 1L-1 -0.6L0.8 -0.6M0.4 -1L0.8 -0.6L0.4 -0.2M-1.4 1L1.4 1",
+            false,
+        ),
+        Shape::Cds => glyph("M-1 -0.6L0.4 -0.6L1 0L0.4 0.6L-1 0.6Z", false),
+        Shape::Terminator => glyph("M0 1L0 -0.8M-0.8 -0.8L0.8 -0.8", false),
+        Shape::Utr => glyph(
+            "M-0.8 0.6L-0.8 -0.2L-0.4 -0.6L0.4 -0.6L0.8 -0.2L0.8 0.6",
+            false,
+        ),
+        Shape::PrimerSite => glyph("M-1 0L1 0L0.5 -0.5", false),
+        Shape::RestrictionSite => glyph("M0 -1L0 1M-0.6 -0.2L0.6 0.2", false),
+        Shape::FivePOverhang => glyph("M-1 -0.3L0.4 -0.3M-0.4 0.3L1 0.3", false),
+        Shape::ThreePOverhang => glyph("M-1 -0.3L0.4 -0.3M-0.4 0.3L1 0.3", true),
+        Shape::NOverhang => glyph("M-1 -0.3L1 -0.3M-0.5 0.3L0.5 0.3", false),
+        Shape::Assembly => glyph(
+            "M-1 -0.3L1 -0.3M-1 0.3L1 0.3M-1 -0.3L-1 0.3M1 -0.3L1 0.3",
+            false,
+        ),
+        Shape::Signature => glyph("M-1 -0.6L-0.4 0M-1 0L-0.4 -0.6M-0.2 0L1 0", false),
+        Shape::Insulator => glyph("M-0.5 -0.5L0.5 -0.5L0.5 0.5L-0.5 0.5Z", false),
+        Shape::RiboSite => glyph("M0 1L0 0M-0.5 0L-0.4 -0.4L0 -0.6L0.4 -0.4L0.5 0", false),
+        Shape::RnaStab => glyph("M0 1L0 0M0 0L-0.4 -0.4L0 -0.8L0.4 -0.4Z", false),
+        Shape::ProteaseSite => glyph("M0 1L0 0M-0.4 -0.8L0.4 0M-0.4 0L0.4 -0.8", false),
+        Shape::ProteinStab => glyph("M0 1L0 0M-0.4 0L-0.4 -0.8L0.4 -0.8L0.4 0Z", false),
+        Shape::RArrow => glyph("M-1 0L1 0M0.5 -0.5L1 0L0.5 0.5", false),
+        Shape::LArrow => glyph("M-1 0L1 0M0.5 -0.5L1 0L0.5 0.5", true),
+    }
+}
+
+// A path given as M, L and Z commands in a y-down unit space around the
+// node's center, scaled to `size` and optionally mirrored left to right
+fn glyph(c: Point, size: f64, mirror: bool, spec: &str) -> String {
+    let flip = if mirror { -1.0 } else { 1.0 };
+    let mut d = String::new();
+    for command in spec.split_inclusive(['M', 'L', 'Z']) {
+        let (coordinates, next) = command.split_at(command.len() - 1);
+        let numbers: Vec<f64> = coordinates
+            .split_whitespace()
+            .filter_map(|n| n.parse().ok())
+            .collect();
+        if let [x, y] = numbers[..] {
+            d.push_str(&pt(Point::new(c.x + x * size * flip, c.y + y * size)));
+        }
+        d.push_str(next);
+    }
+    d
+}
+
+// A short line across every corner
+fn diagonals(points: &[Point]) -> Vec<String> {
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let v = points[i];
+            let towards = |other: Point| {
+                let distance = v.distance(other).max(1e-9);
+                let t = (CORNER_RADIUS / distance).min(0.3);
+                Point::new(v.x + (other.x - v.x) * t, v.y + (other.y - v.y) * t)
+            };
+            let (a, b) = (
+                towards(points[(i + n - 1) % n]),
+                towards(points[(i + 1) % n]),
+            );
+            format!("M{}L{}", pt(a), pt(b))
+        })
+        .collect()
+}
+
+// The polygon with every corner rounded off
+fn rounded_path(points: &[Point]) -> String {
+    let n = points.len();
+    let towards = |v: Point, other: Point| {
+        let distance = v.distance(other).max(1e-9);
+        let t = (CORNER_RADIUS / distance).min(1.0 / 3.0);
+        Point::new(v.x + (other.x - v.x) * t, v.y + (other.y - v.y) * t)
+    };
+    let mut d = format!("M{}", pt(towards(points[0], points[1 % n])));
+    for i in 1..=n {
+        let v = points[i % n];
+        d.push_str(&format!(
+            "L{}Q{} {}",
+            pt(towards(v, points[i - 1])),
+            pt(v),
+            pt(towards(v, points[(i + 1) % n]))
+        ));
+    }
+    d.push('Z');
+    d
+}
+
+// The outline `gap` further inside, for extra peripheries
+fn shrink(outline: &Outline, c: Point, w: f64, h: f64, gap: f64) -> Option<Outline> {
+    if w <= 2.0 * gap || h <= 2.0 * gap {
+        return None;
+    }
+    let (sx, sy) = ((w - 2.0 * gap) / w, (h - 2.0 * gap) / h);
+    match outline {
+        Outline::Ellipse(c, rx, ry) => Some(Outline::Ellipse(*c, rx - gap, ry - gap)),
+        Outline::Polygon(points) => Some(Outline::Polygon(
+            points
+                .iter()
+                .map(|p| Point::new(c.x + (p.x - c.x) * sx, c.y + (p.y - c.y) * sy))
+                .collect(),
+        )),
+        Outline::Path(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::directed;
+    use crate::parser::parse_graph;
+
+    fn svg(dot_source: &str) -> String {
+        to_svg(&parse_graph(dot_source).unwrap())
+    }
+
+    #[test]
+    fn document() {
+        let s = svg(r##"digraph G {
+                subgraph cluster_a { label="A & B"; style=filled; fillcolor=lightblue; a; b }
+                a -> b [label="x<y"];
+                b -> c [color="#ff000080", style=dashed];
+                c [style=invis];
+            }"##);
+        assert!(s.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n<svg"));
+        assert!(s.ends_with("</g>\n</svg>\n"));
+        assert_eq!(s.matches("<g ").count(), s.matches("</g>").count());
+        assert!(s.contains("<title>G</title>"));
+        assert!(s.contains("<g id=\"clust1\" class=\"cluster\">\n<title>cluster_a</title>"));
+        assert!(s.contains("fill=\"#add8e6\""));
+        assert!(s.contains(">A &amp; B</text>"));
+        assert!(s.contains(">x&lt;y</text>"));
+        assert!(s.contains("<title>a-&gt;b</title>"));
+        assert!(s.contains("stroke=\"#ff0000\" stroke-opacity=\"0.5\" stroke-dasharray=\"5,2\""));
+        // The invisible node is left out, its edge is not
+        assert!(!s.contains("<title>c</title>"));
+        assert!(s.contains("<title>b-&gt;c</title>"));
+    }
+
+    #[test]
+    fn shapes_and_styles() {
+        let shapes = [
+            "box",
+            "polygon",
+            "ellipse",
+            "oval",
+            "circle",
+            "point",
+            "egg",
+            "triangle",
+            "plaintext",
+            "plain",
+            "diamond",
+            "trapezium",
+            "parallelogram",
+            "house",
+            "pentagon",
+            "hexagon",
+            "septagon",
+            "octagon",
+            "doublecircle",
+            "doubleoctagon",
+            "tripleoctagon",
+            "invtriangle",
+            "invtrapezium",
+            "invhouse",
+            "Mdiamond",
+            "Msquare",
+            "Mcircle",
+            "rect",
+            "rectangle",
+            "square",
+            "star",
+            "none",
+            "underline",
+            "cylinder",
+            "note",
+            "tab",
+            "folder",
+            "box3d",
+            "component",
+            "promoter",
+            "cds",
+            "terminator",
+            "utr",
+            "primersite",
+            "restrictionsite",
+            "fivepoverhang",
+            "threepoverhang",
+            "noverhang",
+            "assembly",
+            "signature",
+            "insulator",
+            "ribosite",
+            "rnastab",
+            "proteasesite",
+            "proteinstab",
+            "rpromoter",
+            "rarrow",
+            "larrow",
+            "lpromoter",
+            "record",
+            "Mrecord",
+        ];
+        let nodes: Vec<String> = shapes
+            .iter()
+            .map(|shape| format!("\"{0}\" [shape={0}];", shape))
+            .collect();
+        let s = svg(&format!("digraph {{ {} }}", nodes.join(" ")));
+        for shape in &shapes {
+            assert!(
+                s.contains(&format!("<title>{}</title>", shape)),
+                "{}",
+                shape
+            );
+        }
+        assert_eq!(s.matches("<g ").count(), s.matches("</g>").count());
+
+        let s = svg(r#"digraph {
+                a [shape=box, style="filled,rounded", color=red, penwidth=2];
+                b [style=bold]; c [shape=box, style=diagonals]; d [style=dotted];
+                e [shape=box, style=striped, fillcolor="red;0.25:blue"];
+                f [style=wedged, fillcolor="red:green:blue"];
+                g [shape=record, label="<p> one|{two|three}|four"];
+            }"#);
+        assert!(s.contains("<path fill=\"#ff0000\" stroke=\"#ff0000\" stroke-width=\"2\" d=\"M"));
+        assert!(s.contains("stroke-width=\"2\""));
+        assert!(s.contains("stroke-dasharray=\"1,5\""));
+        assert!(s.contains("<rect") && s.contains("fill=\"#0000ff\" stroke=\"none\""));
+        assert!(s.contains(">one</text>") && s.contains(">two | three</text>"));
+    }
+
+    #[test]
+    fn arrowheads() {
+        let s = svg(r#"digraph { a -> b [arrowhead=odiamond, dir=both, arrowtail=tee] }"#);
+        assert_eq!(s.matches("<polygon fill=\"none\"").count(), 1);
+        assert_eq!(s.matches("<polygon fill=\"black\"").count(), 1);
+
+        // Undirected edges have no arrowheads
+        let s = svg("graph { a -- b }");
+        assert!(!s.contains("<polygon fill=\"black\""));
+
+        let mut builder = directed().dot().svg();
+        builder.edge_("a", "b");
+        let s = builder.build_svg();
+        assert!(s.contains("<title>a-&gt;b</title>"));
+    }
+}
@@ -0,0 +1,684 @@
+use std::io::{self, Write};
+
+use crate::ast::{Attribute, Graph};
+use crate::attributes::{Dir, RankDir, Shape, Style};
+use crate::layout::{layout, Layout, Point};
+use crate::model::{find, number, record_text, styles, Model};
+
+// Draws a laid out graph on a grid of characters for terminals. Points are
+// turned into columns and rows so that nodes keep the places the native
+// layout gave them, nodes become boxes around their labels, edges become
+// lines of box-drawing characters ending in arrowheads, and clusters become
+// frames with their label in the top border. A drawing wider than the
+// terminal is replaced by a listing of each node's neighbours.
+
+// Points per column and per row, about a character at the default font size
+const COLUMN: f64 = 7.0;
+const ROW: f64 = 12.0;
+
+// Taller drawings are no easier to read than the listing
+const MAX_ROWS: f64 = 10_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    // Only printable ASCII characters
+    Ascii,
+    // Box-drawing characters and arrows
+    Unicode,
+}
+
+// The lines of a frame: corners clockwise from the top left, then the top
+// and bottom, and the left and right sides
+struct Border {
+    corners: [char; 4],
+    horizontal: char,
+    sides: [char; 2],
+}
+
+impl Border {
+    const fn new(corners: [char; 4], horizontal: char, sides: [char; 2]) -> Border {
+        Border {
+            corners,
+            horizontal,
+            sides,
+        }
+    }
+}
+
+const ASCII_BOX: Border = Border::new(['+', '+', '+', '+'], '-', ['|', '|']);
+const ASCII_ROUND: Border = Border::new(['.', '.', '\'', '\''], '-', ['(', ')']);
+const ASCII_DOUBLE: Border = Border::new(['#', '#', '#', '#'], '=', ['#', '#']);
+const ASCII_ANGLED: Border = Border::new(['/', '\\', '/', '\\'], '-', ['<', '>']);
+const ASCII_DASHED: Border = Border::new(['+', '+', '+', '+'], '.', [':', ':']);
+const UNICODE_BOX: Border = Border::new(['┌', '┐', '┘', '└'], '─', ['│', '│']);
+const UNICODE_ROUND: Border = Border::new(['╭', '╮', '╯', '╰'], '─', ['│', '│']);
+const UNICODE_DOUBLE: Border = Border::new(['╔', '╗', '╝', '╚'], '═', ['║', '║']);
+const UNICODE_ANGLED: Border = Border::new(['╱', '╲', '╱', '╲'], '─', ['❬', '❭']);
+const UNICODE_BOLD: Border = Border::new(['┏', '┓', '┛', '┗'], '━', ['┃', '┃']);
+const UNICODE_DASHED: Border = Border::new(['┌', '┐', '┘', '└'], '┄', ['┆', '┆']);
+const UNICODE_DOTTED: Border = Border::new(['┌', '┐', '┘', '└'], '┈', ['┊', '┊']);
+
+// A node's box in columns and rows, borders included
+#[derive(Clone, Copy)]
+struct Area {
+    left: i64,
+    top: i64,
+    right: i64,
+    bottom: i64,
+}
+
+impl Area {
+    fn contains(&self, (x, y): (i64, i64)) -> bool {
+        self.left <= x && x <= self.right && self.top <= y && y <= self.bottom
+    }
+}
+
+// Draws the graph if it fits in `width` columns and lists it otherwise
+pub fn print_text<W: Write>(
+    writer: &mut W,
+    graph: &Graph,
+    charset: Charset,
+    width: usize,
+) -> io::Result<()> {
+    let model = Model::new(graph);
+    let layout = layout(graph);
+    // Sizes that do not fit are never drawn, so cells stay small numbers
+    let (columns, rows) = (layout.width / COLUMN, layout.height / ROW);
+    if !(columns <= width as f64 && rows <= MAX_ROWS) {
+        return adjacency(writer, &model);
+    }
+    let canvas = Canvas::draw(&model, &layout, charset);
+    if canvas.width() <= width {
+        for line in canvas.lines() {
+            writeln!(writer, "{}", line)?;
+        }
+        Ok(())
+    } else {
+        adjacency(writer, &model)
+    }
+}
+
+pub fn to_text(graph: &Graph, charset: Charset, width: usize) -> String {
+    let mut writer = Vec::new();
+    print_text(&mut writer, graph, charset, width).unwrap();
+    String::from_utf8(writer).unwrap()
+}
+
+// Every node once, followed by the nodes its edges lead to
+fn adjacency<W: Write>(writer: &mut W, model: &Model) -> io::Result<()> {
+    let op = if model.directed { "->" } else { "--" };
+    for (i, node) in model.nodes.iter().enumerate() {
+        let targets: Vec<String> = model
+            .edges
+            .iter()
+            .enumerate()
+            .filter(|(_, edge)| edge.tail == i)
+            .map(|(e, edge)| {
+                let head = model.nodes[edge.head].id.get();
+                match model.edge_label(e) {
+                    Some(label) => format!("{} ({})", head, label.replace('\n', " ")),
+                    None => head.to_string(),
+                }
+            })
+            .collect();
+        if targets.is_empty() {
+            writeln!(writer, "{}", node.id.get())?;
+        } else {
+            writeln!(writer, "{} {} {}", node.id.get(), op, targets.join(", "))?;
+        }
+    }
+    Ok(())
+}
+
+struct Canvas {
+    cells: Vec<Vec<char>>,
+    charset: Charset,
+}
+
+impl Canvas {
+    fn draw(model: &Model, layout: &Layout, charset: Charset) -> Canvas {
+        let mut canvas = Canvas {
+            cells: Vec::new(),
+            charset,
+        };
+        let areas: Vec<Option<Area>> = (0..model.nodes.len())
+            .map(|i| {
+                let invisible = styles(&model.nodes[i].attributes).contains(&Style::Invisible);
+                match layout.nodes.get(i) {
+                    Some(node) if !invisible => Some(node_area(model, i, node.center)),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        for cluster in &layout.clusters {
+            let subgraph = model
+                .clusters()
+                .find(|&s| model.subgraphs[s].id.as_ref() == Some(&cluster.id));
+            if let Some(subgraph) = subgraph {
+                canvas.cluster(model, subgraph, cluster.min, cluster.max);
+            }
+        }
+        let rankdir = match find(&model.attributes, "rankdir") {
+            Some(Attribute::RankDir(rankdir)) => rankdir.clone(),
+            _ => RankDir::TopBottom,
+        };
+        for (i, edge) in layout.edges.iter().enumerate().take(model.edges.len()) {
+            let model_edge = &model.edges[i];
+            if styles(&model_edge.attributes).contains(&Style::Invisible) {
+                continue;
+            }
+            let dir = match find(&model_edge.attributes, "dir") {
+                Some(Attribute::Dir(dir)) => dir.clone(),
+                _ if model.directed => Dir::Forward,
+                _ => Dir::None,
+            };
+            let ends = (areas[model_edge.tail], areas[model_edge.head]);
+            canvas.edge(&edge.points, ends, &dir, &rankdir);
+            if let (Some(text), Some(label)) = (model.edge_label(i), edge.label) {
+                canvas.label(cell(label), &text);
+            }
+        }
+        for (i, area) in areas.iter().enumerate() {
+            if let Some(area) = area {
+                canvas.node(model, i, *area);
+            }
+        }
+        canvas
+    }
+
+    fn width(&self) -> usize {
+        self.lines()
+            .iter()
+            .map(|l| l.chars().count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    // The rows without the blank margin the layout leaves around the drawing
+    fn lines(&self) -> Vec<String> {
+        let rows: Vec<String> = self
+            .cells
+            .iter()
+            .map(|row| row.iter().collect::<String>().trim_end().to_string())
+            .skip_while(|row| row.is_empty())
+            .collect();
+        let margin = rows
+            .iter()
+            .filter(|row| !row.is_empty())
+            .map(|row| row.chars().take_while(|&c| c == ' ').count())
+            .min()
+            .unwrap_or(0);
+        let mut rows: Vec<String> = rows
+            .iter()
+            .map(|row| row.chars().skip(margin).collect())
+            .collect();
+        while rows.last().is_some_and(String::is_empty) {
+            rows.pop();
+        }
+        rows
+    }
+
+    fn get(&self, (x, y): (i64, i64)) -> char {
+        if x < 0 || y < 0 {
+            return ' ';
+        }
+        self.cells
+            .get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .copied()
+            .unwrap_or(' ')
+    }
+
+    fn set(&mut self, (x, y): (i64, i64), c: char) {
+        if x < 0 || y < 0 {
+            return;
+        }
+        let (x, y) = (x as usize, y as usize);
+        if self.cells.len() <= y {
+            self.cells.resize(y + 1, Vec::new());
+        }
+        let row = &mut self.cells[y];
+        if row.len() <= x {
+            row.resize(x + 1, ' ');
+        }
+        row[x] = c;
+    }
+
+    fn text(&mut self, (x, y): (i64, i64), text: &str) {
+        for (k, c) in text.chars().enumerate() {
+            self.set((x + k as i64, y), c);
+        }
+    }
+
+    // Text centered on a cell, or moved sideways to the nearest place where
+    // it does not cover anything drawn before
+    fn label(&mut self, (x, y): (i64, i64), text: &str) {
+        let lines: Vec<&str> = text.split('\n').collect();
+        let top = y - (lines.len() as i64 - 1) / 2;
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as i64;
+        let left = x - width / 2;
+        let free = |shift: i64| {
+            (0..lines.len() as i64)
+                .all(|k| (-1..=width).all(|j| self.get((left + shift + j, top + k)) == ' '))
+        };
+        let shift = (0..=width + 2)
+            .flat_map(|d| [d, -d])
+            .find(|&d| free(d))
+            .unwrap_or(0);
+        for (k, line) in lines.iter().enumerate() {
+            self.text((left + shift, top + k as i64), line);
+        }
+    }
+
+    fn frame(&mut self, area: Area, border: &Border) {
+        let Area {
+            left,
+            top,
+            right,
+            bottom,
+        } = area;
+        for x in left + 1..right {
+            self.set((x, top), border.horizontal);
+            self.set((x, bottom), border.horizontal);
+        }
+        for y in top + 1..bottom {
+            self.set((left, y), border.sides[0]);
+            self.set((right, y), border.sides[1]);
+        }
+        self.set((left, top), border.corners[0]);
+        self.set((right, top), border.corners[1]);
+        self.set((right, bottom), border.corners[2]);
+        self.set((left, bottom), border.corners[3]);
+    }
+
+    fn cluster(&mut self, model: &Model, subgraph: usize, min: Point, max: Point) {
+        let attributes = &model.subgraphs[subgraph].attributes;
+        let styles = styles(attributes);
+        if styles.contains(&Style::Invisible) {
+            return;
+        }
+        let (left, top) = cell(min);
+        let (right, bottom) = cell(max);
+        let border = self.border(&styles, styles.contains(&Style::Rounded));
+        self.frame(
+            Area {
+                left,
+                top,
+                right,
+                bottom,
+            },
+            border,
+        );
+        if let Some(label) = model.subgraph_label(subgraph) {
+            let label: String = label.replace('\n', " ");
+            let room = (right - left - 3).max(0) as usize;
+            let label: String = label.chars().take(room).collect();
+            if !label.is_empty() {
+                self.text((left + 2, top), &format!(" {} ", label));
+            }
+        }
+    }
+
+    // The frame for a shape and its styles
+    fn border(&self, styles: &[Style], rounded: bool) -> &'static Border {
+        let unicode = self.charset == Charset::Unicode;
+        if styles.contains(&Style::Dashed) {
+            return if unicode {
+                &UNICODE_DASHED
+            } else {
+                &ASCII_DASHED
+            };
+        }
+        if styles.contains(&Style::Dotted) {
+            return if unicode {
+                &UNICODE_DOTTED
+            } else {
+                &ASCII_DASHED
+            };
+        }
+        if styles.contains(&Style::Bold) && !rounded {
+            return if unicode {
+                &UNICODE_BOLD
+            } else {
+                &ASCII_DOUBLE
+            };
+        }
+        match (rounded, unicode) {
+            (true, true) => &UNICODE_ROUND,
+            (true, false) => &ASCII_ROUND,
+            (false, true) => &UNICODE_BOX,
+            (false, false) => &ASCII_BOX,
+        }
+    }
+
+    fn node(&mut self, model: &Model, i: usize, area: Area) {
+        let attributes = &model.nodes[i].attributes;
+        let styles = styles(attributes);
+        let shape = match find(attributes, "shape") {
+            Some(Attribute::Shape(shape)) => shape.clone(),
+            _ => Shape::Ellipse,
+        };
+        let unicode = self.charset == Charset::Unicode;
+        for y in area.top..=area.bottom {
+            for x in area.left..=area.right {
+                self.set((x, y), ' ');
+            }
+        }
+        if let Shape::Point = shape {
+            let center = ((area.left + area.right) / 2, (area.top + area.bottom) / 2);
+            self.set(center, if unicode { '●' } else { '*' });
+            return;
+        }
+
+        let peripheries = number(attributes, "peripheries").map(|p| p as usize);
+        let rounded = styles.contains(&Style::Rounded);
+        match shape {
+            Shape::PlainText | Shape::Plain | Shape::None => {}
+            Shape::Underline => {
+                let line = if unicode { '─' } else { '_' };
+                for x in area.left..=area.right {
+                    self.set((x, area.bottom), line);
+                }
+            }
+            _ if peripheries == Some(0) => {}
+            Shape::DoubleCircle | Shape::DoubleOctagon | Shape::TripleOctagon => self.frame(
+                area,
+                if unicode {
+                    &UNICODE_DOUBLE
+                } else {
+                    &ASCII_DOUBLE
+                },
+            ),
+            _ if peripheries.is_some_and(|p| p > 1) => self.frame(
+                area,
+                if unicode {
+                    &UNICODE_DOUBLE
+                } else {
+                    &ASCII_DOUBLE
+                },
+            ),
+            Shape::Ellipse
+            | Shape::Oval
+            | Shape::Circle
+            | Shape::Egg
+            | Shape::MCircle
+            | Shape::MRecord
+            | Shape::Cylinder => {
+                let border = self.border(&styles, true);
+                self.frame(area, border)
+            }
+            Shape::Diamond
+            | Shape::MDiamond
+            | Shape::Triangle
+            | Shape::InvTriangle
+            | Shape::Pentagon
+            | Shape::Hexagon
+            | Shape::Septagon
+            | Shape::Octagon
+            | Shape::House
+            | Shape::InvHouse
+            | Shape::Star
+            | Shape::RArrow
+            | Shape::LArrow => self.frame(
+                area,
+                if unicode {
+                    &UNICODE_ANGLED
+                } else {
+                    &ASCII_ANGLED
+                },
+            ),
+            _ => {
+                let border = self.border(&styles, rounded);
+                self.frame(area, border)
+            }
+        }
+
+        let label = node_text(model, i, &shape);
+        let lines: Vec<&str> = label.split('\n').collect();
+        let middle = (area.left + area.right) / 2;
+        let top = (area.top + area.bottom) / 2 - (lines.len() as i64 - 1) / 2;
+        for (k, line) in lines.iter().enumerate() {
+            let room = (area.right - area.left - 1).max(0) as usize;
+            let line: String = line.chars().take(room).collect();
+            let left = middle - (line.chars().count() as i64 - 1) / 2;
+            self.text((left, top + k as i64), &line);
+        }
+    }
+
+    // A line through the cells the edge crosses outside its nodes, with
+    // arrowheads where the edge leaves and enters them
+    fn edge(
+        &mut self,
+        points: &[Point],
+        (tail, head): (Option<Area>, Option<Area>),
+        dir: &Dir,
+        rankdir: &RankDir,
+    ) {
+        let mut cells: Vec<(i64, i64)> = Vec::new();
+        for pair in points.windows(2) {
+            for c in raster(cell(pair[0]), cell(pair[1])) {
+                if cells.last() != Some(&c) {
+                    cells.push(c);
+                }
+            }
+        }
+        let outside = |c: &(i64, i64)| {
+            !tail.is_some_and(|a| a.contains(*c)) && !head.is_some_and(|a| a.contains(*c))
+        };
+        // The edge leaves the tail and enters the head once, cells inside
+        // them in between belong to the line, e.g. for self loops
+        let first = cells.iter().position(outside);
+        let last = cells.iter().rposition(outside);
+        let (first, last) = match (first, last) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return,
+        };
+        let step = |from: usize, to: usize| {
+            let (a, b) = (cells[from], cells[to]);
+            (b.0 - a.0, b.1 - a.1)
+        };
+        let before = |k: usize| k.saturating_sub(1);
+        let after = |k: usize| (k + 1).min(cells.len() - 1);
+        for (k, &at) in cells.iter().enumerate().take(last + 1).skip(first) {
+            let c = self.line(step(before(k), after(k)));
+            let existing = self.get(at);
+            let c = match existing {
+                ' ' => c,
+                _ if existing == c => c,
+                _ if self.is_line(existing) => self.crossing(),
+                _ => existing,
+            };
+            self.set(at, c);
+        }
+        // Arrowheads point into the node the edge reaches
+        if matches!(dir, Dir::Forward | Dir::Both) {
+            let arrow = self.arrow(step(before(last).max(first), after(last)), rankdir);
+            self.set(cells[last], arrow);
+        }
+        if matches!(dir, Dir::Back | Dir::Both) {
+            let arrow = self.arrow(step(after(first).min(last), before(first)), rankdir);
+            self.set(cells[first], arrow);
+        }
+    }
+
+    fn line(&self, (dx, dy): (i64, i64)) -> char {
+        let unicode = self.charset == Charset::Unicode;
+        match (dx.signum(), dy.signum(), unicode) {
+            (_, 0, true) => '─',
+            (_, 0, false) => '-',
+            (0, _, true) => '│',
+            (0, _, false) => '|',
+            (x, y, true) if x == y => '╲',
+            (x, y, false) if x == y => '\\',
+            (_, _, true) => '╱',
+            (_, _, false) => '/',
+        }
+    }
+
+    fn is_line(&self, c: char) -> bool {
+        "─│╲╱┼-|\\/+".contains(c)
+    }
+
+    fn crossing(&self) -> char {
+        match self.charset {
+            Charset::Unicode => '┼',
+            Charset::Ascii => '+',
+        }
+    }
+
+    // Arrowheads follow the last step of the line. A diagonal step points
+    // along the ranks, so that arrows read in the direction of `rankdir`.
+    fn arrow(&self, (dx, dy): (i64, i64), rankdir: &RankDir) -> char {
+        let vertical = match (dx, dy) {
+            (0, _) => true,
+            (_, 0) => false,
+            _ => matches!(rankdir, RankDir::TopBottom | RankDir::BottomTop),
+        };
+        let arrows = match self.charset {
+            Charset::Unicode => ['▼', '▲', '▶', '◀'],
+            Charset::Ascii => ['v', '^', '>', '<'],
+        };
+        match (vertical, dy > 0, dx > 0) {
+            (true, true, _) => arrows[0],
+            (true, false, _) => arrows[1],
+            (false, _, true) => arrows[2],
+            (false, _, false) => arrows[3],
+        }
+    }
+}
+
+// Clamped well inside i64 so that the arithmetic on cells cannot overflow
+fn cell(p: Point) -> (i64, i64) {
+    let limit = i32::MAX as f64;
+    let round = |v: f64| v.round().clamp(-limit, limit) as i64;
+    (round(p.x / COLUMN), round(p.y / ROW))
+}
+
+// The cells of a straight line, both ends included
+fn raster(from: (i64, i64), to: (i64, i64)) -> Vec<(i64, i64)> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let steps = dx.abs().max(dy.abs());
+    if steps == 0 {
+        return vec![from];
+    }
+    (0..=steps)
+        .map(|k| {
+            let t = k as f64 / steps as f64;
+            (
+                from.0 + (dx as f64 * t).round() as i64,
+                from.1 + (dy as f64 * t).round() as i64,
+            )
+        })
+        .collect()
+}
+
+fn node_text(model: &Model, node: usize, shape: &Shape) -> String {
+    let label = model.node_label(node);
+    match shape {
+        Shape::Record | Shape::MRecord => record_text(&label),
+        _ => label,
+    }
+}
+
+// A box around the label, centered where the layout put the node
+fn node_area(model: &Model, node: usize, center: Point) -> Area {
+    let shape = match find(&model.nodes[node].attributes, "shape") {
+        Some(Attribute::Shape(shape)) => shape.clone(),
+        _ => Shape::Ellipse,
+    };
+    let (x, y) = cell(center);
+    if let Shape::Point = shape {
+        return Area {
+            left: x,
+            top: y,
+            right: x,
+            bottom: y,
+        };
+    }
+    let text = node_text(model, node, &shape);
+    let lines = text.split('\n');
+    let width = lines.clone().map(|l| l.chars().count()).max().unwrap_or(0) as i64;
+    let height = lines.count() as i64;
+    let (width, height) = (width + 4, height + 2);
+    Area {
+        left: x - (width - 1) / 2,
+        top: y - (height - 1) / 2,
+        right: x - (width - 1) / 2 + width - 1,
+        bottom: y - (height - 1) / 2 + height - 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_graph;
+
+    fn text(dot_source: &str, charset: Charset, width: usize) -> String {
+        to_text(&parse_graph(dot_source).unwrap(), charset, width)
+    }
+
+    #[test]
+    fn boxes_and_arrows() {
+        let s = text("digraph { a -> b }", Charset::Unicode, 80);
+        let lines: Vec<&str> = s.lines().collect();
+        let a = lines.iter().position(|l| l.contains("│ a │")).unwrap();
+        let b = lines.iter().position(|l| l.contains("│ b │")).unwrap();
+        assert!(a < b);
+        assert!(lines[a - 1].contains("╭───╮"));
+        assert_eq!(lines[b - 2].trim(), "▼");
+
+        let s = text(
+            "digraph { rankdir=LR; a [shape=box]; a -> b }",
+            Charset::Unicode,
+            80,
+        );
+        assert!(s.contains("┌───┐"));
+        assert!(s.contains("─▶"));
+
+        let s = text("digraph { rankdir=BT; a -> b }", Charset::Ascii, 80);
+        assert!(s.contains("( b )") && s.contains('^'));
+        assert!(s.is_ascii());
+
+        let s = text("graph { a -- b }", Charset::Unicode, 80);
+        assert!(s.contains('│') && !s.contains('▼'));
+    }
+
+    #[test]
+    fn clusters() {
+        let s = text(
+            "digraph { subgraph cluster_x { label=Stage; a -> b } b -> c }",
+            Charset::Unicode,
+            80,
+        );
+        assert!(s.contains("┌─ Stage ─"));
+        assert_eq!(s.matches('┘').count(), 1);
+    }
+
+    #[test]
+    fn too_wide() {
+        let s = text(
+            "digraph { a -> b [label=go]; a -> c; b -> c; d }",
+            Charset::Unicode,
+            10,
+        );
+        assert_eq!(s, "a -> b (go), c\nb -> c\nc\nd\n");
+    }
+
+    #[test]
+    fn huge_nodes() {
+        for attribute in ["width=\"1e30\"", "fontsize=\"1e300\"", "height=\"1e30\""] {
+            let source = format!("digraph {{ a [{}]; a -> b }}", attribute);
+            assert_eq!(text(&source, Charset::Unicode, 80), "a -> b\nb\n");
+        }
+        let s = text("digraph { ranksep=\"1e300\"; a -> b }", Charset::Ascii, 80);
+        assert_eq!(s, "a -> b\nb\n");
+        assert_eq!(
+            cell(Point {
+                x: 1e300,
+                y: f64::NAN
+            }),
+            (i32::MAX as i64, 0)
+        );
+    }
+}
@@ -0,0 +1,324 @@
+use crate::parser::ParseError;
+
+// docs
+// * https://www.w3.org/TR/xml/
+//
+// Just enough XML to read graph exchange formats: elements, attributes,
+// text, CDATA sections and the predefined and numeric character references.
+// The prolog, comments, processing instructions and doctype are skipped.
+// Names lose their namespace prefix, so that `y:ShapeNode` is `ShapeNode`.
+
+pub(crate) struct Element {
+    pub(crate) name: String,
+    pub(crate) attributes: Vec<(String, String)>,
+    pub(crate) children: Vec<Element>,
+    // The text directly inside the element, without its children's
+    pub(crate) text: String,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+}
+
+impl Element {
+    pub(crate) fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub(crate) fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    pub(crate) fn child<'a>(&'a self, name: &'a str) -> Option<&'a Element> {
+        self.children(name).next()
+    }
+
+    pub(crate) fn error(&self, message: &str) -> ParseError {
+        ParseError::new(message, self.line, self.column)
+    }
+}
+
+// The root element of a document
+pub(crate) fn parse(source: &str) -> Result<Element, ParseError> {
+    let mut reader = Reader {
+        chars: source.chars().collect(),
+        pos: 0,
+        line: 1,
+        column: 1,
+    };
+    reader.misc()?;
+    if reader.peek() != Some('<') {
+        return Err(reader.error("expected an element"));
+    }
+    let root = reader.element()?;
+    reader.misc()?;
+    if reader.peek().is_some() {
+        return Err(reader.error("unexpected content after the root element"));
+    }
+    Ok(root)
+}
+
+struct Reader {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Reader {
+    fn error(&self, message: &str) -> ParseError {
+        ParseError::new(message, self.line, self.column)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn advance(&mut self, n: usize) {
+        for _ in 0..n {
+            self.next();
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.next();
+        }
+    }
+
+    // Skips past `end`, which must come before the end of the input
+    fn skip_past(&mut self, end: &str) -> Result<(), ParseError> {
+        while !self.starts_with(end) {
+            if self.next().is_none() {
+                return Err(self.error(&format!("expected '{}'", end)));
+            }
+        }
+        self.advance(end.chars().count());
+        Ok(())
+    }
+
+    // Whitespace, comments, processing instructions and doctypes
+    fn misc(&mut self) -> Result<(), ParseError> {
+        loop {
+            self.skip_whitespace();
+            if self.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if self.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.starts_with("<!DOCTYPE") {
+                self.doctype()?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    // A doctype, with its internal subset in brackets
+    fn doctype(&mut self) -> Result<(), ParseError> {
+        let mut depth = 0;
+        while let Some(c) = self.next() {
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                '>' if depth == 0 => return Ok(()),
+                _ => {}
+            }
+        }
+        Err(self.error("unterminated doctype"))
+    }
+
+    fn name(&mut self) -> Result<String, ParseError> {
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || "/>=".contains(c) {
+                break;
+            }
+            name.push(c);
+            self.next();
+        }
+        if name.is_empty() {
+            return Err(self.error("expected a name"));
+        }
+        // Local names only
+        Ok(match name.rsplit_once(':') {
+            Some((_, local)) => local.to_string(),
+            None => name,
+        })
+    }
+
+    fn element(&mut self) -> Result<Element, ParseError> {
+        let (line, column) = (self.line, self.column);
+        self.next();
+        let mut element = Element {
+            name: self.name()?,
+            attributes: Vec::new(),
+            children: Vec::new(),
+            text: String::new(),
+            line,
+            column,
+        };
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('/') => {
+                    self.next();
+                    if self.next() != Some('>') {
+                        return Err(self.error("expected '>'"));
+                    }
+                    return Ok(element);
+                }
+                Some('>') => {
+                    self.next();
+                    break;
+                }
+                Some(_) => {
+                    let name = self.name()?;
+                    self.skip_whitespace();
+                    if self.next() != Some('=') {
+                        return Err(self.error("expected '=' after an attribute name"));
+                    }
+                    self.skip_whitespace();
+                    let value = self.quoted()?;
+                    element.attributes.push((name, value));
+                }
+                None => return Err(self.error("unterminated start tag")),
+            }
+        }
+
+        loop {
+            if self.starts_with("</") {
+                self.advance(2);
+                let name = self.name()?;
+                if name != element.name {
+                    return Err(self.error(&format!(
+                        "expected the end tag of '{}', found '{}'",
+                        element.name, name
+                    )));
+                }
+                self.skip_whitespace();
+                if self.next() != Some('>') {
+                    return Err(self.error("expected '>'"));
+                }
+                return Ok(element);
+            } else if self.starts_with("<![CDATA[") {
+                self.advance(9);
+                while !self.starts_with("]]>") {
+                    match self.next() {
+                        Some(c) => element.text.push(c),
+                        None => return Err(self.error("unterminated CDATA section")),
+                    }
+                }
+                self.advance(3);
+            } else if self.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if self.peek() == Some('<') {
+                element.children.push(self.element()?);
+            } else if self.peek() == Some('&') {
+                element.text.push(self.reference()?);
+            } else {
+                match self.next() {
+                    Some(c) => element.text.push(c),
+                    None => {
+                        return Err(
+                            self.error(&format!("expected the end tag of '{}'", element.name))
+                        )
+                    }
+                }
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String, ParseError> {
+        let quote = match self.next() {
+            Some(c @ '"') | Some(c @ '\'') => c,
+            _ => return Err(self.error("expected a quoted attribute value")),
+        };
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some(c) if c == quote => {
+                    self.next();
+                    return Ok(value);
+                }
+                Some('&') => value.push(self.reference()?),
+                Some(c) => {
+                    value.push(c);
+                    self.next();
+                }
+                None => return Err(self.error("unterminated attribute value")),
+            }
+        }
+    }
+
+    fn reference(&mut self) -> Result<char, ParseError> {
+        let (line, column) = (self.line, self.column);
+        self.next();
+        let mut name = String::new();
+        loop {
+            match self.next() {
+                Some(';') => break,
+                Some(c) if name.len() < 10 => name.push(c),
+                _ => return Err(ParseError::new("unterminated reference", line, column)),
+            }
+        }
+        let c = match name.as_str() {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => name
+                    .strip_prefix('#')
+                    .and_then(|decimal| decimal.parse().ok())
+                    .and_then(char::from_u32),
+            },
+        };
+        c.ok_or_else(|| ParseError::new(&format!("unknown reference '&{};'", name), line, column))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elements() {
+        let root = parse(
+            "<?xml version=\"1.0\"?>\n<!-- c --><!DOCTYPE a [<!ENTITY x \"y\">]>\n\
+             <a xmlns:y='urn:y' k=\"1 &lt; 2\"><y:b/>t&#x41;&amp;<![CDATA[<c>]]><b>u</b></a>\n",
+        )
+        .unwrap();
+        assert_eq!(root.name, "a");
+        assert_eq!(root.attribute("k"), Some("1 < 2"));
+        assert_eq!(root.attribute("y"), Some("urn:y"));
+        assert_eq!(root.children("b").count(), 2);
+        assert_eq!(root.children[1].text, "u");
+        assert_eq!(root.text, "tA&<c>");
+
+        let error = parse("<a>\n  <b></a>").err().unwrap();
+        assert_eq!((error.line(), error.column()), (2, 9));
+    }
+}